@@ -1,15 +1,48 @@
-use crate::data::ProcessedMarketData;
+use super::benchmark::{Benchmark, BenchmarkComparison};
+use super::carry::CarryModel;
+use super::drawdown::{drawdown_from_peak, drawdown_periods, DrawdownPeriod};
+use super::events::{Event, EventKind};
+use super::exchange::{FillTiming, SimulatedExchange};
+use super::fees::FeeSchedule;
+use super::ledger::Ledger;
+use super::lots::{LotMethod, Pyramiding};
+use super::margin::{FundingRate, MarginConfig, MarginMode};
+use super::metrics::{MetricsConfig, PerformanceMetrics};
+use super::optimizer::OptimizerError;
+use super::orders::{Fill, OrderRequest, OrderSide, OrderType, Trail};
+use super::risk::{average_true_range, ExitDistance, ExitReason, ExitRules};
+use super::rolling::{RollingMetrics, StrategyGate};
+use super::sizing::{PositionSizing, SizingContext, SizingLimits};
+use super::slippage::SlippageModel;
+use super::trade_analysis::TradeAnalysis;
+use crate::data::{MarketData, ProcessedMarketData};
 use crate::strategies::{
     BollingerBands, BollingerSignal, BollingerSignalType, RsiSignal, RsiSignalType, RsiStrategy,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
 /// Represents a single trade executed during the backtesting process.
 ///
 /// Tracks comprehensive details of a trade from entry to exit, including
 /// timing, pricing, position type, and performance metrics.
+///
+/// Each trade is one lot of a position (see `LotMethod`); closing a lot in
+/// parts reports one trade per part, with `pnl` attributed to that lot.
+///
+/// `entry_order_id` and `exit_order_id` link the trade to the orders in the
+/// backtest event log that opened and closed it, and `exit_reason` records
+/// whether a signal or a protective exit closed it. `commission` holds the fees
+/// charged on the trade's entry and exit fills, which `pnl` is net of.
+/// `slippage` is the cost of slippage on the same fills; it is already
+/// reflected in the fill prices and hence in `pnl`.
+///
+/// `max_adverse_excursion` and `max_favorable_excursion` are the furthest the
+/// price moved against and in favour of the trade while it was open, as
+/// fractions of the entry price, from the bar highs and lows and the exit
+/// price. `bars_held` counts the bars from the entry bar to the exit bar, and
+/// `signal` describes the signal that opened the trade.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub entry_time: DateTime<Utc>,
@@ -20,6 +53,68 @@ pub struct Trade {
     pub quantity: f64,
     pub pnl: Option<f64>,
     pub strategy_name: String,
+    #[serde(default)]
+    pub entry_order_id: Option<u64>,
+    #[serde(default)]
+    pub exit_order_id: Option<u64>,
+    #[serde(default)]
+    pub exit_reason: Option<ExitReason>,
+    #[serde(default)]
+    pub commission: f64,
+    #[serde(default)]
+    pub slippage: f64,
+    #[serde(default)]
+    pub max_adverse_excursion: f64,
+    #[serde(default)]
+    pub max_favorable_excursion: f64,
+    #[serde(default)]
+    pub bars_held: usize,
+    #[serde(default)]
+    pub signal: Option<SignalMetadata>,
+}
+
+impl Trade {
+    /// Returns the net PnL of a closed trade as a fraction of its entry notional.
+    pub fn net_return(&self) -> Option<f64> {
+        let notional = self.entry_price * self.quantity;
+        self.pnl
+            .filter(|_| notional > 0.0)
+            .map(|pnl| pnl / notional)
+    }
+
+    /// Widens the trade's excursions to include a price it traded at.
+    fn record_excursion(&mut self, price: f64) {
+        if self.entry_price <= 0.0 {
+            return;
+        }
+        let direction = match self.position_type {
+            PositionType::Long => 1.0,
+            PositionType::Short => -1.0,
+        };
+        let change = direction * (price - self.entry_price) / self.entry_price;
+        self.max_favorable_excursion = self.max_favorable_excursion.max(change);
+        self.max_adverse_excursion = self.max_adverse_excursion.max(-change);
+    }
+}
+
+/// The signal behind an order, as seen on the bar that produced it.
+///
+/// # Fields
+/// * `timestamp`: Timestamp of the signal bar
+/// * `signal`: Direction of the signal
+/// * `price`: Close of the signal bar
+/// * `strength`: Conviction of the signal in `[0, 1]`
+/// * `rsi`: RSI at the signal bar, if available
+/// * `band_position`: Distance of the price from the middle Bollinger band, in
+///   band half-widths; positive above the middle band
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalMetadata {
+    pub timestamp: DateTime<Utc>,
+    pub signal: TradeSignal,
+    pub price: f64,
+    pub strength: f64,
+    pub rsi: Option<f64>,
+    pub band_position: Option<f64>,
 }
 
 /// Represents the type of trading position (long or short).
@@ -45,8 +140,28 @@ pub enum PositionType {
 /// - Win rate
 /// - Average win and loss
 /// - Largest win and loss
-/// - Maximum drawdown
+/// - Maximum drawdown, and the deepest drawdown periods in `drawdown_periods`
 /// - Sharpe Ratio
+/// - Extended return, risk and trade statistics in `metrics`
+/// - Account totals from the ledger: final equity, unrealized PnL of positions
+///   still open at the end, commissions, funding and carry costs
+/// - Total slippage paid on all fills, reported separately from commissions
+/// - Excursion, holding period and exit reason distributions in `trade_analysis`
+/// - Rolling Sharpe ratio, volatility, win rate, drawdown and beta in `rolling`,
+///   one series per configured window
+/// - Alpha, beta and capture ratios against the configured benchmark, with the
+///   relative equity curve, in `benchmark`
+///
+/// `final_equity` reconciles with the trade list:
+/// `initial capital + total_pnl + unrealized_pnl + total_funding - total_carry`,
+/// less the entry commissions of positions still open, where `total_pnl` is net of the
+/// commissions charged on both legs of closed trades.
+///
+/// `events` is the timestamped log of signals, orders, fills and position
+/// changes; `trade_events` extracts the entries behind a single trade.
+///
+/// Fields added since the original result format default when missing, so
+/// results serialized by earlier versions still deserialize.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BacktestResult {
     pub total_trades: usize,
@@ -60,7 +175,44 @@ pub struct BacktestResult {
     pub largest_loss: f64,
     pub max_drawdown: f64,
     pub sharpe_ratio: f64,
+    #[serde(default)]
+    pub final_equity: f64,
+    #[serde(default)]
+    pub unrealized_pnl: f64,
+    #[serde(default)]
+    pub total_commission: f64,
+    #[serde(default)]
+    pub total_funding: f64,
+    #[serde(default)]
+    pub total_slippage: f64,
+    #[serde(default)]
+    pub total_carry: f64,
     pub trades: Vec<Trade>,
+    #[serde(default)]
+    pub equity_curve: Vec<EquityPoint>,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(default)]
+    pub metrics: PerformanceMetrics,
+    #[serde(default)]
+    pub benchmark: Option<BenchmarkComparison>,
+    #[serde(default)]
+    pub drawdown_periods: Vec<DrawdownPeriod>,
+    #[serde(default)]
+    pub trade_analysis: TradeAnalysis,
+    #[serde(default)]
+    pub rolling: Vec<RollingMetrics>,
+}
+
+impl BacktestResult {
+    /// Returns the events of the orders that opened and closed `trade`, in order.
+    pub fn trade_events(&self, trade: &Trade) -> Vec<&Event> {
+        let order_ids = [trade.entry_order_id, trade.exit_order_id];
+        self.events
+            .iter()
+            .filter(|event| event.order_id().is_some() && order_ids.contains(&event.order_id()))
+            .collect()
+    }
 }
 
 /// Represents the mode of strategy execution during backtesting.
@@ -69,13 +221,41 @@ pub struct BacktestResult {
 /// - `Rsi`: Only RSI strategy signals are considered
 /// - `BollingerBands`: Only Bollinger Bands strategy signals are considered
 /// - `Combined`: Requires signal confirmation from both strategies
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum StrategyMode {
     Rsi,            // RSI strategy only
     BollingerBands, // Bollinger Bands strategy only
     Combined,       // Both strategies must agree
 }
 
+impl StrategyMode {
+    /// Returns the tunable parameters of the strategies the mode trades on.
+    ///
+    /// Each name can be set with `Backtester::set_parameter`.
+    pub fn parameters(&self) -> &'static [&'static str] {
+        match self {
+            StrategyMode::Rsi => &["rsi_period", "rsi_oversold", "rsi_overbought"],
+            StrategyMode::BollingerBands => &[
+                "bollinger_period",
+                "bollinger_std_dev",
+                "bollinger_trend_fast",
+                "bollinger_trend_slow",
+                "bollinger_exit_fraction",
+            ],
+            StrategyMode::Combined => &[
+                "rsi_period",
+                "rsi_oversold",
+                "rsi_overbought",
+                "bollinger_period",
+                "bollinger_std_dev",
+                "bollinger_trend_fast",
+                "bollinger_trend_slow",
+                "bollinger_exit_fraction",
+            ],
+        }
+    }
+}
+
 /// Represents possible trading signals used to communicate
 /// trading decisions between strategies and the backtester.
 ///
@@ -85,7 +265,7 @@ pub enum StrategyMode {
 /// - `Hold`: Take no trading action
 /// - `Long`: Explicitly open a long position
 /// - `Short`: Explicitly open a short position
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum TradeSignal {
     Buy,
     Sell,
@@ -98,6 +278,9 @@ pub enum TradeSignal {
 ///
 /// Tracks the portfolio value and drawdown at a specific moment in time,
 /// allowing for detailed performance analysis and visualization.
+///
+/// `drawdown` is the fall from the highest equity reached so far, as a
+/// fraction of that peak, so the curve's drawdowns form the underwater curve.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EquityPoint {
     pub timestamp: DateTime<Utc>,
@@ -113,16 +296,82 @@ pub struct EquityPoint {
 /// - Strategy evaluation
 /// - Metrics calculation
 ///
+/// Cash, positions, commissions and funding are kept in a `Ledger`, and the
+/// equity curve is marked to market on every bar.
+///
+/// The backtest runs as an event loop: signals generated at a bar's close are
+/// turned into orders, the `SimulatedExchange` fills them on a later bar (see
+/// `FillTiming`), and fills update the ledger and trade list. Every step is
+/// recorded in a timestamped event log.
+///
+/// Protective exits (see `ExitRules`) are placed with the exchange as soon as
+/// an entry fills, either for all strategies or per strategy mode, and are
+/// resized as the position is scaled in or out (see `Pyramiding`).
+///
+/// With a `MarginConfig`, positions are opened on margin up to the configured
+/// leverage and are liquidated when a bar's high or low reaches their
+/// liquidation price. Funding rates are paid on open positions at every
+/// funding timestamp, and a `CarryModel` charges borrow and financing costs
+/// for every bar a position is held.
+///
 /// Supports multiple strategy modes and provides comprehensive
 /// performance analysis for trading strategies.
+#[derive(Clone)]
 pub struct Backtester {
     initial_capital: f64,
-    position_size: f64,
-    commission_rate: f64,
+    fees: FeeSchedule,
+    traded_volume: VecDeque<(DateTime<Utc>, f64)>,
     strategy_mode: StrategyMode,
+    sizing: PositionSizing,
+    sizing_limits: SizingLimits,
     trades: Vec<Trade>,
-    current_position: HashMap<String, Option<Trade>>,
+    current_position: HashMap<String, Vec<Trade>>,
+    lot_method: LotMethod,
+    pyramiding: Pyramiding,
+    entry_orders: HashMap<String, HashSet<u64>>,
+    ledger: Ledger,
+    exchange: SimulatedExchange,
+    events: Vec<Event>,
     equity_curve: Vec<EquityPoint>,
+    exit_rules: ExitRules,
+    strategy_exit_rules: HashMap<StrategyMode, ExitRules>,
+    exit_orders: HashMap<u64, ExitReason>,
+    order_signals: HashMap<u64, SignalMetadata>,
+    protection: HashMap<String, Protection>,
+    atr: Vec<Option<f64>>,
+    margin: Option<MarginConfig>,
+    funding_rates: HashMap<String, Vec<FundingRate>>,
+    funding_cursor: HashMap<String, usize>,
+    carry: CarryModel,
+    last_bar_time: HashMap<String, DateTime<Utc>>,
+    metrics_config: MetricsConfig,
+    bars_in_market: usize,
+    peak_equity: f64,
+    benchmark: Benchmark,
+    rolling_windows: Vec<usize>,
+    strategy_gate: Option<StrategyGate>,
+    rsi_strategy: RsiStrategy,
+    bollinger_bands: BollingerBands,
+    warmup_bars: usize,
+}
+
+/// Protective exits working for an open position.
+///
+/// # Fields
+/// * `entry_bar`: Index of the bar the position was opened on
+/// * `atr`: Average true range at entry, for ATR-based distances
+/// * `group`: One-cancels-other group of the exit orders
+/// * `stop_order`: Stop-loss order, moved by the break-even rule
+/// * `order_ids`: All working exit orders
+/// * `break_even_done`: Whether the stop has been moved to the entry price
+#[derive(Debug, Clone)]
+struct Protection {
+    entry_bar: usize,
+    atr: Option<f64>,
+    group: Option<u64>,
+    stop_order: Option<u64>,
+    order_ids: Vec<u64>,
+    break_even_done: bool,
 }
 
 impl Backtester {
@@ -135,21 +384,51 @@ impl Backtester {
     ///
     /// # Arguments
     /// * `initial_capital` - Starting portfolio value
-    /// * `position_size` - Fixed dollar amount per trade
-    /// * `commission_rate` - Transaction fee rate
+    /// * `position_size` - Fixed dollar amount per trade (see `set_position_sizing`)
+    /// * `commission_rate` - Transaction fee rate (see `set_fee_schedule`)
     pub fn new(initial_capital: f64, position_size: f64, commission_rate: f64) -> Self {
         Self {
             initial_capital,
-            position_size,
-            commission_rate,
+            fees: FeeSchedule::flat(commission_rate),
+            traded_volume: VecDeque::new(),
             strategy_mode: StrategyMode::Combined,
+            sizing: PositionSizing::Fixed {
+                notional: position_size,
+            },
+            sizing_limits: SizingLimits::default(),
             trades: Vec::new(),
             current_position: HashMap::new(),
+            lot_method: LotMethod::default(),
+            pyramiding: Pyramiding::default(),
+            entry_orders: HashMap::new(),
+            ledger: Ledger::new(initial_capital),
+            exchange: SimulatedExchange::new(FillTiming::default()),
+            events: Vec::new(),
             equity_curve: vec![EquityPoint {
                 timestamp: Utc::now(),
                 equity: initial_capital,
                 drawdown: 0.0,
             }],
+            exit_rules: ExitRules::default(),
+            strategy_exit_rules: HashMap::new(),
+            exit_orders: HashMap::new(),
+            order_signals: HashMap::new(),
+            protection: HashMap::new(),
+            atr: Vec::new(),
+            margin: None,
+            funding_rates: HashMap::new(),
+            funding_cursor: HashMap::new(),
+            carry: CarryModel::None,
+            last_bar_time: HashMap::new(),
+            metrics_config: MetricsConfig::default(),
+            bars_in_market: 0,
+            peak_equity: initial_capital,
+            benchmark: Benchmark::default(),
+            rolling_windows: vec![30],
+            strategy_gate: None,
+            rsi_strategy: RsiStrategy::new(40.0, 60.0),
+            bollinger_bands: BollingerBands::new(20, 1.8),
+            warmup_bars: 0,
         }
     }
 
@@ -164,15 +443,310 @@ impl Backtester {
         self.strategy_mode = mode;
     }
 
-    /// Calculates commission for a trade based on its value.
+    /// Sets the price at which orders are filled on the bar after the signal.
+    ///
+    /// # Arguments
+    /// * `timing` - Fill at the next bar's open (default) or close
+    pub fn set_fill_timing(&mut self, timing: FillTiming) {
+        self.exchange.set_fill_timing(timing);
+    }
+
+    /// Sets the slippage model applied to fills that take liquidity.
+    ///
+    /// # Arguments
+    /// * `model` - Fixed bps, volatility-proportional, square-root impact or spread model
+    pub fn set_slippage_model(&mut self, model: SlippageModel) {
+        self.exchange.set_slippage_model(model);
+    }
+
+    /// Sets the exchange fees charged on every fill.
+    ///
+    /// # Arguments
+    /// * `fees` - Maker/taker, tiered or preset fee schedule, replacing the flat `commission_rate`
+    pub fn set_fee_schedule(&mut self, fees: FeeSchedule) {
+        self.fees = fees;
+    }
+
+    /// Sets the model used to size new positions.
+    ///
+    /// # Arguments
+    /// * `sizing` - Position sizing model, replacing the fixed `position_size`
+    pub fn set_position_sizing(&mut self, sizing: PositionSizing) {
+        self.sizing = sizing;
+    }
+
+    /// Sets the caps on position size and leverage applied after sizing.
+    ///
+    /// # Arguments
+    /// * `limits` - Maximum single-position fraction of equity and gross leverage
+    pub fn set_sizing_limits(&mut self, limits: SizingLimits) {
+        self.sizing_limits = limits;
+    }
+
+    /// Sets how closing fills are matched against the lots of a position.
+    ///
+    /// # Arguments
+    /// * `method` - Average price (default), FIFO or LIFO lot accounting
+    pub fn set_lot_method(&mut self, method: LotMethod) {
+        self.lot_method = method;
+    }
+
+    /// Sets how positions are scaled into and out of.
+    ///
+    /// # Arguments
+    /// * `pyramiding` - Maximum entries, entry scaling and exit fraction
+    pub fn set_pyramiding(&mut self, pyramiding: Pyramiding) {
+        self.pyramiding = pyramiding;
+    }
+
+    /// Sets the protective exits used by all strategy modes.
+    ///
+    /// # Arguments
+    /// * `rules` - Stop-loss, take-profit, trailing, break-even and time stops
+    pub fn set_exit_rules(&mut self, rules: ExitRules) {
+        self.exit_rules = rules;
+    }
+
+    /// Sets protective exits for one strategy mode, overriding the global rules.
+    ///
+    /// # Arguments
+    /// * `mode` - Strategy mode the rules apply to
+    /// * `rules` - Exit rules used while running in `mode`
+    pub fn set_strategy_exit_rules(&mut self, mode: StrategyMode, rules: ExitRules) {
+        self.strategy_exit_rules.insert(mode, rules);
+    }
+
+    /// Trades on margin, as for perpetual futures.
+    ///
+    /// # Arguments
+    /// * `margin` - Leverage, maintenance margin and margin mode, or `None` for
+    ///   unlevered trading without liquidations
+    pub fn set_margin(&mut self, margin: Option<MarginConfig>) {
+        self.margin = margin;
+    }
+
+    /// Sets the funding rates paid on positions in `symbol`.
+    ///
+    /// # Arguments
+    /// * `symbol` - Symbol the funding rates apply to
+    /// * `rates` - Rate at every funding timestamp, e.g. every 8 hours
+    pub fn set_funding_rates(&mut self, symbol: &str, mut rates: Vec<FundingRate>) {
+        rates.sort_by_key(|rate| rate.timestamp);
+        self.funding_rates.insert(symbol.to_string(), rates);
+    }
+
+    /// Sets the cost of holding positions.
+    ///
+    /// # Arguments
+    /// * `model` - Annualized borrow/financing rates or a per-bar rate series
+    pub fn set_carry_model(&mut self, mut model: CarryModel) {
+        if let CarryModel::PerBar(rates) = &mut model {
+            rates.sort_by_key(|rate| rate.timestamp);
+        }
+        self.carry = model;
+    }
+
+    /// Sets the annualization and risk-free rate used for performance metrics.
+    ///
+    /// # Arguments
+    /// * `config` - Periods per year (365 by default), risk-free rate and VaR confidence
+    pub fn set_metrics_config(&mut self, config: MetricsConfig) {
+        self.metrics_config = config;
+    }
+
+    /// Returns the annualization and risk-free rate used for performance metrics.
+    pub fn metrics_config(&self) -> MetricsConfig {
+        self.metrics_config
+    }
+
+    /// Sets the number of leading bars on which signals are ignored.
+    ///
+    /// The strategies still see these bars, so their indicators are warmed up
+    /// by the time the first trade can be placed.
+    ///
+    /// # Arguments
+    /// * `bars` - Bars to skip before trading; 0 by default
+    pub fn set_warmup_bars(&mut self, bars: usize) {
+        self.warmup_bars = bars;
+    }
+
+    /// Sets what backtest results are compared against.
+    ///
+    /// # Arguments
+    /// * `benchmark` - Buy-and-hold of the traded asset (default), a custom series, or none
+    pub fn set_benchmark(&mut self, mut benchmark: Benchmark) {
+        if let Benchmark::Custom(points) = &mut benchmark {
+            points.sort_by_key(|point| point.timestamp);
+        }
+        self.benchmark = benchmark;
+    }
+
+    /// Sets the windows rolling metrics are reported over.
+    ///
+    /// # Arguments
+    /// * `windows` - Window lengths in bars; 30 by default
+    pub fn set_rolling_windows(&mut self, windows: Vec<usize>) {
+        self.rolling_windows = windows;
+    }
+
+    /// Sets the rolling-metric thresholds that enable sub-strategies in combined mode.
+    ///
+    /// # Arguments
+    /// * `gate` - Thresholds on each sub-strategy's own rolling metrics, or `None`
+    ///   to always combine both
+    pub fn set_strategy_gate(&mut self, gate: Option<StrategyGate>) {
+        self.strategy_gate = gate;
+    }
+
+    /// Sets the RSI strategy signals are generated with.
+    ///
+    /// # Arguments
+    /// * `strategy` - RSI period and thresholds; `RsiStrategy::new(40.0, 60.0)` by default
+    pub fn set_rsi_strategy(&mut self, strategy: RsiStrategy) {
+        self.rsi_strategy = strategy;
+    }
+
+    /// Sets the Bollinger Bands strategy signals are generated with.
+    ///
+    /// # Arguments
+    /// * `strategy` - Band period and width; `BollingerBands::new(20, 1.8)` by default
+    pub fn set_bollinger_bands(&mut self, strategy: BollingerBands) {
+        self.bollinger_bands = strategy;
+    }
+
+    /// Returns the strategy signals are generated with.
+    pub fn strategy_mode(&self) -> StrategyMode {
+        self.strategy_mode
+    }
+
+    /// Sets a strategy parameter by name, as listed by `StrategyMode::parameters`.
+    ///
+    /// See `set_parameters` for the accepted values.
+    ///
+    /// # Errors
+    /// Returns an error for unknown names and invalid values; the strategies
+    /// are left unchanged
+    pub fn set_parameter(&mut self, name: &str, value: f64) -> Result<(), OptimizerError> {
+        self.set_parameters(&[(name, value)])
+    }
+
+    /// Sets several strategy parameters by name, checking them together.
+    ///
+    /// Periods are rounded to whole bars and must be at least one bar. RSI
+    /// thresholds must lie in `[0, 100]` with `rsi_oversold` below
+    /// `rsi_overbought`, `bollinger_trend_fast` must be shorter than
+    /// `bollinger_trend_slow`, the band width `bollinger_std_dev` must be
+    /// positive and `bollinger_exit_fraction` must lie in `(0, 1]`. Pairs are
+    /// compared after every value is applied, so the order does not matter.
+    ///
+    /// # Errors
+    /// Returns an error for unknown names and invalid values; the strategies
+    /// are left unchanged
+    pub fn set_parameters(&mut self, parameters: &[(&str, f64)]) -> Result<(), OptimizerError> {
+        let (rsi_strategy, bollinger_bands) =
+            (self.rsi_strategy.clone(), self.bollinger_bands.clone());
+        let result = parameters
+            .iter()
+            .try_for_each(|&(name, value)| self.apply_parameter(name, value))
+            .and_then(|()| {
+                let (oversold, overbought) = (
+                    self.rsi_strategy.oversold_threshold,
+                    self.rsi_strategy.overbought_threshold,
+                );
+                let (fast, slow) = (
+                    self.bollinger_bands.trend_fast_period,
+                    self.bollinger_bands.trend_slow_period,
+                );
+                if oversold >= overbought {
+                    Err(OptimizerError::InvalidValue {
+                        name: "rsi_oversold".to_string(),
+                        value: oversold,
+                    })
+                } else if fast >= slow {
+                    Err(OptimizerError::InvalidValue {
+                        name: "bollinger_trend_fast".to_string(),
+                        value: fast as f64,
+                    })
+                } else {
+                    Ok(())
+                }
+            });
+        if result.is_err() {
+            self.rsi_strategy = rsi_strategy;
+            self.bollinger_bands = bollinger_bands;
+        }
+        result
+    }
+
+    /// Sets one strategy parameter after checking its own range.
+    fn apply_parameter(&mut self, name: &str, value: f64) -> Result<(), OptimizerError> {
+        let invalid = || OptimizerError::InvalidValue {
+            name: name.to_string(),
+            value,
+        };
+        let period = || {
+            let period = value.round();
+            if period >= 1.0 {
+                Ok(period as usize)
+            } else {
+                Err(invalid())
+            }
+        };
+        let within = |valid: bool| if valid { Ok(value) } else { Err(invalid()) };
+
+        match name {
+            "rsi_period" => self.rsi_strategy.period = period()?,
+            "rsi_oversold" => {
+                self.rsi_strategy.oversold_threshold = within((0.0..=100.0).contains(&value))?
+            }
+            "rsi_overbought" => {
+                self.rsi_strategy.overbought_threshold = within((0.0..=100.0).contains(&value))?
+            }
+            "bollinger_period" => self.bollinger_bands.period = period()?,
+            "bollinger_std_dev" => self.bollinger_bands.std_dev_multiplier = within(value > 0.0)?,
+            "bollinger_trend_fast" => self.bollinger_bands.trend_fast_period = period()?,
+            "bollinger_trend_slow" => self.bollinger_bands.trend_slow_period = period()?,
+            "bollinger_exit_fraction" => {
+                self.bollinger_bands.exit_band_fraction = within(value > 0.0 && value <= 1.0)?
+            }
+            _ => {
+                return Err(OptimizerError::UnknownParameter {
+                    name: name.to_string(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the exit rules in effect for the current strategy mode.
+    fn active_exit_rules(&self) -> ExitRules {
+        self.strategy_exit_rules
+            .get(&self.strategy_mode)
+            .copied()
+            .unwrap_or(self.exit_rules)
+    }
+
+    /// Calculates commission for a fill and adds it to the trailing volume.
     ///
-    /// Applies the transaction commission rate to the trade value to
-    /// determine the commission amount.
+    /// Applies the fee schedule tier reached by the notional traded over the
+    /// schedule's volume window before this fill.
     ///
     /// # Arguments
-    /// * `trade_value` - Total value of the trade
-    fn calculate_commission(&self, trade_value: f64) -> f64 {
-        trade_value * self.commission_rate
+    /// * `fill` - Execution to charge
+    fn calculate_commission(&mut self, fill: &Fill) -> f64 {
+        let window_start = fill.timestamp - Duration::days(self.fees.volume_window_days);
+        while self
+            .traded_volume
+            .front()
+            .is_some_and(|(timestamp, _)| *timestamp <= window_start)
+        {
+            self.traded_volume.pop_front();
+        }
+
+        let notional = fill.quantity * fill.price;
+        let trailing_volume: f64 = self.traded_volume.iter().map(|(_, volume)| volume).sum();
+        self.traded_volume.push_back((fill.timestamp, notional));
+        self.fees.fee(notional, fill.liquidity, trailing_volume)
     }
 
     /// Executes the backtest on the provided market data.
@@ -184,33 +758,105 @@ impl Backtester {
     /// # Arguments
     /// * `data` - Slice of processed market data
     pub fn run_backtest(&mut self, data: &[ProcessedMarketData]) -> BacktestResult {
+        let gates = self.sub_strategy_gates(data);
         self.trades.clear();
         self.current_position.clear();
+        self.entry_orders.clear();
+        self.ledger = Ledger::new(self.initial_capital);
+        self.traded_volume.clear();
+        self.funding_cursor.clear();
+        self.last_bar_time.clear();
+        self.bars_in_market = 0;
+        self.peak_equity = self.initial_capital;
+        self.exchange.reset();
+        self.events.clear();
+        self.exit_orders.clear();
+        self.order_signals.clear();
+        self.protection.clear();
+        let bars: Vec<MarketData> = data.iter().map(|bar| bar.raw_data.clone()).collect();
+        self.atr = average_true_range(&bars, self.active_exit_rules().atr_period);
         self.equity_curve.clear();
         self.equity_curve.push(EquityPoint {
-            timestamp: Utc::now(),
+            timestamp: data
+                .first()
+                .map_or_else(Utc::now, |first| first.raw_data.timestamp),
             equity: self.initial_capital,
             drawdown: 0.0,
         });
 
-        // Initialize strategies with refined thresholds
-        let rsi_strategy = RsiStrategy::new(40.0, 60.0);
-        let mut bollinger_bands = BollingerBands::new(20, 1.8);
+        // Start the strategies from their configured parameters
+        let rsi_strategy = self.rsi_strategy.clone();
+        let mut bollinger_bands = self.bollinger_bands.clone();
 
         // Process signals for each strategy
-        let rsi_signals = rsi_strategy.analyze_batch(data);
+        let mut rsi_signals = rsi_strategy.analyze_batch(data);
         let mut bollinger_signals = Vec::new();
 
         for market_data in data {
             bollinger_signals.push(bollinger_bands.analyze(market_data));
         }
 
+        // Sub-strategies switched off by the gate hold
+        for (i, [rsi_enabled, bollinger_enabled]) in gates.iter().flatten().enumerate() {
+            if !rsi_enabled {
+                rsi_signals[i].signal_type = RsiSignalType::Hold;
+            }
+            if !bollinger_enabled {
+                bollinger_signals[i].signal_type = BollingerSignalType::Hold;
+            }
+        }
+
         // Process trades based on strategy mode
         for i in 0..data.len() {
             let rsi_signal = &rsi_signals[i];
             let bollinger_signal = &bollinger_signals[i];
             let market_data = &data[i];
 
+            if let Some(gates) = &gates {
+                let previous = i
+                    .checked_sub(1)
+                    .map_or([true; 2], |previous| gates[previous]);
+                let modes = [StrategyMode::Rsi, StrategyMode::BollingerBands];
+                for (mode, (enabled, was_enabled)) in
+                    modes.into_iter().zip(gates[i].into_iter().zip(previous))
+                {
+                    if enabled != was_enabled {
+                        self.record(
+                            market_data.raw_data.timestamp,
+                            EventKind::StrategyToggled {
+                                strategy: mode,
+                                enabled,
+                            },
+                        );
+                    }
+                }
+            }
+
+            self.apply_funding(&market_data.raw_data);
+            self.accrue_carry(&market_data.raw_data);
+
+            // Fill orders generated on earlier bars before looking at this bar's signals,
+            // then match protective exits attached to fills at this bar's open
+            loop {
+                let execution = self.exchange.process_bar(&market_data.raw_data);
+                if execution.fills.is_empty() && execution.cancelled.is_empty() {
+                    break;
+                }
+                for fill in execution.fills {
+                    self.record(fill.timestamp, EventKind::OrderFilled(fill.clone()));
+                    self.apply_fill(&fill, i);
+                }
+                for (order, reason) in execution.cancelled {
+                    self.record(
+                        market_data.raw_data.timestamp,
+                        EventKind::OrderCancelled { order, reason },
+                    );
+                }
+            }
+
+            self.check_liquidation(&market_data.raw_data, i);
+            self.track_open_lots(&market_data.raw_data);
+
             let mut should_trade = false;
             let mut trade_signal = None;
 
@@ -247,15 +893,32 @@ impl Backtester {
                 }
             }
 
-            if should_trade {
+            // Mark open positions to the bar close so sizing sees current equity
+            self.ledger
+                .mark(&market_data.raw_data.symbol, market_data.raw_data.price);
+
+            if should_trade && i >= self.warmup_bars {
                 if let Some(signal) = trade_signal {
-                    self.execute_trade(market_data, signal);
+                    let metadata = Self::signal_metadata(
+                        self.strategy_mode,
+                        signal,
+                        rsi_signal,
+                        bollinger_signal,
+                    );
+                    self.submit_orders(market_data, metadata, &bars[..=i]);
                 }
             }
 
-            // Update equity curve
-            let current_equity = self.calculate_current_equity(market_data.raw_data.price);
-            let drawdown = (self.initial_capital - current_equity) / self.initial_capital;
+            self.update_protection(&market_data.raw_data, i);
+
+            if !self.current_position.is_empty() {
+                self.bars_in_market += 1;
+            }
+
+            // Update the equity curve
+            let current_equity = self.ledger.equity();
+            self.peak_equity = self.peak_equity.max(current_equity);
+            let drawdown = drawdown_from_peak(self.peak_equity, current_equity);
             self.equity_curve.push(EquityPoint {
                 timestamp: market_data.raw_data.timestamp,
                 equity: current_equity,
@@ -263,7 +926,81 @@ impl Backtester {
             });
         }
 
-        self.calculate_results()
+        // Orders generated on the last bar have nothing left to fill against
+        if let Some(last) = data.last() {
+            for order in self.exchange.cancel_all() {
+                self.record(
+                    last.raw_data.timestamp,
+                    EventKind::OrderCancelled {
+                        order,
+                        reason: "end of data".to_string(),
+                    },
+                );
+            }
+        }
+
+        let mut result = self.calculate_results();
+        result.benchmark =
+            self.benchmark
+                .compare(&result.equity_curve, &bars, &self.metrics_config);
+        let benchmark = self.benchmark.equity_curve(&result.equity_curve, &bars);
+        result.rolling = self
+            .rolling_windows
+            .iter()
+            .map(|&window| {
+                RollingMetrics::calculate(
+                    &result.equity_curve,
+                    &result.trades,
+                    benchmark.as_deref(),
+                    window,
+                    &self.metrics_config,
+                )
+            })
+            .collect();
+        result
+    }
+
+    /// Decides on which bars each sub-strategy of combined mode is enabled.
+    ///
+    /// Runs the RSI and Bollinger Bands strategies on their own with the same
+    /// settings, and enables each on a bar if the `StrategyGate` accepts its
+    /// rolling metrics up to the previous bar.
+    ///
+    /// # Returns
+    /// `[rsi, bollinger]` flags per bar, or `None` outside combined mode or
+    /// without a gate
+    fn sub_strategy_gates(&self, data: &[ProcessedMarketData]) -> Option<Vec<[bool; 2]>> {
+        let gate = self.strategy_gate?;
+        if self.strategy_mode != StrategyMode::Combined {
+            return None;
+        }
+
+        let mut shadow = self.clone();
+        shadow.strategy_gate = None;
+        shadow.rolling_windows.clear();
+        shadow.benchmark = Benchmark::None;
+        let rolling = [StrategyMode::Rsi, StrategyMode::BollingerBands].map(|mode| {
+            shadow.set_strategy_mode(mode);
+            let result = shadow.run_backtest(data);
+            RollingMetrics::calculate(
+                &result.equity_curve,
+                &result.trades,
+                None,
+                gate.window,
+                &self.metrics_config,
+            )
+        });
+
+        // Equity point `i` is the equity after bar `i - 1`
+        Some(
+            (0..data.len())
+                .map(|i| {
+                    rolling
+                        .each_ref()
+                        .map(|rolling| rolling.at(i).is_none_or(|point| gate.allows(point)))
+                })
+                .collect(),
+        )
     }
 
     /// Determines if signals from both RSI and Bollinger Bands agree.
@@ -319,138 +1056,733 @@ impl Backtester {
         }
     }
 
-    /// Executes a trade based on the provided signal.
+    /// Measures the conviction of the signals at a bar, in `[0, 1]`.
+    ///
+    /// RSI conviction grows with the distance of the RSI from 50; Bollinger
+    /// conviction with the distance of the price from the middle band relative
+    /// to the band half-width. Combined mode averages the two.
+    fn signal_strength(
+        mode: StrategyMode,
+        rsi_signal: &RsiSignal,
+        bollinger_signal: &BollingerSignal,
+    ) -> f64 {
+        let rsi = rsi_signal
+            .rsi
+            .map(|rsi| ((rsi - 50.0).abs() / 50.0).clamp(0.0, 1.0));
+        let bands = &bollinger_signal.bands;
+        let half_width = bands.upper - bands.middle;
+        let bollinger = (half_width > 0.0)
+            .then(|| ((bollinger_signal.price - bands.middle).abs() / half_width).clamp(0.0, 1.0));
+
+        let strength = match mode {
+            StrategyMode::Rsi => rsi,
+            StrategyMode::BollingerBands => bollinger,
+            StrategyMode::Combined => match (rsi, bollinger) {
+                (Some(rsi), Some(bollinger)) => Some((rsi + bollinger) / 2.0),
+                (rsi, bollinger) => rsi.or(bollinger),
+            },
+        };
+        strength.unwrap_or(0.0)
+    }
+
+    /// Describes the signal taken at a bar for the trades it opens.
+    fn signal_metadata(
+        mode: StrategyMode,
+        signal: TradeSignal,
+        rsi_signal: &RsiSignal,
+        bollinger_signal: &BollingerSignal,
+    ) -> SignalMetadata {
+        let bands = &bollinger_signal.bands;
+        let half_width = bands.upper - bands.middle;
+        SignalMetadata {
+            timestamp: bollinger_signal.timestamp,
+            signal,
+            price: bollinger_signal.price,
+            strength: Self::signal_strength(mode, rsi_signal, bollinger_signal),
+            rsi: rsi_signal.rsi,
+            band_position: (half_width > 0.0)
+                .then(|| (bollinger_signal.price - bands.middle) / half_width),
+        }
+    }
+
+    /// Updates the excursions and holding periods of the open lots of the bar's symbol.
+    fn track_open_lots(&mut self, bar: &MarketData) {
+        let Some(lots) = self.current_position.get_mut(&bar.symbol) else {
+            return;
+        };
+        for lot in lots {
+            lot.record_excursion(bar.low);
+            lot.record_excursion(bar.high);
+            if bar.timestamp > lot.entry_time {
+                lot.bars_held += 1;
+            }
+        }
+    }
+
+    /// Appends an entry to the event log.
+    fn record(&mut self, timestamp: DateTime<Utc>, kind: EventKind) {
+        self.events.push(Event { timestamp, kind });
+    }
+
+    /// Returns the open lots of `symbol` combined into a single position.
+    fn open_position(&self, symbol: &str) -> Option<Trade> {
+        let lots = self.current_position.get(symbol)?;
+        let first = lots.first()?.clone();
+        let quantity: f64 = lots.iter().map(|lot| lot.quantity).sum();
+        let cost: f64 = lots.iter().map(|lot| lot.entry_price * lot.quantity).sum();
+
+        Some(Trade {
+            quantity,
+            entry_price: cost / quantity,
+            commission: lots.iter().map(|lot| lot.commission).sum(),
+            slippage: lots.iter().map(|lot| lot.slippage).sum(),
+            ..first
+        })
+    }
+
+    /// Turns a trade signal into orders for the simulated exchange.
     ///
-    /// Opens a long position if the signal is Buy, closes a short position if the signal is Sell,
-    /// or does nothing if the signal is Hold.
+    /// Opens a position if none exists, adds to it while the `Pyramiding`
+    /// limits allow, or closes (part of) it on an opposite signal, opening one
+    /// in the opposite direction once it is closed in full. New entries are
+    /// sized by the `PositionSizing` model at the signal bar's close, capped
+    /// by the `SizingLimits`, and filled on a later bar.
     ///
     /// # Arguments
-    /// * `market_data` - Processed market data containing the current price and timestamp
-    /// * `signal` - Trade signal indicating the direction to take
-    fn execute_trade(&mut self, market_data: &ProcessedMarketData, signal: TradeSignal) {
-        let symbol = &market_data.raw_data.symbol;
+    /// * `market_data` - Processed market data of the bar that produced the signal
+    /// * `metadata` - Trade signal, its conviction and the indicators behind it
+    /// * `history` - Bars up to and including the signal bar
+    fn submit_orders(
+        &mut self,
+        market_data: &ProcessedMarketData,
+        metadata: SignalMetadata,
+        history: &[MarketData],
+    ) {
+        let signal = metadata.signal;
+        let symbol = market_data.raw_data.symbol.clone();
+        let timestamp = market_data.raw_data.timestamp;
         let current_price = market_data.raw_data.price;
 
-        // Calculate trade commission
-        let trade_quantity = self.position_size / current_price;
-        let commission = self.calculate_commission(self.position_size);
+        let (side, direction) = match signal {
+            TradeSignal::Buy => (OrderSide::Buy, PositionType::Long),
+            TradeSignal::Sell => (OrderSide::Sell, PositionType::Short),
+            _ => return,
+        };
+        let current = self.open_position(&symbol);
+        let adding = current
+            .as_ref()
+            .is_some_and(|position| position.position_type == direction);
+        let held = if adding {
+            self.ledger
+                .position(&symbol)
+                .map_or(0.0, |position| position.market_value().abs())
+        } else {
+            0.0
+        };
 
-        match (signal, self.current_position.get(symbol).cloned()) {
-            // Open long position on buy signal if no position exists
-            (TradeSignal::Buy, None) => {
-                let trade = Trade {
-                    entry_time: market_data.raw_data.timestamp,
-                    exit_time: None,
-                    entry_price: current_price,
-                    exit_price: None,
-                    position_type: PositionType::Long,
-                    quantity: trade_quantity,
-                    pnl: None,
-                    strategy_name: format!("{:?}", self.strategy_mode),
-                };
-                self.current_position.insert(symbol.clone(), Some(trade));
+        let context = SizingContext {
+            equity: self.ledger.equity(),
+            price: current_price,
+            history,
+            atr: self.atr.get(history.len() - 1).copied().flatten(),
+            periods_per_year: self.metrics_config.periods_per_year,
+            signal_strength: metadata.strength,
+            trades: &self.trades,
+            other_exposure: self
+                .ledger
+                .positions()
+                .filter(|position| position.symbol != symbol)
+                .map(|position| position.market_value().abs())
+                .sum::<f64>()
+                + held,
+        };
+        let mut notional = self
+            .sizing_limits
+            .apply(self.sizing.notional(&context), &context);
+        if let Some(fraction) = self.sizing_limits.max_position_fraction {
+            // Added entries share the single-position cap with the open lots
+            notional = notional.min(context.equity * fraction - held).max(0.0);
+        }
+        if let Some(margin) = self.margin {
+            notional = notional.min(margin.max_notional(context.equity, context.other_exposure));
+        }
+        let trade_quantity = notional / current_price;
+
+        let entries = self.entry_orders.get(&symbol).map_or(0, HashSet::len);
+        let mut orders = match &current {
+            // Open a position if none exists
+            None => vec![(side, trade_quantity)],
+
+            // Add to the position while pyramiding allows
+            Some(_) if adding => self
+                .pyramiding
+                .next_entry_scale(entries)
+                .map_or_else(Vec::new, |scale| vec![(side, trade_quantity * scale)]),
+
+            // Close the current position and reverse
+            Some(position) if self.pyramiding.closes_fully() => {
+                vec![(side, position.quantity), (side, trade_quantity)]
             }
 
-            // Open short position on sell signal if no position exists
-            (TradeSignal::Sell, None) => {
-                let trade = Trade {
-                    entry_time: market_data.raw_data.timestamp,
-                    exit_time: None,
-                    entry_price: current_price,
-                    exit_price: None,
-                    position_type: PositionType::Short,
-                    quantity: trade_quantity,
-                    pnl: None,
-                    strategy_name: format!("{:?}", self.strategy_mode),
-                };
-                self.current_position.insert(symbol.clone(), Some(trade));
-            }
+            // Scale out of the current position
+            Some(position) => vec![(side, position.quantity * self.pyramiding.exit_fraction)],
+        };
 
-            // Close long position on sell signal and potentially open short
-            (TradeSignal::Sell, Some(Some(trade))) if trade.position_type == PositionType::Long => {
-                let mut closed_trade = trade.clone();
-                closed_trade.exit_time = Some(market_data.raw_data.timestamp);
-                closed_trade.exit_price = Some(current_price);
+        // The sizer may decline to open a new position
+        orders.retain(|(_, quantity)| *quantity > 0.0);
+        if orders.is_empty() {
+            return;
+        }
 
-                // Calculate PnL
-                let entry_value = closed_trade.entry_price * closed_trade.quantity;
-                let exit_value = current_price * closed_trade.quantity;
-                let pnl = exit_value - entry_value - commission;
-                closed_trade.pnl = Some(pnl);
+        // The signal closes the position, so its protective exits are no longer needed
+        if current.is_some() && !adding && self.pyramiding.closes_fully() {
+            self.clear_protection(&symbol, timestamp, "closed by signal");
+        }
 
-                self.trades.push(closed_trade);
+        self.record(
+            timestamp,
+            EventKind::Signal {
+                symbol: symbol.clone(),
+                signal,
+                price: current_price,
+            },
+        );
+        for (side, quantity) in orders {
+            let order = self.exchange.submit(
+                OrderRequest::market(&symbol, side, quantity),
+                timestamp,
+                current_price,
+            );
+            self.order_signals.insert(order.id, metadata.clone());
+            self.record(timestamp, EventKind::OrderSubmitted(order));
+        }
+    }
 
-                // Open new short position
-                let new_trade = Trade {
-                    entry_time: market_data.raw_data.timestamp,
-                    exit_time: None,
-                    entry_price: current_price,
-                    exit_price: None,
-                    position_type: PositionType::Short,
-                    quantity: trade_quantity,
-                    pnl: None,
-                    strategy_name: format!("{:?}", self.strategy_mode),
+    /// Applies an exchange fill to the ledger and the trade list.
+    ///
+    /// Commission is charged on the whole fill. A fill against the open
+    /// position closes its lots in `LotMethod` order, booking each lot's
+    /// realized PnL net of its entry and exit commissions; any remaining
+    /// quantity opens a new position or adds to the existing one.
+    ///
+    /// # Arguments
+    /// * `fill` - Execution reported by the simulated exchange
+    /// * `bar_index` - Index of the bar the fill happened on
+    fn apply_fill(&mut self, fill: &Fill, bar_index: usize) {
+        let exit_reason = self
+            .exit_orders
+            .remove(&fill.order_id)
+            .unwrap_or(ExitReason::Signal);
+        let symbol = fill.symbol.clone();
+        let fill_type = match fill.side {
+            OrderSide::Buy => PositionType::Long,
+            OrderSide::Sell => PositionType::Short,
+        };
+        let mut remaining = fill.quantity;
+        let commission = self.calculate_commission(fill);
+        self.ledger.charge_commission(commission);
+
+        let mut lots = self.current_position.remove(&symbol).unwrap_or_default();
+        let mut scaled = false;
+        if lots
+            .first()
+            .is_some_and(|lot| lot.position_type != fill_type)
+        {
+            let open_quantity: f64 = lots.iter().map(|lot| lot.quantity).sum();
+            let closing = remaining.min(open_quantity);
+            self.ledger
+                .fill(&symbol, fill.side.sign() * closing, fill.price);
+
+            let mut to_close = closing;
+            while to_close > 1e-12 && !lots.is_empty() {
+                let index = match self.lot_method {
+                    LotMethod::Lifo => lots.len() - 1,
+                    LotMethod::AveragePrice | LotMethod::Fifo => 0,
                 };
-                self.current_position
-                    .insert(symbol.clone(), Some(new_trade));
-            }
-
-            // Close short position on buy signal and potentially open long
-            (TradeSignal::Buy, Some(Some(trade))) if trade.position_type == PositionType::Short => {
-                let mut closed_trade = trade.clone();
-                closed_trade.exit_time = Some(market_data.raw_data.timestamp);
-                closed_trade.exit_price = Some(current_price);
-
-                // Calculate PnL
-                let entry_value = closed_trade.entry_price * closed_trade.quantity;
-                let exit_value = current_price * closed_trade.quantity;
-                let pnl = entry_value - exit_value - commission;
+                let lot = &mut lots[index];
+                let closed_quantity = to_close.min(lot.quantity);
+                let direction = match lot.position_type {
+                    PositionType::Long => 1.0,
+                    PositionType::Short => -1.0,
+                };
+                let realized = (fill.price - lot.entry_price) * closed_quantity * direction;
+                let entry_commission = lot.commission * closed_quantity / lot.quantity;
+                let entry_slippage = lot.slippage * closed_quantity / lot.quantity;
+
+                let mut closed_trade = lot.clone();
+                closed_trade.quantity = closed_quantity;
+                closed_trade.commission =
+                    entry_commission + commission * closed_quantity / fill.quantity;
+                closed_trade.slippage =
+                    entry_slippage + fill.slippage * closed_quantity / fill.quantity;
+                closed_trade.exit_time = Some(fill.timestamp);
+                closed_trade.exit_price = Some(fill.price);
+                closed_trade.exit_order_id = Some(fill.order_id);
+                closed_trade.exit_reason = Some(exit_reason);
+                closed_trade.record_excursion(fill.price);
+                if fill.timestamp > closed_trade.entry_time {
+                    closed_trade.bars_held += 1;
+                }
+                let pnl = realized - closed_trade.commission;
                 closed_trade.pnl = Some(pnl);
 
+                lot.quantity -= closed_quantity;
+                lot.commission -= entry_commission;
+                lot.slippage -= entry_slippage;
+                if lot.quantity <= 1e-12 {
+                    lots.remove(index);
+                }
+                to_close -= closed_quantity;
+
+                self.record(
+                    fill.timestamp,
+                    EventKind::PositionClosed {
+                        symbol: symbol.clone(),
+                        order_id: fill.order_id,
+                        position_type: closed_trade.position_type,
+                        quantity: closed_quantity,
+                        price: fill.price,
+                        pnl,
+                        reason: exit_reason,
+                    },
+                );
                 self.trades.push(closed_trade);
+            }
+
+            remaining -= closing;
+            scaled = true;
+            if lots.is_empty() {
+                self.entry_orders.remove(&symbol);
+                self.clear_protection(&symbol, fill.timestamp, "position closed");
+            }
+        }
 
-                // Open new long position
-                let new_trade = Trade {
-                    entry_time: market_data.raw_data.timestamp,
+        let mut opened = false;
+        if remaining > 1e-12 {
+            self.ledger
+                .fill(&symbol, fill.side.sign() * remaining, fill.price);
+            let commission = commission * remaining / fill.quantity;
+            let slippage = fill.slippage * remaining / fill.quantity;
+            opened = lots.is_empty();
+            scaled = !opened;
+            self.entry_orders
+                .entry(symbol.clone())
+                .or_default()
+                .insert(fill.order_id);
+
+            // Average-price positions hold a single lot; otherwise only further
+            // fills of the same entry order join its lot
+            let lot_method = self.lot_method;
+            let lot = lots.iter_mut().find(|lot| {
+                lot_method == LotMethod::AveragePrice || lot.entry_order_id == Some(fill.order_id)
+            });
+            match lot {
+                // Add to the lot at a blended entry price
+                Some(lot) => {
+                    let quantity = lot.quantity + remaining;
+                    lot.entry_price =
+                        (lot.entry_price * lot.quantity + fill.price * remaining) / quantity;
+                    lot.quantity = quantity;
+                    lot.commission += commission;
+                    lot.slippage += slippage;
+                }
+                None => lots.push(Trade {
+                    entry_time: fill.timestamp,
                     exit_time: None,
-                    entry_price: current_price,
+                    entry_price: fill.price,
                     exit_price: None,
-                    position_type: PositionType::Long,
-                    quantity: trade_quantity,
+                    position_type: fill_type,
+                    quantity: remaining,
                     pnl: None,
                     strategy_name: format!("{:?}", self.strategy_mode),
-                };
-                self.current_position
-                    .insert(symbol.clone(), Some(new_trade));
+                    entry_order_id: Some(fill.order_id),
+                    exit_order_id: None,
+                    exit_reason: None,
+                    commission,
+                    slippage,
+                    max_adverse_excursion: 0.0,
+                    max_favorable_excursion: 0.0,
+                    bars_held: 0,
+                    signal: self.order_signals.get(&fill.order_id).cloned(),
+                }),
             }
 
-            // Hold current position
-            _ => {}
+            self.record(
+                fill.timestamp,
+                EventKind::PositionOpened {
+                    symbol: symbol.clone(),
+                    order_id: fill.order_id,
+                    position_type: fill_type,
+                    quantity: remaining,
+                    price: fill.price,
+                },
+            );
+        }
+
+        let open = !lots.is_empty();
+        if open {
+            self.current_position.insert(symbol.clone(), lots);
+        }
+        if opened {
+            self.attach_protection(fill, fill_type, bar_index);
+        } else if open && scaled {
+            self.resize_protection(&symbol, fill.timestamp);
         }
     }
 
-    /// Calculates the current equity based on open positions.
-    ///
-    /// # Arguments
-    /// * `current_price` - The current price of the asset.
-    fn calculate_current_equity(&self, current_price: f64) -> f64 {
-        // Calculate current equity based on open positions
-        let mut current_equity = self.initial_capital;
-
-        for (_, trade_opt) in &self.current_position {
-            if let Some(trade) = trade_opt {
-                let pnl = match trade.position_type {
-                    PositionType::Long => (current_price - trade.entry_price) * trade.quantity,
-                    PositionType::Short => (trade.entry_price - current_price) * trade.quantity,
-                };
-                current_equity += pnl;
+    /// Resizes the working protective exits of `symbol` to the open quantity.
+    fn resize_protection(&mut self, symbol: &str, timestamp: DateTime<Utc>) {
+        let (Some(protection), Some(position)) =
+            (self.protection.get(symbol), self.open_position(symbol))
+        else {
+            return;
+        };
+
+        for id in protection.order_ids.clone() {
+            let unchanged = self
+                .exchange
+                .pending_orders()
+                .iter()
+                .find(|order| order.id == id)
+                .is_none_or(|order| (order.remaining() - position.quantity).abs() <= 1e-12);
+            if unchanged {
+                continue;
+            }
+            if let Some(order) = self.exchange.resize(id, position.quantity) {
+                self.record(timestamp, EventKind::OrderAmended(order));
             }
         }
-
-        current_equity
     }
 
-    /// Calculates the backtest results based on the trades and current equity.
+    /// Places the protective exits of the active `ExitRules` for a new position.
+    ///
+    /// # Arguments
+    /// * `fill` - Fill that opened the position
+    /// * `position_type` - Direction of the new position
+    /// * `bar_index` - Index of the bar the fill happened on
+    fn attach_protection(&mut self, fill: &Fill, position_type: PositionType, bar_index: usize) {
+        let rules = self.active_exit_rules();
+        if rules.is_empty() {
+            return;
+        }
+
+        // Distances use the ATR known when the order was generated
+        let atr = self.atr.get(bar_index.saturating_sub(1)).copied().flatten();
+        let direction = fill.side.sign();
+        let exit_side = match position_type {
+            PositionType::Long => OrderSide::Sell,
+            PositionType::Short => OrderSide::Buy,
+        };
+
+        let mut legs = Vec::new();
+        if let Some(amount) = rules
+            .stop_loss
+            .and_then(|distance| distance.amount(fill.price, atr))
+        {
+            let trigger = fill.price - direction * amount;
+            legs.push((
+                OrderRequest::stop(&fill.symbol, exit_side, fill.quantity, trigger),
+                ExitReason::StopLoss,
+            ));
+        }
+        if let Some(amount) = rules
+            .take_profit
+            .and_then(|distance| distance.amount(fill.price, atr))
+        {
+            let target = fill.price + direction * amount;
+            legs.push((
+                OrderRequest::limit(&fill.symbol, exit_side, fill.quantity, target),
+                ExitReason::TakeProfit,
+            ));
+        }
+        if let Some(distance) = rules.trailing_stop {
+            let trail = match distance {
+                ExitDistance::Percent(fraction) => Some(Trail::Percent(fraction)),
+                ExitDistance::Atr(_) => distance.amount(fill.price, atr).map(Trail::Absolute),
+            };
+            if let Some(trail) = trail {
+                legs.push((
+                    OrderRequest::trailing_stop(&fill.symbol, exit_side, fill.quantity, trail),
+                    ExitReason::TrailingStop,
+                ));
+            }
+        }
+
+        let mut protection = Protection {
+            entry_bar: bar_index,
+            atr,
+            group: None,
+            stop_order: None,
+            order_ids: Vec::new(),
+            break_even_done: false,
+        };
+
+        if !legs.is_empty() {
+            let (requests, reasons): (Vec<_>, Vec<_>) = legs.into_iter().unzip();
+            let orders =
+                self.exchange
+                    .submit_protective(requests, fill.timestamp, fill.price, None);
+            protection.group = orders.first().and_then(|order| order.oco_group);
+
+            for (order, reason) in orders.into_iter().zip(reasons) {
+                if reason == ExitReason::StopLoss {
+                    protection.stop_order = Some(order.id);
+                }
+                protection.order_ids.push(order.id);
+                self.exit_orders.insert(order.id, reason);
+                self.record(fill.timestamp, EventKind::OrderSubmitted(order));
+            }
+        }
+
+        self.protection.insert(fill.symbol.clone(), protection);
+    }
+
+    /// Applies the break-even and time-stop rules at the close of a bar.
+    ///
+    /// Both act on the next bar: the moved stop works from the next bar, and
+    /// the time stop closes the position at the next fill.
+    ///
+    /// # Arguments
+    /// * `bar` - The bar that just closed
+    /// * `bar_index` - Index of the bar
+    fn update_protection(&mut self, bar: &MarketData, bar_index: usize) {
+        let rules = self.active_exit_rules();
+        let Some(trade) = self.open_position(&bar.symbol) else {
+            return;
+        };
+        let Some(protection) = self.protection.get(&bar.symbol).cloned() else {
+            return;
+        };
+        let exit_side = match trade.position_type {
+            PositionType::Long => OrderSide::Sell,
+            PositionType::Short => OrderSide::Buy,
+        };
+
+        if rules
+            .max_bars_held
+            .is_some_and(|bars| bar_index - protection.entry_bar >= bars)
+        {
+            self.clear_protection(&bar.symbol, bar.timestamp, "time stop");
+            let order = self.exchange.submit(
+                OrderRequest::market(&bar.symbol, exit_side, trade.quantity),
+                bar.timestamp,
+                bar.price,
+            );
+            self.exit_orders.insert(order.id, ExitReason::TimeStop);
+            self.record(bar.timestamp, EventKind::OrderSubmitted(order));
+            return;
+        }
+
+        let Some(threshold) = rules
+            .break_even
+            .filter(|_| !protection.break_even_done)
+            .and_then(|distance| distance.amount(trade.entry_price, protection.atr))
+        else {
+            return;
+        };
+        let favourable = match trade.position_type {
+            PositionType::Long => bar.high - trade.entry_price,
+            PositionType::Short => trade.entry_price - bar.low,
+        };
+        if favourable < threshold {
+            return;
+        }
+
+        let break_even = OrderType::Stop {
+            trigger: trade.entry_price,
+        };
+        let current_stop = protection.stop_order.and_then(|id| {
+            self.exchange
+                .pending_orders()
+                .iter()
+                .find(|order| order.id == id)
+                .map(|order| order.order_type)
+        });
+
+        let mut updated = protection.clone();
+        updated.break_even_done = true;
+        match (protection.stop_order, current_stop) {
+            (Some(id), Some(OrderType::Stop { trigger })) => {
+                // Only move the stop if it tightens it
+                let tighter = match trade.position_type {
+                    PositionType::Long => trigger < trade.entry_price,
+                    PositionType::Short => trigger > trade.entry_price,
+                };
+                if tighter {
+                    if let Some(order) = self.exchange.amend(id, break_even) {
+                        self.exit_orders.insert(id, ExitReason::BreakEven);
+                        self.record(bar.timestamp, EventKind::OrderAmended(order));
+                    }
+                }
+            }
+            _ => {
+                let request = OrderRequest::new(&bar.symbol, exit_side, trade.quantity, break_even);
+                let orders = self.exchange.submit_protective(
+                    vec![request],
+                    bar.timestamp,
+                    bar.price,
+                    protection.group,
+                );
+                for order in orders {
+                    updated.group = order.oco_group;
+                    updated.stop_order = Some(order.id);
+                    updated.order_ids.push(order.id);
+                    self.exit_orders.insert(order.id, ExitReason::BreakEven);
+                    self.record(bar.timestamp, EventKind::OrderSubmitted(order));
+                }
+            }
+        }
+        self.protection.insert(bar.symbol.clone(), updated);
+    }
+
+    /// Pays funding on the open position in the bar's symbol.
+    ///
+    /// Every funding timestamp up to the bar is settled against the position
+    /// held coming into the bar, at its last mark price.
+    fn apply_funding(&mut self, bar: &MarketData) {
+        let Some(rates) = self.funding_rates.get(&bar.symbol) else {
+            return;
+        };
+        let cursor = self.funding_cursor.entry(bar.symbol.clone()).or_insert(0);
+        let due: Vec<FundingRate> = rates[*cursor..]
+            .iter()
+            .take_while(|rate| rate.timestamp <= bar.timestamp)
+            .copied()
+            .collect();
+        *cursor += due.len();
+
+        let Some(position) = self.ledger.position(&bar.symbol).cloned() else {
+            return;
+        };
+        for rate in due {
+            let amount = -position.market_value() * rate.rate;
+            self.ledger.apply_funding(amount);
+            self.record(
+                rate.timestamp,
+                EventKind::FundingPaid {
+                    symbol: bar.symbol.clone(),
+                    rate: rate.rate,
+                    amount,
+                },
+            );
+        }
+    }
+
+    /// Charges the carry cost of the position held into the bar since the
+    /// previous bar of the same symbol, and logs the charge.
+    fn accrue_carry(&mut self, bar: &MarketData) {
+        let previous = self.last_bar_time.insert(bar.symbol.clone(), bar.timestamp);
+        let (Some(previous), Some(position)) = (previous, self.ledger.position(&bar.symbol)) else {
+            return;
+        };
+
+        let cost = self.carry.cost(
+            position.market_value(),
+            bar.timestamp - previous,
+            bar.timestamp,
+        );
+        if cost != 0.0 {
+            self.ledger.charge_carry(cost);
+            self.record(
+                bar.timestamp,
+                EventKind::CarryCharged {
+                    symbol: bar.symbol.clone(),
+                    amount: cost,
+                },
+            );
+        }
+    }
+
+    /// Liquidates the position in the bar's symbol if the bar reached its
+    /// liquidation price.
+    ///
+    /// The position is closed at the liquidation price, or at the open if the
+    /// bar gapped through it; isolated positions never lose more than their
+    /// initial margin.
+    ///
+    /// # Arguments
+    /// * `bar` - Bar to check against
+    /// * `bar_index` - Index of the bar
+    fn check_liquidation(&mut self, bar: &MarketData, bar_index: usize) {
+        let Some(margin) = self.margin else {
+            return;
+        };
+        let Some(position) = self.ledger.position(&bar.symbol).cloned() else {
+            return;
+        };
+        let other_maintenance: f64 = self
+            .ledger
+            .positions()
+            .filter(|other| other.symbol != bar.symbol)
+            .map(|other| margin.maintenance_margin(other.market_value()))
+            .sum();
+        let Some(liquidation) =
+            margin.liquidation_price(&position, self.ledger.equity(), other_maintenance)
+        else {
+            return;
+        };
+
+        let long = position.quantity > 0.0;
+        let breached = if long {
+            bar.low <= liquidation
+        } else {
+            bar.high >= liquidation
+        };
+        if !breached {
+            return;
+        }
+
+        let mut price = if long {
+            bar.open.min(liquidation)
+        } else {
+            bar.open.max(liquidation)
+        };
+        if margin.mode == MarginMode::Isolated {
+            let bankruptcy = margin.bankruptcy_price(position.quantity, position.average_price);
+            price = if long {
+                price.max(bankruptcy)
+            } else {
+                price.min(bankruptcy)
+            };
+        }
+
+        let side = if long {
+            OrderSide::Sell
+        } else {
+            OrderSide::Buy
+        };
+        let (order, fill) = self.exchange.execute_immediately(
+            OrderRequest::market(&bar.symbol, side, position.quantity.abs()),
+            bar.timestamp,
+            price,
+        );
+        self.exit_orders.insert(order.id, ExitReason::Liquidation);
+        self.record(bar.timestamp, EventKind::OrderSubmitted(order));
+        self.record(bar.timestamp, EventKind::OrderFilled(fill.clone()));
+        self.apply_fill(&fill, bar_index);
+    }
+
+    /// Cancels the working protective exits of `symbol`.
+    fn clear_protection(&mut self, symbol: &str, timestamp: DateTime<Utc>, reason: &str) {
+        let Some(protection) = self.protection.remove(symbol) else {
+            return;
+        };
+
+        for id in protection.order_ids {
+            self.exit_orders.remove(&id);
+            for order in self.exchange.cancel(id) {
+                self.record(
+                    timestamp,
+                    EventKind::OrderCancelled {
+                        order,
+                        reason: reason.to_string(),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Calculates the backtest results based on the trades and current equity.
     ///
     /// # Returns
     /// A `BacktestResult` struct containing the backtest statistics.
@@ -498,52 +1830,21 @@ impl Backtester {
             0.0
         };
 
-        // Calculate max drawdown using equity curve
-        let mut max_drawdown: f64 = 0.0;
-        let mut peak: f64 = self.initial_capital;
-
-        for point in &self.equity_curve {
-            if point.equity > peak {
-                peak = point.equity;
-            }
-            let drawdown = (peak - point.equity) / peak;
-            max_drawdown = f64::max(max_drawdown, drawdown);
-        }
-
-        // Calculate daily returns for Sharpe Ratio
-        let mut returns: Vec<f64> = Vec::new();
-        if self.equity_curve.len() >= 2 {
-            for window in self.equity_curve.windows(2) {
-                let prev = window[0].equity;
-                let current = window[1].equity;
-                let daily_return = (current - prev) / prev;
-                returns.push(daily_return);
-            }
-        }
-
-        // Calculate annualized Sharpe Ratio
-        let sharpe_ratio = if !returns.is_empty() {
-            let avg_return = returns.iter().sum::<f64>() / returns.len() as f64;
-            let variance = returns
-                .iter()
-                .map(|r| (r - avg_return).powi(2))
-                .sum::<f64>()
-                / returns.len() as f64;
-            let std_dev = variance.sqrt();
-
-            // Annualize metrics (assuming daily data)
-            let annualized_return = avg_return * 252.0; // 252 trading days in a year
-            let annualized_std_dev = std_dev * (252.0_f64).sqrt();
-            let risk_free_rate = 0.02; // 2% annual risk-free rate
-
-            if annualized_std_dev > 0.0 {
-                (annualized_return - risk_free_rate) / annualized_std_dev
-            } else {
-                0.0
-            }
-        } else {
-            0.0
-        };
+        let traded_notional: f64 = self
+            .events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                EventKind::OrderFilled(fill) => Some(fill.quantity * fill.price),
+                _ => None,
+            })
+            .sum();
+        let metrics = PerformanceMetrics::calculate(
+            &self.equity_curve,
+            &self.trades,
+            self.bars_in_market,
+            traded_notional,
+            &self.metrics_config,
+        );
 
         BacktestResult {
             total_trades,
@@ -555,9 +1856,35 @@ impl Backtester {
             average_loss,
             largest_win,
             largest_loss,
-            max_drawdown,
-            sharpe_ratio,
+            max_drawdown: metrics.max_drawdown,
+            sharpe_ratio: metrics.sharpe_ratio,
+            final_equity: self.ledger.equity(),
+            unrealized_pnl: self.ledger.unrealized_pnl(),
+            total_commission: self.ledger.commissions(),
+            total_funding: self.ledger.funding(),
+            total_carry: self.ledger.carry(),
+            total_slippage: self
+                .events
+                .iter()
+                .map(|event| match &event.kind {
+                    EventKind::OrderFilled(fill) => fill.slippage,
+                    _ => 0.0,
+                })
+                .sum(),
             trades: self.trades.clone(),
+            equity_curve: self.equity_curve.clone(),
+            events: self.events.clone(),
+            metrics,
+            benchmark: None,
+            rolling: Vec::new(),
+            trade_analysis: TradeAnalysis::calculate(
+                &self.trades,
+                self.metrics_config.histogram_bins,
+            ),
+            drawdown_periods: drawdown_periods(
+                &self.equity_curve,
+                self.metrics_config.top_drawdowns,
+            ),
         }
     }
 }
@@ -565,7 +1892,15 @@ impl Backtester {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::data::MarketData;
+    use crate::backtesting::FeeTier;
+    use crate::data::{DataProcessor, Scenario};
+
+    fn synthetic_data(scenario: Scenario, bars: usize) -> Vec<ProcessedMarketData> {
+        let mut processor = DataProcessor::new(500);
+        processor
+            .process_batch(scenario.generate(bars, 42))
+            .unwrap()
+    }
 
     fn create_test_data(price: f64, timestamp: DateTime<Utc>) -> ProcessedMarketData {
         ProcessedMarketData {
@@ -576,6 +1911,7 @@ mod tests {
                 volume: 1000.0,
                 high: price + 1.0,
                 low: price - 1.0,
+                open: price,
             },
             moving_average_5: Some(price),
             moving_average_20: Some(price),
@@ -589,16 +1925,9 @@ mod tests {
     fn test_individual_strategies() {
         let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
         backtester.set_strategy_mode(StrategyMode::Rsi);
-        let now = Utc::now();
 
-        // Create test data with a simple price trend
-        let mut market_data = Vec::new();
-        let prices = vec![100.0, 101.0, 102.0, 103.0, 102.0, 101.0, 100.0];
-
-        for (i, &price) in prices.iter().enumerate() {
-            let timestamp = now + chrono::Duration::hours(i as i64);
-            market_data.push(create_test_data(price, timestamp));
-        }
+        // Long enough synthetic series for the RSI window to warm up
+        let market_data = synthetic_data(Scenario::Sideways, 200);
 
         let result = backtester.run_backtest(&market_data);
 
@@ -607,6 +1936,654 @@ mod tests {
         assert!(result.win_rate >= 0.0 && result.win_rate <= 1.0);
     }
 
+    #[test]
+    fn test_strategies_on_stress_scenarios() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+
+        for scenario in [Scenario::Crash, Scenario::Sideways, Scenario::RegimeShifts] {
+            let market_data = synthetic_data(scenario, 365);
+
+            for mode in [
+                StrategyMode::Rsi,
+                StrategyMode::BollingerBands,
+                StrategyMode::Combined,
+            ] {
+                backtester.set_strategy_mode(mode);
+                let result = backtester.run_backtest(&market_data);
+
+                assert!(result.total_pnl.is_finite());
+                assert!(result.sharpe_ratio.is_finite());
+                assert!(result.max_drawdown >= 0.0 && result.max_drawdown <= 1.0);
+                assert_eq!(
+                    result.total_trades,
+                    result.winning_trades + result.losing_trades
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_equity_reconciles_with_trades() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let market_data = synthetic_data(Scenario::Sideways, 200);
+
+        let result = backtester.run_backtest(&market_data);
+        assert!(result.total_trades > 0);
+
+        // Realized PnL of closed trades flows into equity
+        let open_commission: f64 = backtester
+            .current_position
+            .values()
+            .flatten()
+            .map(|trade| trade.commission)
+            .sum();
+        let expected = 10000.0 + result.total_pnl + result.unrealized_pnl + result.total_funding
+            - open_commission;
+        assert!((result.final_equity - expected).abs() < 1e-6);
+        assert_eq!(
+            result.equity_curve.last().unwrap().equity,
+            result.final_equity
+        );
+
+        // Both legs of every trade pay commission
+        let traded_values: f64 = result
+            .events
+            .iter()
+            .filter_map(|event| match &event.kind {
+                EventKind::OrderFilled(fill) => Some(fill.price * fill.quantity),
+                _ => None,
+            })
+            .sum();
+        assert!((result.total_commission - traded_values * 0.001).abs() < 1e-9);
+        for trade in &result.trades {
+            let legs = (trade.entry_price + trade.exit_price.unwrap()) * trade.quantity;
+            assert!((trade.commission - legs * 0.001).abs() < 1e-9);
+        }
+        assert_eq!(result.equity_curve.len(), market_data.len() + 1);
+    }
+
+    #[test]
+    fn test_orders_fill_on_next_bar_open() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let mut market_data = synthetic_data(Scenario::Sideways, 200);
+        // Make the open distinguishable from the previous close
+        for bar in &mut market_data {
+            bar.raw_data.open = bar.raw_data.price * 1.01;
+        }
+
+        let result = backtester.run_backtest(&market_data);
+        assert!(result.total_trades > 0);
+
+        for trade in &result.trades {
+            let entry_bar = market_data
+                .iter()
+                .position(|bar| bar.raw_data.timestamp == trade.entry_time)
+                .unwrap();
+            assert!(entry_bar > 0);
+            assert_eq!(trade.entry_price, market_data[entry_bar].raw_data.open);
+
+            // Submitted on an earlier bar, filled, then opened the position
+            let events = result.trade_events(trade);
+            assert!(matches!(events[0].kind, EventKind::OrderSubmitted(_)));
+            assert!(events[0].timestamp < trade.entry_time);
+            assert!(events
+                .iter()
+                .any(|event| matches!(event.kind, EventKind::PositionClosed { .. })));
+        }
+
+        let timestamps: Vec<_> = result.events.iter().map(|event| event.timestamp).collect();
+        assert!(timestamps.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn test_slippage_is_tracked_separately() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let market_data = synthetic_data(Scenario::Sideways, 200);
+        let frictionless = backtester.run_backtest(&market_data);
+        assert_eq!(frictionless.total_slippage, 0.0);
+
+        backtester.set_slippage_model(SlippageModel::FixedBps { bps: 10.0 });
+        let result = backtester.run_backtest(&market_data);
+        assert!(result.total_trades > 0);
+        assert!(result.total_slippage > 0.0);
+        assert!(result.final_equity < frictionless.final_equity);
+
+        for trade in &result.trades {
+            let entry_bar = market_data
+                .iter()
+                .find(|bar| bar.raw_data.timestamp == trade.entry_time)
+                .unwrap();
+            let direction = match trade.position_type {
+                PositionType::Long => 1.0,
+                PositionType::Short => -1.0,
+            };
+            let expected = entry_bar.raw_data.open * (1.0 + direction * 0.001);
+            assert!((trade.entry_price - expected).abs() < 1e-9);
+            assert!(trade.slippage > 0.0);
+        }
+
+        // Every fill's slippage is attributed to a closed or still open trade
+        let closed: f64 = result.trades.iter().map(|trade| trade.slippage).sum();
+        let open: f64 = backtester
+            .current_position
+            .values()
+            .flatten()
+            .map(|trade| trade.slippage)
+            .sum();
+        assert!((closed + open - result.total_slippage).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fee_schedules() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.0);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        backtester.set_exit_rules(ExitRules {
+            take_profit: Some(ExitDistance::Percent(0.02)),
+            ..Default::default()
+        });
+        let market_data = synthetic_data(Scenario::Sideways, 200);
+
+        // Take-profit limits pay the maker rate, market entries the taker rate
+        backtester.set_fee_schedule(FeeSchedule::maker_taker(0.0, 0.001).with_minimum_fee(0.5));
+        let result = backtester.run_backtest(&market_data);
+        let targets: Vec<&Trade> = result
+            .trades
+            .iter()
+            .filter(|trade| trade.exit_reason == Some(ExitReason::TakeProfit))
+            .collect();
+        assert!(!targets.is_empty());
+        for trade in targets {
+            let taker = (trade.entry_price * trade.quantity * 0.001).max(0.5);
+            assert!((trade.commission - taker - 0.5).abs() < 1e-9);
+        }
+
+        // Higher tiers are reached as trailing volume builds up
+        let tiers = FeeSchedule::tiered(vec![
+            FeeTier {
+                min_volume: 0.0,
+                maker_rate: 0.002,
+                taker_rate: 0.002,
+            },
+            FeeTier {
+                min_volume: 3000.0,
+                maker_rate: 0.001,
+                taker_rate: 0.001,
+            },
+        ]);
+        backtester.set_fee_schedule(tiers);
+        let tiered = backtester.run_backtest(&market_data);
+        backtester.set_fee_schedule(FeeSchedule::flat(0.002));
+        let flat = backtester.run_backtest(&market_data);
+        assert!(tiered.total_commission < flat.total_commission);
+        assert!(tiered.total_commission > flat.total_commission / 2.0);
+    }
+
+    #[test]
+    fn test_margin_liquidation_and_funding() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.0);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        backtester.set_position_sizing(PositionSizing::FixedFractional { fraction: 20.0 });
+        let market_data = synthetic_data(Scenario::Crash, 365);
+
+        // Leverage caps the notional the sizer may open
+        let margin = MarginConfig {
+            mode: MarginMode::Isolated,
+            leverage: 20.0,
+            maintenance_margin_rate: 0.005,
+        };
+        backtester.set_margin(Some(MarginConfig {
+            leverage: 2.0,
+            ..margin
+        }));
+        let capped = backtester.run_backtest(&market_data);
+        let first = &capped.trades[0];
+        assert!(first.entry_price * first.quantity <= 2.0 * 10000.0 + 1e-6);
+
+        backtester.set_margin(Some(margin));
+        let result = backtester.run_backtest(&market_data);
+        let liquidated: Vec<&Trade> = result
+            .trades
+            .iter()
+            .filter(|trade| trade.exit_reason == Some(ExitReason::Liquidation))
+            .collect();
+        assert!(!liquidated.is_empty());
+        for trade in liquidated {
+            let bar = &market_data
+                .iter()
+                .find(|bar| bar.raw_data.timestamp == trade.exit_time.unwrap())
+                .unwrap()
+                .raw_data;
+            let exit_price = trade.exit_price.unwrap();
+            assert!(exit_price >= bar.low - 1e-9 && exit_price <= bar.high + 1e-9);
+
+            // An isolated position loses at most its initial margin
+            let initial_margin = margin.initial_margin(trade.entry_price * trade.quantity);
+            assert!(trade.pnl.unwrap() >= -initial_margin - 1e-6);
+        }
+
+        // Funding is paid at every funding timestamp a position is held through
+        let rates: Vec<FundingRate> = market_data
+            .iter()
+            .map(|bar| FundingRate {
+                timestamp: bar.raw_data.timestamp,
+                rate: 0.0001,
+            })
+            .collect();
+        backtester.set_margin(None);
+        backtester.set_position_sizing(PositionSizing::Fixed { notional: 1000.0 });
+        backtester.set_funding_rates(&market_data[0].raw_data.symbol, rates);
+        let funded = backtester.run_backtest(&market_data);
+        let payments: Vec<f64> = funded
+            .events
+            .iter()
+            .filter_map(|event| match event.kind {
+                EventKind::FundingPaid { amount, .. } => Some(amount),
+                _ => None,
+            })
+            .collect();
+        assert!(!payments.is_empty());
+        assert!((funded.total_funding - payments.iter().sum::<f64>()).abs() < 1e-9);
+        let open_commission: f64 = backtester
+            .current_position
+            .values()
+            .flatten()
+            .map(|trade| trade.commission)
+            .sum();
+        let expected = 10000.0 + funded.total_pnl + funded.unrealized_pnl + funded.total_funding
+            - open_commission;
+        assert!((funded.final_equity - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_carry_costs_accrue_in_ledger() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let market_data = synthetic_data(Scenario::Crash, 365);
+        let free = backtester.run_backtest(&market_data);
+        assert_eq!(free.total_carry, 0.0);
+        assert!(free
+            .trades
+            .iter()
+            .any(|trade| trade.position_type == PositionType::Short));
+
+        // Fixed sizing keeps the trades identical, so equity differs by the carry alone
+        backtester.set_carry_model(CarryModel::short_borrow(0.5));
+        let result = backtester.run_backtest(&market_data);
+        assert!(result.total_carry > 0.0);
+        assert_eq!(result.total_trades, free.total_trades);
+        assert!((free.final_equity - result.final_equity - result.total_carry).abs() < 1e-6);
+        let charges: f64 = result
+            .events
+            .iter()
+            .filter_map(|event| match event.kind {
+                EventKind::CarryCharged { amount, .. } => Some(amount),
+                _ => None,
+            })
+            .sum();
+        assert!((charges - result.total_carry).abs() < 1e-9);
+
+        let open_commission: f64 = backtester
+            .current_position
+            .values()
+            .flatten()
+            .map(|trade| trade.commission)
+            .sum();
+        let expected = 10000.0 + result.total_pnl + result.unrealized_pnl + result.total_funding
+            - result.total_carry
+            - open_commission;
+        assert!((result.final_equity - expected).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_pyramiding_and_lot_accounting() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        backtester.set_pyramiding(Pyramiding {
+            max_entries: 3,
+            entry_scale: 0.5,
+            exit_fraction: 1.0,
+        });
+        let market_data = synthetic_data(Scenario::Sideways, 200);
+
+        let mut results = Vec::new();
+        for method in [LotMethod::AveragePrice, LotMethod::Fifo, LotMethod::Lifo] {
+            backtester.set_lot_method(method);
+            results.push(backtester.run_backtest(&market_data));
+        }
+
+        // Never more than three entries per position
+        let mut open_quantity: f64 = 0.0;
+        let mut entries = 0;
+        for event in &results[1].events {
+            match event.kind {
+                EventKind::PositionOpened { quantity, .. } => {
+                    entries += 1;
+                    open_quantity += quantity;
+                    assert!(entries <= 3);
+                }
+                EventKind::PositionClosed { quantity, .. } => {
+                    open_quantity -= quantity;
+                    if open_quantity <= 1e-9 {
+                        entries = 0;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // One exit fill closes every lot, oldest first under FIFO
+        let fifo = &results[1];
+        let lots_closed_together = fifo.trades.windows(2).any(|pair| {
+            pair[0].exit_order_id == pair[1].exit_order_id
+                && pair[0].entry_time < pair[1].entry_time
+        });
+        assert!(lots_closed_together);
+        assert!(results[2].trades.windows(2).any(|pair| {
+            pair[0].exit_order_id == pair[1].exit_order_id
+                && pair[0].entry_time > pair[1].entry_time
+        }));
+        assert!(results[0].trades.len() < fifo.trades.len());
+
+        // Lot accounting only changes how PnL is attributed
+        for result in &results[1..] {
+            assert!((result.final_equity - results[0].final_equity).abs() < 1e-6);
+        }
+
+        // Scaling out closes each lot in parts
+        backtester.set_pyramiding(Pyramiding {
+            exit_fraction: 0.5,
+            ..Default::default()
+        });
+        let result = backtester.run_backtest(&market_data);
+        let partially_closed = result.trades.windows(2).any(|pair| {
+            pair[0].entry_order_id == pair[1].entry_order_id
+                && pair[0].exit_order_id != pair[1].exit_order_id
+        });
+        assert!(partially_closed);
+
+        // Protective exits follow the position size as it is scaled
+        backtester.set_exit_rules(ExitRules {
+            stop_loss: Some(ExitDistance::Percent(0.05)),
+            ..Default::default()
+        });
+        let result = backtester.run_backtest(&market_data);
+        assert!(result
+            .events
+            .iter()
+            .any(|event| matches!(event.kind, EventKind::OrderAmended(_))));
+    }
+
+    #[test]
+    fn test_configurable_metrics() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let market_data = synthetic_data(Scenario::Sideways, 200);
+
+        let crypto = backtester.run_backtest(&market_data);
+        assert_eq!(crypto.sharpe_ratio, crypto.metrics.sharpe_ratio);
+        assert_eq!(crypto.max_drawdown, crypto.metrics.max_drawdown);
+        assert!(crypto.metrics.exposure > 0.0 && crypto.metrics.exposure <= 1.0);
+        assert!(crypto.metrics.turnover > 0.0);
+
+        backtester.set_metrics_config(MetricsConfig {
+            periods_per_year: 252.0,
+            risk_free_rate: 0.0,
+            ..Default::default()
+        });
+        let equities = backtester.run_backtest(&market_data);
+        assert_ne!(equities.sharpe_ratio, crypto.sharpe_ratio);
+        let mean_return = equities.metrics.annualized_return / 252.0;
+        assert!((crypto.metrics.annualized_return - mean_return * 365.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_benchmark_comparison() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let market_data = synthetic_data(Scenario::Sideways, 200);
+
+        let result = backtester.run_backtest(&market_data);
+        let comparison = result.benchmark.expect("buy-and-hold benchmark by default");
+        let first = market_data.first().unwrap().raw_data.price;
+        let last = market_data.last().unwrap().raw_data.price;
+        assert!((comparison.benchmark_return - (last / first - 1.0)).abs() < 1e-9);
+        assert_eq!(comparison.relative_equity.len(), result.equity_curve.len());
+
+        backtester.set_benchmark(Benchmark::None);
+        assert!(backtester.run_backtest(&market_data).benchmark.is_none());
+    }
+
+    #[test]
+    fn test_underwater_curve_and_drawdown_periods() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let market_data = synthetic_data(Scenario::Crash, 200);
+
+        let result = backtester.run_backtest(&market_data);
+        let mut peak = 0.0_f64;
+        for point in &result.equity_curve {
+            peak = peak.max(point.equity);
+            assert!((point.drawdown - (peak - point.equity) / peak).abs() < 1e-12);
+        }
+        let deepest = result
+            .equity_curve
+            .iter()
+            .map(|point| point.drawdown)
+            .fold(0.0, f64::max);
+        assert!((deepest - result.max_drawdown).abs() < 1e-12);
+
+        assert!(result.drawdown_periods.len() <= 5);
+        if let Some(first) = result.drawdown_periods.first() {
+            assert!((first.depth - result.max_drawdown).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_trade_excursions_and_metadata() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.0);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        backtester.set_exit_rules(ExitRules {
+            stop_loss: Some(ExitDistance::Percent(0.02)),
+            ..Default::default()
+        });
+        let market_data = synthetic_data(Scenario::Crash, 200);
+
+        let result = backtester.run_backtest(&market_data);
+        assert!(!result.trades.is_empty());
+        for trade in &result.trades {
+            let signal = trade.signal.as_ref().expect("entries carry their signal");
+            assert!(signal.timestamp < trade.entry_time);
+            assert!(trade.max_adverse_excursion >= 0.0 && trade.max_favorable_excursion >= 0.0);
+
+            // The exit price lies within the recorded excursions
+            let return_at_exit = trade.net_return().unwrap();
+            assert!(return_at_exit <= trade.max_favorable_excursion + 1e-9);
+            assert!(-return_at_exit <= trade.max_adverse_excursion + 1e-9);
+            if trade.exit_reason == Some(ExitReason::StopLoss) {
+                assert!(trade.max_adverse_excursion >= 0.02 - 1e-9);
+            }
+            if trade.exit_time > Some(trade.entry_time) {
+                assert!(trade.bars_held >= 1);
+            }
+        }
+
+        let analysis = &result.trade_analysis;
+        assert_eq!(analysis.excursions.len(), result.trades.len());
+        let reasons: usize = analysis.exit_reasons.iter().map(|(_, count)| count).sum();
+        assert_eq!(reasons, result.trades.len());
+    }
+
+    #[test]
+    fn test_rolling_metrics_and_strategy_gate() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        let market_data = synthetic_data(Scenario::Sideways, 200);
+
+        backtester.set_rolling_windows(vec![20, 60]);
+        let result = backtester.run_backtest(&market_data);
+        assert_eq!(result.rolling.len(), 2);
+        assert_eq!(
+            result.rolling[0].points.len(),
+            result.equity_curve.len() - 20
+        );
+        assert_eq!(
+            result.rolling[1].points.len(),
+            result.equity_curve.len() - 60
+        );
+        assert!(result.rolling[0]
+            .points
+            .iter()
+            .all(|point| point.beta.is_some()));
+
+        // A gate no strategy can pass switches both off after the first window
+        backtester.set_strategy_gate(Some(StrategyGate {
+            window: 20,
+            min_sharpe: Some(f64::INFINITY),
+            ..Default::default()
+        }));
+        let gated = backtester.run_backtest(&market_data);
+        let cutoff = market_data[20].raw_data.timestamp;
+        assert!(gated.events.iter().all(|event| {
+            !matches!(event.kind, EventKind::Signal { .. }) || event.timestamp < cutoff
+        }));
+        let toggles: Vec<_> = gated
+            .events
+            .iter()
+            .filter_map(|event| match event.kind {
+                EventKind::StrategyToggled { strategy, enabled } => Some((strategy, enabled)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            toggles,
+            vec![
+                (StrategyMode::Rsi, false),
+                (StrategyMode::BollingerBands, false)
+            ]
+        );
+
+        // The gate only applies to combined mode
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let rsi = backtester.run_backtest(&market_data);
+        assert!(rsi
+            .events
+            .iter()
+            .all(|event| !matches!(event.kind, EventKind::StrategyToggled { .. })));
+    }
+
+    #[test]
+    fn test_protective_exits() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        backtester.set_exit_rules(ExitRules {
+            stop_loss: Some(ExitDistance::Percent(0.02)),
+            take_profit: Some(ExitDistance::Percent(0.04)),
+            ..ExitRules::default()
+        });
+        let market_data = synthetic_data(Scenario::RegimeShifts, 365);
+
+        let result = backtester.run_backtest(&market_data);
+        let protective: Vec<_> = result
+            .trades
+            .iter()
+            .filter(|trade| {
+                matches!(
+                    trade.exit_reason,
+                    Some(ExitReason::StopLoss | ExitReason::TakeProfit)
+                )
+            })
+            .collect();
+        assert!(!protective.is_empty());
+        assert!(result
+            .trades
+            .iter()
+            .all(|trade| trade.exit_reason.is_some()));
+
+        for trade in protective {
+            let direction = match trade.position_type {
+                PositionType::Long => 1.0,
+                PositionType::Short => -1.0,
+            };
+            let exit_return = direction * (trade.exit_price.unwrap() / trade.entry_price - 1.0);
+            // Exits fill at their level or, on a gap, at a worse open for stops / better for targets
+            match trade.exit_reason {
+                Some(ExitReason::StopLoss) => assert!(exit_return <= -0.02 + 1e-9),
+                _ => assert!(exit_return >= 0.04 - 1e-9),
+            }
+        }
+    }
+
+    #[test]
+    fn test_time_stop_and_strategy_rules() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        backtester.set_exit_rules(ExitRules {
+            stop_loss: Some(ExitDistance::Atr(100.0)),
+            ..ExitRules::default()
+        });
+        backtester.set_strategy_exit_rules(
+            StrategyMode::Rsi,
+            ExitRules {
+                max_bars_held: Some(3),
+                break_even: Some(ExitDistance::Percent(0.01)),
+                ..ExitRules::default()
+            },
+        );
+        let market_data = synthetic_data(Scenario::Sideways, 200);
+
+        let result = backtester.run_backtest(&market_data);
+        assert!(result
+            .trades
+            .iter()
+            .any(|trade| trade.exit_reason == Some(ExitReason::TimeStop)));
+        for trade in &result.trades {
+            let held = trade.exit_time.unwrap() - trade.entry_time;
+            assert!(held <= chrono::Duration::days(4));
+            assert_ne!(trade.exit_reason, Some(ExitReason::StopLoss));
+        }
+    }
+
+    #[test]
+    fn test_position_sizing_and_limits() {
+        let market_data = synthetic_data(Scenario::RegimeShifts, 365);
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        backtester.set_position_sizing(PositionSizing::FixedFractional { fraction: 0.5 });
+        backtester.set_sizing_limits(SizingLimits {
+            max_position_fraction: Some(0.2),
+            max_leverage: None,
+        });
+
+        let result = backtester.run_backtest(&market_data);
+        assert!(result.total_trades > 0);
+
+        // Each entry is sized to 20% of the equity marked at the signal bar
+        for trade in &result.trades {
+            let signal_point = result
+                .equity_curve
+                .iter()
+                .rev()
+                .find(|point| point.timestamp < trade.entry_time)
+                .unwrap();
+            let signal_bar = market_data
+                .iter()
+                .find(|bar| bar.raw_data.timestamp == signal_point.timestamp)
+                .unwrap();
+            let expected = signal_point.equity * 0.2 / signal_bar.raw_data.price;
+            assert!((trade.quantity - expected).abs() < 1e-9 * expected.max(1.0));
+        }
+
+        // Signal-strength sizing skips signals without conviction
+        backtester.set_position_sizing(PositionSizing::SignalStrength { max_fraction: 0.1 });
+        let result = backtester.run_backtest(&market_data);
+        assert!(result
+            .trades
+            .iter()
+            .all(|trade| trade.quantity * trade.entry_price < 10000.0));
+    }
+
     #[test]
     fn test_combined_strategies() {
         let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
@@ -615,7 +2592,7 @@ mod tests {
 
         // Create test data with a simple price trend
         let mut market_data = Vec::new();
-        let prices = vec![100.0, 101.0, 102.0, 103.0, 102.0, 101.0, 100.0];
+        let prices = [100.0, 101.0, 102.0, 103.0, 102.0, 101.0, 100.0];
 
         for (i, &price) in prices.iter().enumerate() {
             let timestamp = now + chrono::Duration::hours(i as i64);
@@ -635,7 +2612,7 @@ mod tests {
 
         // Create test data
         let mut market_data = Vec::new();
-        let prices = vec![100.0, 101.0, 102.0, 103.0, 102.0, 101.0, 100.0];
+        let prices = [100.0, 101.0, 102.0, 103.0, 102.0, 101.0, 100.0];
 
         for (i, &price) in prices.iter().enumerate() {
             let timestamp = now + chrono::Duration::hours(i as i64);
@@ -653,4 +2630,57 @@ mod tests {
         // Combined mode should generally have fewer trades
         assert!(combined_result.total_trades <= individual_result.total_trades);
     }
+
+    #[test]
+    fn test_set_parameters() {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        for mode in [
+            StrategyMode::Rsi,
+            StrategyMode::BollingerBands,
+            StrategyMode::Combined,
+        ] {
+            for name in mode.parameters() {
+                let value = match *name {
+                    "rsi_oversold" => 30.0,
+                    "rsi_overbought" => 70.0,
+                    "bollinger_exit_fraction" => 0.5,
+                    "bollinger_trend_fast" => 5.0,
+                    "bollinger_trend_slow" => 20.0,
+                    _ => 10.0,
+                };
+                backtester.set_parameter(name, value).unwrap();
+            }
+        }
+
+        // Thresholds are checked together, whatever order they come in
+        backtester.set_parameter("rsi_oversold", 30.0).unwrap();
+        backtester
+            .set_parameters(&[("rsi_oversold", 70.0), ("rsi_overbought", 80.0)])
+            .unwrap();
+        assert!(backtester.set_parameter("rsi_overbought", 70.0).is_err());
+        assert_eq!(backtester.rsi_strategy.overbought_threshold, 80.0);
+        assert!(backtester
+            .set_parameters(&[
+                ("bollinger_trend_fast", 20.0),
+                ("bollinger_trend_slow", 20.0)
+            ])
+            .is_err());
+        backtester
+            .set_parameters(&[
+                ("bollinger_trend_slow", 40.0),
+                ("bollinger_trend_fast", 30.0),
+            ])
+            .unwrap();
+        assert_eq!(backtester.bollinger_bands.trend_fast_period, 30);
+
+        assert!(backtester.set_parameter("rsi_period", 0.4).is_err());
+        assert!(backtester.set_parameter("bollinger_std_dev", -1.0).is_err());
+        assert!(backtester
+            .set_parameter("bollinger_exit_fraction", 1.5)
+            .is_err());
+        assert!(matches!(
+            backtester.set_parameter("lookback", 1.0),
+            Err(OptimizerError::UnknownParameter { .. })
+        ));
+    }
 }
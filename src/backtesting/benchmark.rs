@@ -0,0 +1,303 @@
+use super::backtester::EquityPoint;
+use super::metrics::{mean, period_returns, simple_returns, std_dev, variance, MetricsConfig};
+use crate::data::MarketData;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A value of a custom benchmark series.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkPoint {
+    pub timestamp: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// What a backtest is compared against.
+///
+/// - `None`: No benchmark comparison
+/// - `BuyAndHold`: Holding the first traded symbol from the first bar's close
+/// - `Custom`: Any supplied series, e.g. an index or another asset; its value
+///   at each bar is the last point at or before the bar, and the comparison
+///   starts at the first equity point the series covers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum Benchmark {
+    None,
+    #[default]
+    BuyAndHold,
+    Custom(Vec<BenchmarkPoint>),
+}
+
+/// Strategy and benchmark equity at one point of the equity curve.
+///
+/// # Fields
+/// * `timestamp`: Timestamp of the equity point
+/// * `strategy`: Strategy equity
+/// * `benchmark`: Benchmark value scaled to the strategy's equity where the
+///   comparison starts
+/// * `relative`: Strategy equity over benchmark equity; above 1 when ahead
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelativePoint {
+    pub timestamp: DateTime<Utc>,
+    pub strategy: f64,
+    pub benchmark: f64,
+    pub relative: f64,
+}
+
+/// Performance of a backtest relative to its benchmark.
+///
+/// Statistics use the bar-to-bar returns of both curves and are annualized
+/// with `MetricsConfig::periods_per_year`.
+///
+/// # Fields
+/// * `strategy_return`: Total return of the strategy over the comparison
+/// * `benchmark_return`: Total return of the benchmark over the comparison
+/// * `alpha`: Annualized return not explained by benchmark exposure (Jensen's alpha)
+/// * `beta`: Sensitivity of strategy returns to benchmark returns
+/// * `tracking_error`: Annualized standard deviation of active returns
+/// * `information_ratio`: Annualized active return over tracking error
+/// * `up_capture`: Mean strategy return over mean benchmark return on up bars
+/// * `down_capture`: Mean strategy return over mean benchmark return on down bars
+/// * `relative_equity`: Strategy and benchmark equity over time
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BenchmarkComparison {
+    pub strategy_return: f64,
+    pub benchmark_return: f64,
+    pub alpha: f64,
+    pub beta: f64,
+    pub tracking_error: f64,
+    pub information_ratio: f64,
+    pub up_capture: f64,
+    pub down_capture: f64,
+    pub relative_equity: Vec<RelativePoint>,
+}
+
+impl Benchmark {
+    /// Builds the benchmark equity aligned with a strategy's equity curve.
+    ///
+    /// # Arguments
+    /// * `equity_curve` - Strategy equity before the first bar, then after every bar
+    /// * `bars` - Bars of the backtest, one per equity point after the first
+    ///
+    /// # Returns
+    /// One benchmark value per equity point from the first point the benchmark
+    /// covers, scaled to the strategy's equity there, or `None` without a
+    /// benchmark or usable data
+    pub fn equity_curve(
+        &self,
+        equity_curve: &[EquityPoint],
+        bars: &[MarketData],
+    ) -> Option<Vec<f64>> {
+        let values: Vec<Option<f64>> = match self {
+            Benchmark::None => return None,
+            Benchmark::BuyAndHold => {
+                let symbol = &bars.first()?.symbol;
+                let mut last = None;
+                let held: Vec<Option<f64>> = bars
+                    .iter()
+                    .map(|bar| {
+                        if &bar.symbol == symbol {
+                            last = Some(bar.price);
+                        }
+                        last
+                    })
+                    .collect();
+                std::iter::once(held.first().copied().flatten())
+                    .chain(held)
+                    .collect()
+            }
+            Benchmark::Custom(points) => {
+                let mut points = points.clone();
+                points.sort_by_key(|point| point.timestamp);
+                equity_curve
+                    .iter()
+                    .map(|equity| {
+                        let index =
+                            points.partition_point(|point| point.timestamp <= equity.timestamp);
+                        index.checked_sub(1).map(|index| points[index].value)
+                    })
+                    .collect()
+            }
+        };
+
+        // Start at the first usable value rather than backfilling earlier points
+        let start = values
+            .iter()
+            .position(|value| value.is_some_and(|value| value > 0.0))?;
+        let base = values[start]?;
+        let initial = equity_curve[start].equity;
+        Some(
+            values[start..]
+                .iter()
+                .flatten()
+                .map(|value| initial * value / base)
+                .collect(),
+        )
+    }
+
+    /// Compares a strategy's equity curve with the benchmark.
+    ///
+    /// # Returns
+    /// `None` without a benchmark or usable benchmark data
+    pub fn compare(
+        &self,
+        equity_curve: &[EquityPoint],
+        bars: &[MarketData],
+        config: &MetricsConfig,
+    ) -> Option<BenchmarkComparison> {
+        let benchmark = self.equity_curve(equity_curve, bars)?;
+        let equity_curve = &equity_curve[equity_curve.len() - benchmark.len()..];
+        let periods = config.periods_per_year;
+        let risk_free = config.risk_free_rate / periods;
+
+        let strategy_returns = period_returns(equity_curve);
+        let benchmark_returns = simple_returns(&benchmark);
+        let active: Vec<f64> = strategy_returns
+            .iter()
+            .zip(&benchmark_returns)
+            .map(|(strategy, benchmark)| strategy - benchmark)
+            .collect();
+
+        let mut comparison = BenchmarkComparison::default();
+        let (first, last) = (equity_curve.first()?, equity_curve.last()?);
+        comparison.strategy_return = last.equity / first.equity - 1.0;
+        comparison.benchmark_return = benchmark.last()? / benchmark.first()? - 1.0;
+
+        if !strategy_returns.is_empty() {
+            let strategy_mean = mean(&strategy_returns);
+            let benchmark_mean = mean(&benchmark_returns);
+            comparison.beta = beta(&strategy_returns, &benchmark_returns);
+            comparison.alpha = ((strategy_mean - risk_free)
+                - comparison.beta * (benchmark_mean - risk_free))
+                * periods;
+
+            comparison.tracking_error = std_dev(&active) * periods.sqrt();
+            if comparison.tracking_error > 0.0 {
+                comparison.information_ratio = mean(&active) * periods / comparison.tracking_error;
+            }
+
+            comparison.up_capture = capture(&strategy_returns, &benchmark_returns, |b| b > 0.0);
+            comparison.down_capture = capture(&strategy_returns, &benchmark_returns, |b| b < 0.0);
+        }
+
+        comparison.relative_equity = equity_curve
+            .iter()
+            .zip(&benchmark)
+            .map(|(point, &benchmark)| RelativePoint {
+                timestamp: point.timestamp,
+                strategy: point.equity,
+                benchmark,
+                relative: if benchmark > 0.0 {
+                    point.equity / benchmark
+                } else {
+                    0.0
+                },
+            })
+            .collect();
+
+        Some(comparison)
+    }
+}
+
+/// Beta of `strategy` returns against `benchmark` returns; 0 when the
+/// benchmark does not move.
+pub(crate) fn beta(strategy: &[f64], benchmark: &[f64]) -> f64 {
+    let (strategy_mean, benchmark_mean) = (mean(strategy), mean(benchmark));
+    let covariance = strategy
+        .iter()
+        .zip(benchmark)
+        .map(|(s, b)| (s - strategy_mean) * (b - benchmark_mean))
+        .sum::<f64>()
+        / strategy.len() as f64;
+    let variance = variance(benchmark);
+    if variance > 0.0 {
+        covariance / variance
+    } else {
+        0.0
+    }
+}
+
+/// Mean strategy return over mean benchmark return on the bars selected by `side`.
+fn capture(strategy: &[f64], benchmark: &[f64], side: impl Fn(f64) -> bool) -> f64 {
+    let (strategy, benchmark): (Vec<f64>, Vec<f64>) = strategy
+        .iter()
+        .zip(benchmark)
+        .filter(|(_, benchmark)| side(**benchmark))
+        .map(|(strategy, benchmark)| (*strategy, *benchmark))
+        .unzip();
+    if benchmark.is_empty() {
+        return 0.0;
+    }
+    mean(&strategy) / mean(&benchmark)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::test_support::{curve, daily_bars};
+    use chrono::Duration;
+
+    /// Daily bars from the day after the curve start, so equity point `i + 1`
+    /// is marked at bar `i`.
+    /// Bars closing one day after each equity point but the first.
+    fn bars(prices: &[f64]) -> Vec<MarketData> {
+        daily_bars(prices)
+            .into_iter()
+            .map(|bar| MarketData {
+                timestamp: bar.timestamp + Duration::days(1),
+                ..bar
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_buy_and_hold_comparison() {
+        let bars = bars(&[100.0, 110.0, 99.0, 118.8]);
+        // Half invested in the asset: beta 0.5 and half of every move captured
+        let equity = curve(&[1000.0, 1000.0, 1050.0, 997.5, 1097.25]);
+        let config = MetricsConfig {
+            risk_free_rate: 0.0,
+            ..Default::default()
+        };
+
+        let comparison = Benchmark::BuyAndHold
+            .compare(&equity, &bars, &config)
+            .unwrap();
+        assert!((comparison.benchmark_return - 0.188).abs() < 1e-9);
+        assert!((comparison.beta - 0.5).abs() < 1e-9);
+        assert!(comparison.alpha.abs() < 1e-9);
+        assert!((comparison.up_capture - 0.5).abs() < 1e-9);
+        assert!((comparison.down_capture - 0.5).abs() < 1e-9);
+        assert!(comparison.tracking_error > 0.0 && comparison.information_ratio < 0.0);
+        assert_eq!(comparison.relative_equity.len(), equity.len());
+        assert!((comparison.relative_equity[4].benchmark - 1188.0).abs() < 1e-9);
+
+        assert!(Benchmark::None.compare(&equity, &bars, &config).is_none());
+    }
+
+    #[test]
+    fn test_custom_benchmark_alignment() {
+        let bars = bars(&[100.0, 100.0, 100.0]);
+        let equity = curve(&[1000.0, 1100.0, 1100.0, 1100.0]);
+        let series = Benchmark::Custom(vec![
+            BenchmarkPoint {
+                timestamp: bars[2].timestamp,
+                value: 60.0,
+            },
+            BenchmarkPoint {
+                timestamp: bars[0].timestamp,
+                value: 50.0,
+            },
+        ]);
+
+        // Values carry forward between points, and the series starts at its
+        // first point, scaled to the strategy's equity there
+        let values = series.equity_curve(&equity, &bars).unwrap();
+        assert_eq!(values, vec![1100.0, 1100.0, 1320.0]);
+
+        let config = MetricsConfig::default();
+        let comparison = series.compare(&equity, &bars, &config).unwrap();
+        assert_eq!(comparison.strategy_return, 0.0);
+        assert!((comparison.benchmark_return - 0.2).abs() < 1e-9);
+        assert_eq!(comparison.relative_equity.len(), 3);
+        assert_eq!(comparison.relative_equity[0].timestamp, bars[0].timestamp);
+    }
+}
@@ -0,0 +1,114 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Seconds in the 365-day year annualized rates are quoted over.
+const SECONDS_PER_YEAR: f64 = 365.0 * 24.0 * 60.0 * 60.0;
+
+/// A carry rate charged for holding positions through one bar.
+///
+/// # Fields
+/// * `timestamp`: Timestamp of the bar the rate applies to
+/// * `short_rate`: Cost of holding a short, as a fraction of its notional
+/// * `long_rate`: Cost of holding a long, as a fraction of its notional
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CarryRate {
+    pub timestamp: DateTime<Utc>,
+    pub short_rate: f64,
+    pub long_rate: f64,
+}
+
+/// Cost of holding positions, accrued on every bar a position is held.
+///
+/// - `None`: Positions are free to hold
+/// - `AnnualRate`: Borrow rate on shorts and financing rate on longs, quoted
+///   per 365-day year and accrued for the time between bars
+/// - `PerBar`: Rates charged on the bars listed in the series, e.g. a
+///   per-bar funding or borrow fee history; bars without a rate are free
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum CarryModel {
+    #[default]
+    None,
+    AnnualRate {
+        short_rate: f64,
+        long_rate: f64,
+    },
+    PerBar(Vec<CarryRate>),
+}
+
+impl CarryModel {
+    /// Creates a model charging an annualized borrow rate on shorts only.
+    pub fn short_borrow(annual_rate: f64) -> Self {
+        CarryModel::AnnualRate {
+            short_rate: annual_rate,
+            long_rate: 0.0,
+        }
+    }
+
+    /// Calculates the carry cost of holding a position over one bar.
+    ///
+    /// # Arguments
+    /// * `market_value` - Signed market value of the position held into the bar
+    /// * `elapsed` - Time since the previous bar
+    /// * `timestamp` - Timestamp of the bar
+    ///
+    /// # Returns
+    /// The cost in quote currency; negative rates credit the position
+    pub fn cost(&self, market_value: f64, elapsed: Duration, timestamp: DateTime<Utc>) -> f64 {
+        let rate = match self {
+            CarryModel::None => return 0.0,
+            CarryModel::AnnualRate {
+                short_rate,
+                long_rate,
+            } => {
+                let year_fraction = elapsed.num_seconds().max(0) as f64 / SECONDS_PER_YEAR;
+                Self::side_rate(market_value, *short_rate, *long_rate) * year_fraction
+            }
+            CarryModel::PerBar(rates) => rates
+                .binary_search_by_key(&timestamp, |rate| rate.timestamp)
+                .map_or(0.0, |index| {
+                    Self::side_rate(
+                        market_value,
+                        rates[index].short_rate,
+                        rates[index].long_rate,
+                    )
+                }),
+        };
+
+        market_value.abs() * rate
+    }
+
+    fn side_rate(market_value: f64, short_rate: f64, long_rate: f64) -> f64 {
+        if market_value < 0.0 {
+            short_rate
+        } else {
+            long_rate
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_carry_costs() {
+        let timestamp = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+
+        // 36.5% a year is 0.1% a day on shorts; unlevered longs are free
+        let borrow = CarryModel::short_borrow(0.365);
+        let cost = borrow.cost(-10000.0, Duration::days(1), timestamp);
+        assert!((cost - 10.0).abs() < 1e-9);
+        assert_eq!(borrow.cost(10000.0, Duration::days(1), timestamp), 0.0);
+
+        let series = CarryModel::PerBar(vec![CarryRate {
+            timestamp,
+            short_rate: 0.001,
+            long_rate: -0.0005,
+        }]);
+        assert!((series.cost(-1000.0, Duration::days(1), timestamp) - 1.0).abs() < 1e-9);
+        assert!((series.cost(1000.0, Duration::days(1), timestamp) + 0.5).abs() < 1e-9);
+        let other_bar = timestamp + Duration::days(1);
+        assert_eq!(series.cost(-1000.0, Duration::days(1), other_bar), 0.0);
+    }
+}
@@ -0,0 +1,114 @@
+use super::backtester::EquityPoint;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A period spent below a running equity peak.
+///
+/// # Fields
+/// * `start`: Timestamp of the peak the period started from
+/// * `trough`: Timestamp of the lowest equity in the period
+/// * `recovery`: Timestamp equity regained the peak; `None` if it never did
+/// * `depth`: Fall from the peak to the trough, as a fraction of the peak
+/// * `duration_bars`: Bars from the peak to the recovery (or the end of the curve)
+/// * `duration_days`: Days from the peak to the recovery (or the end of the curve)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrawdownPeriod {
+    pub start: DateTime<Utc>,
+    pub trough: DateTime<Utc>,
+    pub recovery: Option<DateTime<Utc>>,
+    pub depth: f64,
+    pub duration_bars: usize,
+    pub duration_days: f64,
+}
+
+/// Returns the drawdown of `equity` from `peak`, as a fraction of the peak.
+pub(crate) fn drawdown_from_peak(peak: f64, equity: f64) -> f64 {
+    if peak > 0.0 {
+        ((peak - equity) / peak).max(0.0)
+    } else {
+        0.0
+    }
+}
+
+/// Finds the deepest drawdown periods of an equity curve.
+///
+/// # Arguments
+/// * `equity_curve` - Equity over time
+/// * `top_n` - Number of periods to return
+///
+/// # Returns
+/// Up to `top_n` periods, deepest first
+pub fn drawdown_periods(equity_curve: &[EquityPoint], top_n: usize) -> Vec<DrawdownPeriod> {
+    let mut periods = Vec::new();
+    let Some(first) = equity_curve.first() else {
+        return periods;
+    };
+
+    let (mut peak, mut peak_index) = (first.equity, 0);
+    let mut trough_index = 0;
+    let close = |peak_index: usize, trough_index: usize, end: usize, recovered: bool| {
+        let (start, end_point) = (&equity_curve[peak_index], &equity_curve[end]);
+        DrawdownPeriod {
+            start: start.timestamp,
+            trough: equity_curve[trough_index].timestamp,
+            recovery: recovered.then_some(end_point.timestamp),
+            depth: drawdown_from_peak(start.equity, equity_curve[trough_index].equity),
+            duration_bars: end - peak_index,
+            duration_days: (end_point.timestamp - start.timestamp).num_seconds() as f64 / 86_400.0,
+        }
+    };
+
+    for (index, point) in equity_curve.iter().enumerate() {
+        if point.equity >= peak {
+            if trough_index > peak_index {
+                periods.push(close(peak_index, trough_index, index, true));
+            }
+            peak = point.equity;
+            peak_index = index;
+            trough_index = index;
+        } else if point.equity < equity_curve[trough_index].equity {
+            trough_index = index;
+        }
+    }
+    if trough_index > peak_index {
+        periods.push(close(
+            peak_index,
+            trough_index,
+            equity_curve.len() - 1,
+            false,
+        ));
+    }
+
+    periods.sort_by(|a, b| b.depth.total_cmp(&a.depth));
+    periods.truncate(top_n);
+    periods
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::test_support::curve;
+
+    #[test]
+    fn test_drawdown_periods() {
+        let equity = curve(&[100.0, 90.0, 95.0, 100.0, 120.0, 96.0, 110.0]);
+        let periods = drawdown_periods(&equity, 5);
+        assert_eq!(periods.len(), 2);
+
+        // The open 20% drawdown from the 120 peak ranks first
+        assert!((periods[0].depth - 0.2).abs() < 1e-9);
+        assert_eq!(periods[0].start, equity[4].timestamp);
+        assert_eq!(periods[0].trough, equity[5].timestamp);
+        assert_eq!(periods[0].recovery, None);
+        assert_eq!(periods[0].duration_bars, 2);
+
+        assert!((periods[1].depth - 0.1).abs() < 1e-9);
+        assert_eq!(periods[1].trough, equity[1].timestamp);
+        assert_eq!(periods[1].recovery, Some(equity[3].timestamp));
+        assert_eq!(periods[1].duration_bars, 3);
+        assert_eq!(periods[1].duration_days, 3.0);
+
+        assert_eq!(drawdown_periods(&equity, 1).len(), 1);
+        assert!(drawdown_periods(&curve(&[100.0, 110.0]), 5).is_empty());
+    }
+}
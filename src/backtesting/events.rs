@@ -0,0 +1,90 @@
+use super::backtester::{PositionType, StrategyMode, TradeSignal};
+use super::orders::{Fill, Order};
+use super::risk::ExitReason;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What happened at a step of the backtest event loop.
+///
+/// - `Signal`: The strategy produced an actionable signal at the bar close
+/// - `OrderSubmitted`: An order was queued at the simulated exchange
+/// - `OrderAmended`: The execution condition of a working order changed
+/// - `OrderFilled`: The exchange executed an order
+/// - `OrderCancelled`: A pending order was removed without being filled
+/// - `PositionOpened`: A fill opened (or added to) a position
+/// - `PositionClosed`: A fill closed (part of) a position, booking its PnL
+/// - `FundingPaid`: A funding payment on an open position (`amount` is
+///   negative when paid, positive when received)
+/// - `CarryCharged`: Borrow or financing carry accrued on an open position over
+///   a bar (`amount` is the cost, negative when credited)
+/// - `StrategyToggled`: The strategy gate switched a sub-strategy of combined
+///   mode on or off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum EventKind {
+    Signal {
+        symbol: String,
+        signal: TradeSignal,
+        price: f64,
+    },
+    OrderSubmitted(Order),
+    OrderAmended(Order),
+    OrderFilled(Fill),
+    OrderCancelled {
+        order: Order,
+        reason: String,
+    },
+    PositionOpened {
+        symbol: String,
+        order_id: u64,
+        position_type: PositionType,
+        quantity: f64,
+        price: f64,
+    },
+    PositionClosed {
+        symbol: String,
+        order_id: u64,
+        position_type: PositionType,
+        quantity: f64,
+        price: f64,
+        pnl: f64,
+        reason: ExitReason,
+    },
+    FundingPaid {
+        symbol: String,
+        rate: f64,
+        amount: f64,
+    },
+    CarryCharged {
+        symbol: String,
+        amount: f64,
+    },
+    StrategyToggled {
+        strategy: StrategyMode,
+        enabled: bool,
+    },
+}
+
+/// A timestamped entry of the backtest event log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: DateTime<Utc>,
+    pub kind: EventKind,
+}
+
+impl Event {
+    /// Returns the identifier of the order the event refers to, if any.
+    pub fn order_id(&self) -> Option<u64> {
+        match &self.kind {
+            EventKind::Signal { .. }
+            | EventKind::FundingPaid { .. }
+            | EventKind::CarryCharged { .. }
+            | EventKind::StrategyToggled { .. } => None,
+            EventKind::OrderSubmitted(order)
+            | EventKind::OrderAmended(order)
+            | EventKind::OrderCancelled { order, .. } => Some(order.id),
+            EventKind::OrderFilled(fill) => Some(fill.order_id),
+            EventKind::PositionOpened { order_id, .. }
+            | EventKind::PositionClosed { order_id, .. } => Some(*order_id),
+        }
+    }
+}
@@ -0,0 +1,526 @@
+use rand::rngs::StdRng;
+use rand::Rng;
+use rand_distr::StandardNormal;
+use serde::{Deserialize, Serialize};
+
+/// Attempts at drawing a candidate that satisfies the constraints before
+/// keeping an infeasible one.
+const MAX_RESAMPLES: usize = 100;
+
+/// Settings of a genetic algorithm search.
+///
+/// Candidates are positions in the unit hypercube spanned by the parameter
+/// ranges. Each generation keeps the best `elitism` candidates and breeds the
+/// rest from parents chosen by tournament.
+///
+/// # Fields
+/// * `population`: Candidates per generation
+/// * `generations`: Largest number of generations
+/// * `crossover_rate`: Chance two parents are blended rather than copied
+/// * `mutation_rate`: Chance each parameter of a child is mutated
+/// * `mutation_scale`: Standard deviation of a mutation, as a fraction of the range
+/// * `elitism`: Best candidates carried unchanged into the next generation
+/// * `tournament_size`: Candidates competing for each parent slot
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeneticConfig {
+    pub population: usize,
+    pub generations: usize,
+    pub crossover_rate: f64,
+    pub mutation_rate: f64,
+    pub mutation_scale: f64,
+    pub elitism: usize,
+    pub tournament_size: usize,
+}
+
+impl Default for GeneticConfig {
+    fn default() -> Self {
+        Self {
+            population: 30,
+            generations: 40,
+            crossover_rate: 0.9,
+            mutation_rate: 0.2,
+            mutation_scale: 0.1,
+            elitism: 2,
+            tournament_size: 3,
+        }
+    }
+}
+
+/// Settings of a CMA-ES (covariance matrix adaptation evolution strategy) search.
+///
+/// Candidates are drawn from a multivariate normal distribution over the unit
+/// hypercube spanned by the parameter ranges, whose mean, step size and
+/// covariance adapt towards the best candidates of each generation.
+///
+/// # Fields
+/// * `population`: Candidates per generation; 0 uses `4 + 3 ln(n)` for `n` parameters
+/// * `generations`: Largest number of generations
+/// * `initial_step`: Initial standard deviation, as a fraction of the ranges
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CmaEsConfig {
+    pub population: usize,
+    pub generations: usize,
+    pub initial_step: f64,
+}
+
+impl Default for CmaEsConfig {
+    fn default() -> Self {
+        Self {
+            population: 0,
+            generations: 40,
+            initial_step: 0.3,
+        }
+    }
+}
+
+/// Stops an evolutionary search once the best objective stalls.
+///
+/// # Fields
+/// * `patience`: Generations without improvement before stopping
+/// * `min_improvement`: Smallest rise of the best objective counted as improvement
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EarlyStopping {
+    pub patience: usize,
+    pub min_improvement: f64,
+}
+
+impl Default for EarlyStopping {
+    fn default() -> Self {
+        Self {
+            patience: 8,
+            min_improvement: 1e-4,
+        }
+    }
+}
+
+/// Progress of an evolutionary search.
+///
+/// # Fields
+/// * `convergence`: Best objective found after each generation
+/// * `stopped_early`: Whether early stopping ended the search
+pub(crate) struct Evolution {
+    pub convergence: Vec<f64>,
+    pub stopped_early: bool,
+}
+
+impl Evolution {
+    fn new() -> Self {
+        Self {
+            convergence: Vec::new(),
+            stopped_early: false,
+        }
+    }
+
+    /// Records a generation's best objective and returns `true` if the search should stop.
+    fn record(&mut self, best: f64, early_stopping: Option<EarlyStopping>) -> bool {
+        let previous = self.convergence.last().copied();
+        self.convergence
+            .push(previous.map_or(best, |previous| previous.max(best)));
+
+        let Some(early_stopping) = early_stopping else {
+            return false;
+        };
+        let generations = self.convergence.len();
+        if generations <= early_stopping.patience {
+            return false;
+        }
+        let before = self.convergence[generations - 1 - early_stopping.patience];
+        let stalled = self.convergence[generations - 1] - before <= early_stopping.min_improvement;
+        self.stopped_early = stalled;
+        stalled
+    }
+}
+
+/// Draws candidates until one is feasible, keeping the last one otherwise.
+fn sample_feasible(
+    rng: &mut StdRng,
+    mut sample: impl FnMut(&mut StdRng) -> Vec<f64>,
+    feasible: &impl Fn(&[f64]) -> bool,
+) -> Vec<f64> {
+    let mut candidate = sample(rng);
+    for _ in 1..MAX_RESAMPLES {
+        if feasible(&candidate) {
+            break;
+        }
+        candidate = sample(rng);
+    }
+    candidate
+}
+
+/// Runs a genetic algorithm over the unit hypercube, maximizing the objective.
+///
+/// # Arguments
+/// * `config` - Population and operator settings
+/// * `dimensions` - Number of parameters
+/// * `rng` - Seeded random number generator
+/// * `early_stopping` - Optional stall criterion
+/// * `feasible` - Whether a candidate satisfies the constraints
+/// * `evaluate` - Scores a generation of candidates
+pub(crate) fn genetic<E>(
+    config: &GeneticConfig,
+    dimensions: usize,
+    rng: &mut StdRng,
+    early_stopping: Option<EarlyStopping>,
+    feasible: impl Fn(&[f64]) -> bool,
+    mut evaluate: impl FnMut(&[Vec<f64>]) -> Result<Vec<f64>, E>,
+) -> Result<Evolution, E> {
+    let mut evolution = Evolution::new();
+    let size = config.population.max(2);
+    let mut population: Vec<Vec<f64>> = (0..size)
+        .map(|_| {
+            sample_feasible(
+                rng,
+                |rng| (0..dimensions).map(|_| rng.gen::<f64>()).collect(),
+                &feasible,
+            )
+        })
+        .collect();
+
+    for _ in 0..config.generations {
+        let scores = evaluate(&population)?;
+        let mut ranked: Vec<(Vec<f64>, f64)> = population.into_iter().zip(scores).collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        if evolution.record(ranked[0].1, early_stopping) {
+            break;
+        }
+
+        // Ranked best first, so the lowest drawn index wins
+        let tournament = |rng: &mut StdRng| {
+            (0..config.tournament_size.max(1))
+                .map(|_| rng.gen_range(0..ranked.len()))
+                .min()
+                .unwrap_or(0)
+        };
+        let mut next: Vec<Vec<f64>> = ranked
+            .iter()
+            .take(config.elitism.min(size))
+            .map(|(candidate, _)| candidate.clone())
+            .collect();
+        while next.len() < size {
+            let child = sample_feasible(
+                rng,
+                |rng| {
+                    let (first, second) = (tournament(rng), tournament(rng));
+                    let blend = rng.gen::<f64>() < config.crossover_rate;
+                    ranked[first]
+                        .0
+                        .iter()
+                        .zip(&ranked[second].0)
+                        .map(|(&a, &b)| {
+                            let mut gene = if blend {
+                                let weight = rng.gen::<f64>();
+                                a * weight + b * (1.0 - weight)
+                            } else {
+                                a
+                            };
+                            if rng.gen::<f64>() < config.mutation_rate {
+                                let step: f64 = rng.sample(StandardNormal);
+                                gene += step * config.mutation_scale;
+                            }
+                            gene.clamp(0.0, 1.0)
+                        })
+                        .collect()
+                },
+                &feasible,
+            );
+            next.push(child);
+        }
+        population = next;
+    }
+
+    Ok(evolution)
+}
+
+/// Runs CMA-ES over the unit hypercube, maximizing the objective.
+///
+/// Follows Hansen's tutorial defaults for the learning rates. Candidates are
+/// clipped to the hypercube before they are scored.
+///
+/// # Arguments
+/// * `config` - Population, generations and initial step size
+/// * `dimensions` - Number of parameters
+/// * `rng` - Seeded random number generator
+/// * `early_stopping` - Optional stall criterion
+/// * `feasible` - Whether a candidate satisfies the constraints
+/// * `evaluate` - Scores a generation of candidates
+pub(crate) fn cma_es<E>(
+    config: &CmaEsConfig,
+    dimensions: usize,
+    rng: &mut StdRng,
+    early_stopping: Option<EarlyStopping>,
+    feasible: impl Fn(&[f64]) -> bool,
+    mut evaluate: impl FnMut(&[Vec<f64>]) -> Result<Vec<f64>, E>,
+) -> Result<Evolution, E> {
+    let mut evolution = Evolution::new();
+    let n = dimensions as f64;
+    let lambda = if config.population > 0 {
+        config.population.max(2)
+    } else {
+        4 + (3.0 * n.ln()).floor() as usize
+    };
+    let mu = lambda / 2;
+    let weights: Vec<f64> = {
+        let raw: Vec<f64> = (1..=mu)
+            .map(|i| (mu as f64 + 0.5).ln() - (i as f64).ln())
+            .collect();
+        let total: f64 = raw.iter().sum();
+        raw.iter().map(|weight| weight / total).collect()
+    };
+    let mu_eff = 1.0 / weights.iter().map(|weight| weight * weight).sum::<f64>();
+
+    let c_sigma = (mu_eff + 2.0) / (n + mu_eff + 5.0);
+    let d_sigma = 1.0 + 2.0 * (((mu_eff - 1.0) / (n + 1.0)).sqrt() - 1.0).max(0.0) + c_sigma;
+    let c_c = (4.0 + mu_eff / n) / (n + 4.0 + 2.0 * mu_eff / n);
+    let c_1 = 2.0 / ((n + 1.3).powi(2) + mu_eff);
+    let c_mu = (1.0 - c_1).min(2.0 * (mu_eff - 2.0 + 1.0 / mu_eff) / ((n + 2.0).powi(2) + mu_eff));
+    let chi_n = n.sqrt() * (1.0 - 1.0 / (4.0 * n) + 1.0 / (21.0 * n * n));
+
+    let mut mean = vec![0.5; dimensions];
+    let mut sigma = config.initial_step;
+    let mut covariance = identity(dimensions);
+    let mut basis = identity(dimensions);
+    let mut scales = vec![1.0; dimensions];
+    let mut path_sigma = vec![0.0; dimensions];
+    let mut path_c = vec![0.0; dimensions];
+
+    for generation in 0..config.generations {
+        let candidates: Vec<Vec<f64>> = (0..lambda)
+            .map(|_| {
+                sample_feasible(
+                    rng,
+                    |rng| {
+                        let z: Vec<f64> = (0..dimensions)
+                            .map(|_| rng.sample(StandardNormal))
+                            .collect();
+                        let scaled: Vec<f64> = z.iter().zip(&scales).map(|(z, d)| z * d).collect();
+                        let y = mat_vec(&basis, &scaled);
+                        mean.iter()
+                            .zip(&y)
+                            .map(|(m, y)| (m + sigma * y).clamp(0.0, 1.0))
+                            .collect()
+                    },
+                    &feasible,
+                )
+            })
+            .collect();
+        let scores = evaluate(&candidates)?;
+
+        let mut order: Vec<usize> = (0..lambda).collect();
+        order.sort_by(|&a, &b| scores[b].total_cmp(&scores[a]));
+        if evolution.record(scores[order[0]], early_stopping) {
+            break;
+        }
+
+        // Steps of the selected candidates, in units of sigma
+        let steps: Vec<Vec<f64>> = order[..mu]
+            .iter()
+            .map(|&index| {
+                candidates[index]
+                    .iter()
+                    .zip(&mean)
+                    .map(|(x, m)| (x - m) / sigma)
+                    .collect()
+            })
+            .collect();
+        let step_mean: Vec<f64> = (0..dimensions)
+            .map(|d| weights.iter().zip(&steps).map(|(w, y)| w * y[d]).sum())
+            .collect();
+        for (m, y) in mean.iter_mut().zip(&step_mean) {
+            *m += sigma * y;
+        }
+
+        // C^(-1/2) y = B D^-1 B^T y
+        let rotated = mat_vec(&transpose(&basis), &step_mean);
+        let whitened: Vec<f64> = rotated.iter().zip(&scales).map(|(y, d)| y / d).collect();
+        let whitened = mat_vec(&basis, &whitened);
+        let sigma_rate = (c_sigma * (2.0 - c_sigma) * mu_eff).sqrt();
+        for (path, y) in path_sigma.iter_mut().zip(&whitened) {
+            *path = (1.0 - c_sigma) * *path + sigma_rate * y;
+        }
+        let path_sigma_norm = path_sigma.iter().map(|p| p * p).sum::<f64>().sqrt();
+        let h_sigma = path_sigma_norm
+            / (1.0 - (1.0 - c_sigma).powi(2 * (generation as i32 + 1))).sqrt()
+            < (1.4 + 2.0 / (n + 1.0)) * chi_n;
+        let h_sigma = if h_sigma { 1.0 } else { 0.0 };
+        let c_rate = (c_c * (2.0 - c_c) * mu_eff).sqrt();
+        for (path, y) in path_c.iter_mut().zip(&step_mean) {
+            *path = (1.0 - c_c) * *path + h_sigma * c_rate * y;
+        }
+
+        for i in 0..dimensions {
+            for j in 0..dimensions {
+                let rank_one =
+                    path_c[i] * path_c[j] + (1.0 - h_sigma) * c_c * (2.0 - c_c) * covariance[i][j];
+                let rank_mu: f64 = weights
+                    .iter()
+                    .zip(&steps)
+                    .map(|(w, y)| w * y[i] * y[j])
+                    .sum();
+                covariance[i][j] =
+                    (1.0 - c_1 - c_mu) * covariance[i][j] + c_1 * rank_one + c_mu * rank_mu;
+            }
+        }
+        sigma *= ((c_sigma / d_sigma) * (path_sigma_norm / chi_n - 1.0)).exp();
+        sigma = sigma.min(1.0);
+
+        let (values, vectors) = symmetric_eigen(&covariance);
+        scales = values.iter().map(|value| value.max(1e-20).sqrt()).collect();
+        basis = vectors;
+    }
+
+    Ok(evolution)
+}
+
+fn identity(size: usize) -> Vec<Vec<f64>> {
+    (0..size)
+        .map(|i| (0..size).map(|j| if i == j { 1.0 } else { 0.0 }).collect())
+        .collect()
+}
+
+fn transpose(matrix: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    (0..matrix.len())
+        .map(|j| matrix.iter().map(|row| row[j]).collect())
+        .collect()
+}
+
+fn mat_vec(matrix: &[Vec<f64>], vector: &[f64]) -> Vec<f64> {
+    matrix
+        .iter()
+        .map(|row| row.iter().zip(vector).map(|(a, b)| a * b).sum())
+        .collect()
+}
+
+/// Eigenvalues and eigenvectors (as columns) of a symmetric matrix, by Jacobi rotations.
+fn symmetric_eigen(matrix: &[Vec<f64>]) -> (Vec<f64>, Vec<Vec<f64>>) {
+    let size = matrix.len();
+    let mut a = matrix.to_vec();
+    let mut vectors = identity(size);
+
+    for _ in 0..100 {
+        let off_diagonal: f64 = (0..size)
+            .flat_map(|i| (0..size).filter(move |&j| j != i).map(move |j| (i, j)))
+            .map(|(i, j)| a[i][j] * a[i][j])
+            .sum();
+        if off_diagonal < 1e-22 {
+            break;
+        }
+        for p in 0..size {
+            for q in p + 1..size {
+                if a[p][q].abs() < 1e-30 {
+                    continue;
+                }
+                let theta = (a[q][q] - a[p][p]) / (2.0 * a[p][q]);
+                let t = theta.signum() / (theta.abs() + (theta * theta + 1.0).sqrt());
+                let c = 1.0 / (t * t + 1.0).sqrt();
+                let s = t * c;
+                for row in a.iter_mut() {
+                    let (akp, akq) = (row[p], row[q]);
+                    row[p] = c * akp - s * akq;
+                    row[q] = s * akp + c * akq;
+                }
+                let (row_p, row_q) = (a[p].clone(), a[q].clone());
+                for (k, (apk, aqk)) in row_p.iter().zip(&row_q).enumerate() {
+                    a[p][k] = c * apk - s * aqk;
+                    a[q][k] = s * apk + c * aqk;
+                }
+                for row in vectors.iter_mut() {
+                    let (vkp, vkq) = (row[p], row[q]);
+                    row[p] = c * vkp - s * vkq;
+                    row[q] = s * vkp + c * vkq;
+                }
+            }
+        }
+    }
+
+    ((0..size).map(|i| a[i][i]).collect(), vectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    /// Concave objective peaking at (0.7, 0.2).
+    fn score(candidates: &[Vec<f64>]) -> Result<Vec<f64>, ()> {
+        Ok(candidates
+            .iter()
+            .map(|x| -((x[0] - 0.7).powi(2) + (x[1] - 0.2).powi(2)))
+            .collect())
+    }
+
+    #[test]
+    fn test_symmetric_eigen() {
+        let matrix = vec![vec![2.0, 1.0], vec![1.0, 2.0]];
+        let (values, vectors) = symmetric_eigen(&matrix);
+        let mut sorted = values.clone();
+        sorted.sort_by(f64::total_cmp);
+        assert!((sorted[0] - 1.0).abs() < 1e-9 && (sorted[1] - 3.0).abs() < 1e-9);
+
+        // A v = lambda v for every column
+        for (column, value) in values.iter().enumerate() {
+            let v: Vec<f64> = vectors.iter().map(|row| row[column]).collect();
+            let av = mat_vec(&matrix, &v);
+            assert!(av.iter().zip(&v).all(|(a, v)| (a - value * v).abs() < 1e-9));
+        }
+    }
+
+    #[test]
+    fn test_searches_converge() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let genetic = genetic(
+            &GeneticConfig::default(),
+            2,
+            &mut rng,
+            None,
+            |_| true,
+            score,
+        )
+        .unwrap();
+        assert_eq!(genetic.convergence.len(), 40);
+        assert!(genetic
+            .convergence
+            .windows(2)
+            .all(|pair| pair[1] >= pair[0]));
+        assert!(*genetic.convergence.last().unwrap() > -1e-3);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let cma_es = cma_es(&CmaEsConfig::default(), 2, &mut rng, None, |_| true, score).unwrap();
+        assert!(*cma_es.convergence.last().unwrap() > -1e-4);
+    }
+
+    #[test]
+    fn test_early_stopping_and_constraints() {
+        // A flat objective never improves after the first generation
+        let mut rng = StdRng::seed_from_u64(42);
+        let early_stopping = EarlyStopping {
+            patience: 3,
+            min_improvement: 0.0,
+        };
+        let flat = genetic(
+            &GeneticConfig::default(),
+            2,
+            &mut rng,
+            Some(early_stopping),
+            |_| true,
+            |candidates: &[Vec<f64>]| Ok::<_, ()>(vec![1.0; candidates.len()]),
+        )
+        .unwrap();
+        assert!(flat.stopped_early);
+        assert_eq!(flat.convergence.len(), 4);
+
+        // Every scored candidate keeps the first coordinate below the second
+        let mut rng = StdRng::seed_from_u64(42);
+        cma_es(
+            &CmaEsConfig::default(),
+            2,
+            &mut rng,
+            None,
+            |x| x[0] < x[1],
+            |candidates: &[Vec<f64>]| {
+                assert!(candidates.iter().all(|x| x[0] < x[1]));
+                score(candidates)
+            },
+        )
+        .unwrap();
+    }
+}
@@ -0,0 +1,908 @@
+use super::orders::{Fill, Liquidity, Order, OrderRequest, OrderSide, OrderType, TimeInForce};
+use super::slippage::SlippageModel;
+use crate::data::MarketData;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Quantities below this are treated as fully filled.
+const QUANTITY_EPSILON: f64 = 1e-12;
+
+/// An order that would execute on the current bar.
+struct Candidate {
+    id: u64,
+    side: OrderSide,
+    price: f64,
+    quantity: f64,
+    oco_group: Option<u64>,
+    is_stop: bool,
+    takes_liquidity: bool,
+}
+
+impl Candidate {
+    /// Returns `true` if `self` is assumed to execute before `other` of the same group.
+    ///
+    /// Stops beat targets, and among stops the one with the worse price wins.
+    fn precedes(&self, other: &Candidate) -> bool {
+        let adverse = |candidate: &Candidate| candidate.side.sign() * candidate.price;
+        match (self.is_stop, other.is_stop) {
+            (true, false) => true,
+            (false, true) => false,
+            _ if adverse(self) != adverse(other) => adverse(self) > adverse(other),
+            _ => self.id < other.id,
+        }
+    }
+}
+
+/// Determines the price at which pending market orders are filled.
+///
+/// Orders are never filled on the bar that generated them, so neither option
+/// looks ahead:
+/// - `NextOpen`: Fill at the open of the next bar
+/// - `NextClose`: Fill at the close of the next bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FillTiming {
+    #[default]
+    NextOpen,
+    NextClose,
+}
+
+/// Outcome of matching pending orders against one bar.
+///
+/// # Fields
+/// * `fills`: Executions, in the order they were matched
+/// * `cancelled`: Orders removed without being completely filled, with the reason
+#[derive(Debug, Clone, Default)]
+pub struct BarExecution {
+    pub fills: Vec<Fill>,
+    pub cancelled: Vec<(Order, String)>,
+}
+
+/// Simulated exchange matching orders against subsequent bars.
+///
+/// Orders are queued on submission and only become eligible for execution on
+/// a later bar of the same symbol. Market orders fill according to
+/// `FillTiming`; limit and stop orders fill against the bar's high and low.
+///
+/// One-cancels-other groups are resolved conservatively: if a stop and a
+/// target of the same group would both fill on one bar, only the stop fills,
+/// and of several stops the one with the worst price fills.
+///
+/// # Fields
+/// * `fill_timing`: Price used to fill market orders
+/// * `max_participation`: Optional cap on the fraction of bar volume an order may fill
+/// * `slippage`: Price concession applied to fills that take liquidity
+/// * `pending`: Orders waiting to be filled, in submission order
+/// * `next_order_id`: Identifier assigned to the next submitted order
+/// * `current_bar`: Symbol and timestamp of the bar last processed
+/// * `evaluated`: Orders already matched against `current_bar`
+#[derive(Debug, Clone)]
+pub struct SimulatedExchange {
+    fill_timing: FillTiming,
+    max_participation: Option<f64>,
+    slippage: SlippageModel,
+    pending: Vec<Order>,
+    next_order_id: u64,
+    current_bar: Option<(String, DateTime<Utc>)>,
+    evaluated: HashSet<u64>,
+}
+
+impl Default for SimulatedExchange {
+    fn default() -> Self {
+        Self::new(FillTiming::default())
+    }
+}
+
+impl SimulatedExchange {
+    /// Creates an exchange with no pending orders.
+    pub fn new(fill_timing: FillTiming) -> Self {
+        Self {
+            fill_timing,
+            max_participation: None,
+            slippage: SlippageModel::None,
+            pending: Vec::new(),
+            next_order_id: 1,
+            current_bar: None,
+            evaluated: HashSet::new(),
+        }
+    }
+
+    /// Returns the fill timing used for market orders.
+    pub fn fill_timing(&self) -> FillTiming {
+        self.fill_timing
+    }
+
+    /// Sets the fill timing used for market orders.
+    pub fn set_fill_timing(&mut self, fill_timing: FillTiming) {
+        self.fill_timing = fill_timing;
+    }
+
+    /// Limits each fill to a fraction of the bar's volume.
+    ///
+    /// With a cap, orders may fill partially: IOC orders cancel the remainder,
+    /// FOK orders are cancelled unless they fill completely, and GTC/GTD orders
+    /// keep working on later bars.
+    ///
+    /// # Arguments
+    /// * `fraction` - Maximum fraction of bar volume per fill, or `None` for no cap
+    pub fn set_max_participation(&mut self, fraction: Option<f64>) {
+        self.max_participation = fraction;
+    }
+
+    /// Sets the slippage model applied to market, stop and trailing stop fills.
+    pub fn set_slippage_model(&mut self, slippage: SlippageModel) {
+        self.slippage = slippage;
+    }
+
+    /// Removes all pending orders and restarts order numbering.
+    pub fn reset(&mut self) {
+        self.pending.clear();
+        self.next_order_id = 1;
+        self.current_bar = None;
+        self.evaluated.clear();
+    }
+
+    /// Returns the orders waiting to be filled.
+    pub fn pending_orders(&self) -> &[Order] {
+        &self.pending
+    }
+
+    /// Queues an order generated on the bar at `timestamp`.
+    ///
+    /// # Arguments
+    /// * `request` - Order parameters
+    /// * `timestamp` - Timestamp of the bar that generated the order
+    /// * `reference_price` - Price trailing stops start trailing from
+    ///
+    /// # Returns
+    /// The accepted order with its assigned identifier
+    pub fn submit(
+        &mut self,
+        request: OrderRequest,
+        timestamp: DateTime<Utc>,
+        reference_price: f64,
+    ) -> Order {
+        self.accept(request, timestamp, reference_price, None, None, false)
+    }
+
+    /// Queues protective exits for a position filled on the bar at `timestamp`.
+    ///
+    /// The orders share a one-cancels-other group, or join `group` if given.
+    /// With `FillTiming::NextOpen` the position was entered at the bar's open,
+    /// so the exits are already matched against the rest of that bar when
+    /// `process_bar` is called for it again.
+    ///
+    /// # Returns
+    /// The accepted orders
+    pub fn submit_protective(
+        &mut self,
+        requests: Vec<OrderRequest>,
+        timestamp: DateTime<Utc>,
+        reference_price: f64,
+        group: Option<u64>,
+    ) -> Vec<Order> {
+        let group = Some(group.unwrap_or(self.next_order_id));
+        let immediate = self.fill_timing == FillTiming::NextOpen;
+
+        requests
+            .into_iter()
+            .map(|request| self.accept(request, timestamp, reference_price, group, None, immediate))
+            .collect()
+    }
+
+    /// Replaces the execution condition of a pending order.
+    ///
+    /// # Returns
+    /// The amended order, or `None` if `order_id` is not pending
+    pub fn amend(&mut self, order_id: u64, order_type: OrderType) -> Option<Order> {
+        let order = self.pending.iter_mut().find(|order| order.id == order_id)?;
+        order.order_type = order_type;
+        order.triggered = false;
+        Some(order.clone())
+    }
+
+    /// Changes the quantity a pending order still has to fill.
+    ///
+    /// # Returns
+    /// The resized order, or `None` if `order_id` is not pending
+    pub fn resize(&mut self, order_id: u64, remaining: f64) -> Option<Order> {
+        let order = self.pending.iter_mut().find(|order| order.id == order_id)?;
+        order.quantity = order.filled_quantity + remaining;
+        Some(order.clone())
+    }
+
+    /// Queues two orders that cancel each other once either fills.
+    pub fn submit_oco(
+        &mut self,
+        first: OrderRequest,
+        second: OrderRequest,
+        timestamp: DateTime<Utc>,
+        reference_price: f64,
+    ) -> Vec<Order> {
+        let group = Some(self.next_order_id);
+        vec![
+            self.accept(first, timestamp, reference_price, group, None, false),
+            self.accept(second, timestamp, reference_price, group, None, false),
+        ]
+    }
+
+    /// Queues an entry order with a take-profit limit and a stop-loss attached.
+    ///
+    /// The exits are one-cancels-other and only start working once the entry
+    /// has completed, sized to the quantity it actually filled. If the entry is
+    /// cancelled without filling, the exits are cancelled with it.
+    ///
+    /// # Returns
+    /// The entry, take-profit and stop-loss orders
+    pub fn submit_bracket(
+        &mut self,
+        entry: OrderRequest,
+        take_profit: f64,
+        stop_loss: f64,
+        timestamp: DateTime<Utc>,
+        reference_price: f64,
+    ) -> Vec<Order> {
+        let exit_side = match entry.side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let target = OrderRequest::limit(&entry.symbol, exit_side, entry.quantity, take_profit);
+        let stop = OrderRequest::stop(&entry.symbol, exit_side, entry.quantity, stop_loss);
+
+        let entry = self.accept(entry, timestamp, reference_price, None, None, false);
+        let group = Some(self.next_order_id);
+        let parent = Some(entry.id);
+        vec![
+            entry,
+            self.accept(target, timestamp, reference_price, group, parent, false),
+            self.accept(stop, timestamp, reference_price, group, parent, false),
+        ]
+    }
+
+    /// Executes an order immediately at `price`, bypassing the order book.
+    ///
+    /// Used for forced liquidations, which the venue executes rather than the
+    /// strategy. The fill takes liquidity but no slippage model applies.
+    ///
+    /// # Returns
+    /// The executed order and its fill
+    pub fn execute_immediately(
+        &mut self,
+        request: OrderRequest,
+        timestamp: DateTime<Utc>,
+        price: f64,
+    ) -> (Order, Fill) {
+        let mut order = self.accept(request, timestamp, price, None, None, false);
+        self.pending.retain(|pending| pending.id != order.id);
+        order.filled_quantity = order.quantity;
+
+        let fill = Fill {
+            order_id: order.id,
+            symbol: order.symbol.clone(),
+            side: order.side,
+            quantity: order.quantity,
+            price,
+            timestamp,
+            slippage: 0.0,
+            liquidity: Liquidity::Taker,
+        };
+        (order, fill)
+    }
+
+    fn accept(
+        &mut self,
+        request: OrderRequest,
+        timestamp: DateTime<Utc>,
+        reference_price: f64,
+        oco_group: Option<u64>,
+        parent_id: Option<u64>,
+        immediate: bool,
+    ) -> Order {
+        let order = Order {
+            id: self.next_order_id,
+            symbol: request.symbol,
+            side: request.side,
+            quantity: request.quantity,
+            order_type: request.order_type,
+            time_in_force: request.time_in_force,
+            submitted_at: timestamp,
+            filled_quantity: 0.0,
+            oco_group,
+            parent_id,
+            triggered: false,
+            immediate,
+            trail_reference: matches!(request.order_type, OrderType::TrailingStop { .. })
+                .then_some(reference_price),
+        };
+        self.next_order_id += 1;
+        self.pending.push(order.clone());
+        order
+    }
+
+    /// Cancels a pending order together with any bracket exits attached to it.
+    ///
+    /// # Returns
+    /// The cancelled orders, empty if `order_id` is not pending
+    pub fn cancel(&mut self, order_id: u64) -> Vec<Order> {
+        let mut cancelled = Vec::new();
+        self.pending.retain(|order| {
+            let matched = order.id == order_id || order.parent_id == Some(order_id);
+            if matched {
+                cancelled.push(order.clone());
+            }
+            !matched
+        });
+        cancelled
+    }
+
+    /// Removes and returns every pending order.
+    pub fn cancel_all(&mut self) -> Vec<Order> {
+        std::mem::take(&mut self.pending)
+    }
+
+    /// Matches pending orders of `bar.symbol` submitted before `bar.timestamp`.
+    ///
+    /// Bracket exits activated by an entry filled on this bar are matched
+    /// against the same bar. Calling this again for the same bar only matches
+    /// orders submitted since, such as protective exits for a fill at the open.
+    pub fn process_bar(&mut self, bar: &MarketData) -> BarExecution {
+        let mut execution = BarExecution::default();
+
+        let bar_key = (bar.symbol.clone(), bar.timestamp);
+        if self.current_bar.as_ref() != Some(&bar_key) {
+            self.current_bar = Some(bar_key);
+            self.evaluated.clear();
+        }
+
+        let expired = self.remove_where(|order| {
+            order.symbol == bar.symbol
+                && matches!(order.time_in_force, TimeInForce::Gtd(expiry) if bar.timestamp > expiry)
+        });
+        for order in expired {
+            self.retire(order, Some("expired"), &mut execution);
+        }
+
+        loop {
+            let pending_ids: HashSet<u64> = self.pending.iter().map(|order| order.id).collect();
+            let mut evaluated_now = HashSet::new();
+            let mut candidates = Vec::new();
+
+            for order in self.pending.iter_mut() {
+                let eligible = order.symbol == bar.symbol
+                    && (order.submitted_at < bar.timestamp
+                        || (order.immediate && order.submitted_at == bar.timestamp))
+                    && !self.evaluated.contains(&order.id)
+                    && !order
+                        .parent_id
+                        .is_some_and(|parent| pending_ids.contains(&parent));
+                if !eligible {
+                    continue;
+                }
+                self.evaluated.insert(order.id);
+                evaluated_now.insert(order.id);
+
+                let Some(price) = execution_price(order, bar, self.fill_timing) else {
+                    continue;
+                };
+                let quantity = match self.max_participation {
+                    Some(fraction) => order.remaining().min(bar.volume * fraction),
+                    None => order.remaining(),
+                };
+                if quantity <= 0.0
+                    || (order.time_in_force == TimeInForce::Fok
+                        && quantity < order.remaining() - QUANTITY_EPSILON)
+                {
+                    continue;
+                }
+                candidates.push(Candidate {
+                    id: order.id,
+                    side: order.side,
+                    price,
+                    quantity,
+                    oco_group: order.oco_group,
+                    is_stop: order.order_type.is_stop(),
+                    takes_liquidity: order.order_type.takes_liquidity(),
+                });
+            }
+
+            if evaluated_now.is_empty() {
+                break;
+            }
+
+            // Only one order of a one-cancels-other group fills per bar
+            let winners = candidates.iter().filter(|candidate| {
+                !candidates.iter().any(|other| {
+                    candidate.oco_group.is_some()
+                        && other.oco_group == candidate.oco_group
+                        && other.id != candidate.id
+                        && other.precedes(candidate)
+                })
+            });
+
+            let mut filled_groups = HashSet::new();
+            for candidate in winners {
+                if let Some(order) = self
+                    .pending
+                    .iter_mut()
+                    .find(|order| order.id == candidate.id)
+                {
+                    let slippage = if candidate.takes_liquidity {
+                        self.slippage
+                            .cost_per_unit(candidate.price, candidate.quantity, bar)
+                    } else {
+                        0.0
+                    };
+                    order.filled_quantity += candidate.quantity;
+                    execution.fills.push(Fill {
+                        order_id: order.id,
+                        symbol: order.symbol.clone(),
+                        side: order.side,
+                        quantity: candidate.quantity,
+                        price: candidate.price + order.side.sign() * slippage,
+                        timestamp: bar.timestamp,
+                        slippage: slippage * candidate.quantity,
+                        liquidity: if candidate.takes_liquidity {
+                            Liquidity::Taker
+                        } else {
+                            Liquidity::Maker
+                        },
+                    });
+                }
+                if let Some(group) = candidate.oco_group {
+                    filled_groups.insert((group, candidate.id));
+                }
+            }
+
+            let mut finished = Vec::new();
+            self.pending.retain(|order| {
+                let reason = if order.remaining() <= QUANTITY_EPSILON {
+                    Some(None)
+                } else if order.oco_group.is_some_and(|group| {
+                    filled_groups
+                        .iter()
+                        .any(|(filled, id)| *filled == group && *id != order.id)
+                }) {
+                    Some(Some("OCO sibling filled"))
+                } else if evaluated_now.contains(&order.id) {
+                    match order.time_in_force {
+                        TimeInForce::Ioc => Some(Some("IOC remainder not filled")),
+                        TimeInForce::Fok => Some(Some("FOK could not be filled in full")),
+                        _ => None,
+                    }
+                } else {
+                    None
+                };
+
+                match reason {
+                    Some(reason) => {
+                        finished.push((order.clone(), reason));
+                        false
+                    }
+                    None => true,
+                }
+            });
+
+            for (order, reason) in finished {
+                self.retire(order, reason, &mut execution);
+            }
+        }
+
+        execution
+    }
+
+    /// Handles an order leaving the book, activating or cancelling its bracket exits.
+    ///
+    /// `cancel_reason` is `None` for orders that left the book completely filled.
+    fn retire(&mut self, order: Order, cancel_reason: Option<&str>, execution: &mut BarExecution) {
+        if order.filled_quantity > QUANTITY_EPSILON {
+            // Exits protect what the entry actually filled
+            for child in self
+                .pending
+                .iter_mut()
+                .filter(|child| child.parent_id == Some(order.id))
+            {
+                child.quantity = order.filled_quantity;
+            }
+        } else {
+            for child in self.remove_where(|child| child.parent_id == Some(order.id)) {
+                execution
+                    .cancelled
+                    .push((child, "bracket entry cancelled".to_string()));
+            }
+        }
+
+        if let Some(reason) = cancel_reason {
+            execution.cancelled.push((order, reason.to_string()));
+        }
+    }
+
+    fn remove_where(&mut self, predicate: impl Fn(&Order) -> bool) -> Vec<Order> {
+        let mut removed = Vec::new();
+        self.pending.retain(|order| {
+            if predicate(order) {
+                removed.push(order.clone());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+}
+
+/// Returns the price `order` executes at on `bar`, if it executes at all.
+///
+/// Updates the trigger state of stop-limit orders and the reference price of
+/// trailing stops as a side effect. Trailing stops are checked against the
+/// stop level from previous bars before this bar's extreme moves it.
+fn execution_price(order: &mut Order, bar: &MarketData, timing: FillTiming) -> Option<f64> {
+    let side = order.side;
+
+    match order.order_type {
+        OrderType::Market => Some(match timing {
+            FillTiming::NextOpen => bar.open,
+            FillTiming::NextClose => bar.price,
+        }),
+        OrderType::Limit { price } => limit_execution(side, price, bar),
+        OrderType::Stop { trigger } => stop_execution(side, trigger, bar),
+        OrderType::StopLimit { trigger, limit } => {
+            if order.triggered {
+                return limit_execution(side, limit, bar);
+            }
+
+            let triggered_at = stop_execution(side, trigger, bar)?;
+            order.triggered = true;
+
+            if triggered_at == bar.open {
+                // Gapped through the trigger: a working limit for the whole bar
+                limit_execution(side, limit, bar)
+            } else {
+                // Triggered intrabar: only fill if the trigger is within the limit
+                let marketable = match side {
+                    OrderSide::Buy => trigger <= limit,
+                    OrderSide::Sell => trigger >= limit,
+                };
+                marketable.then_some(trigger)
+            }
+        }
+        OrderType::TrailingStop { trail } => {
+            let reference = order.trail_reference.unwrap_or(bar.open);
+            let execution = stop_execution(side, trail.stop_price(reference, side), bar);
+
+            order.trail_reference = Some(match side {
+                OrderSide::Buy => reference.min(bar.low),
+                OrderSide::Sell => reference.max(bar.high),
+            });
+
+            execution
+        }
+    }
+}
+
+/// Fills a limit at the open if the bar opens through it, else at the limit if touched.
+fn limit_execution(side: OrderSide, limit: f64, bar: &MarketData) -> Option<f64> {
+    match side {
+        OrderSide::Buy if bar.open <= limit => Some(bar.open),
+        OrderSide::Buy if bar.low <= limit => Some(limit),
+        OrderSide::Sell if bar.open >= limit => Some(bar.open),
+        OrderSide::Sell if bar.high >= limit => Some(limit),
+        _ => None,
+    }
+}
+
+/// Fills a stop at the open if the bar gaps through it, else at the trigger if touched.
+fn stop_execution(side: OrderSide, trigger: f64, bar: &MarketData) -> Option<f64> {
+    match side {
+        OrderSide::Buy if bar.open >= trigger => Some(bar.open),
+        OrderSide::Buy if bar.high >= trigger => Some(trigger),
+        OrderSide::Sell if bar.open <= trigger => Some(bar.open),
+        OrderSide::Sell if bar.low <= trigger => Some(trigger),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::orders::Trail;
+    use crate::backtesting::test_support::{daily_bars, start};
+    use chrono::Duration;
+
+    fn timestamp(day: i64) -> DateTime<Utc> {
+        start() + Duration::days(day)
+    }
+
+    fn bar(day: i64, open: f64, close: f64) -> MarketData {
+        ohlc(
+            day,
+            open,
+            open.max(close) + 1.0,
+            open.min(close) - 1.0,
+            close,
+        )
+    }
+
+    fn ohlc(day: i64, open: f64, high: f64, low: f64, close: f64) -> MarketData {
+        let [bar] = daily_bars(&[close]).try_into().unwrap();
+        MarketData {
+            timestamp: timestamp(day),
+            high,
+            low,
+            open,
+            ..bar
+        }
+    }
+
+    #[test]
+    fn test_orders_fill_on_next_bar() {
+        let mut exchange = SimulatedExchange::new(FillTiming::NextOpen);
+        let signal_bar = bar(0, 100.0, 101.0);
+        let order = exchange.submit(
+            OrderRequest::market("SOL", OrderSide::Buy, 2.0),
+            signal_bar.timestamp,
+            101.0,
+        );
+        assert_eq!(order.id, 1);
+
+        // Never filled on the bar that generated it
+        assert!(exchange.process_bar(&signal_bar).fills.is_empty());
+        assert_eq!(exchange.pending_orders().len(), 1);
+
+        let fills = exchange.process_bar(&bar(1, 102.0, 99.0)).fills;
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].order_id, 1);
+        assert_eq!(fills[0].price, 102.0);
+        assert_eq!(fills[0].signed_quantity(), 2.0);
+        assert!(exchange.pending_orders().is_empty());
+    }
+
+    #[test]
+    fn test_slippage_applies_to_taker_fills() {
+        let mut exchange = SimulatedExchange::new(FillTiming::NextOpen);
+        exchange.set_slippage_model(SlippageModel::FixedBps { bps: 50.0 });
+        exchange.submit(
+            OrderRequest::market("SOL", OrderSide::Sell, 2.0),
+            timestamp(0),
+            100.0,
+        );
+        exchange.submit(
+            OrderRequest::limit("SOL", OrderSide::Buy, 2.0, 99.0),
+            timestamp(0),
+            100.0,
+        );
+
+        let fills = exchange.process_bar(&bar(1, 100.0, 98.0)).fills;
+        assert_eq!(fills.len(), 2);
+        assert_eq!(fills[0].price, 99.5);
+        assert_eq!(fills[0].slippage, 1.0);
+        assert_eq!(fills[1].price, 99.0);
+        assert_eq!(fills[1].slippage, 0.0);
+        assert_eq!(fills[0].liquidity, Liquidity::Taker);
+        assert_eq!(fills[1].liquidity, Liquidity::Maker);
+    }
+
+    #[test]
+    fn test_next_close_timing_and_cancellation() {
+        let mut exchange = SimulatedExchange::new(FillTiming::NextClose);
+        exchange.submit(
+            OrderRequest::market("SOL", OrderSide::Sell, 1.0),
+            timestamp(0),
+            101.0,
+        );
+        exchange.submit(
+            OrderRequest::market("ETH", OrderSide::Buy, 1.0),
+            timestamp(0),
+            101.0,
+        );
+
+        let fills = exchange.process_bar(&bar(1, 102.0, 99.0)).fills;
+        assert_eq!(fills.len(), 1);
+        assert_eq!(fills[0].price, 99.0);
+        assert_eq!(fills[0].signed_quantity(), -1.0);
+
+        let cancelled = exchange.cancel_all();
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].symbol, "ETH");
+    }
+
+    #[test]
+    fn test_limit_and_stop_fills_use_bar_range() {
+        let mut exchange = SimulatedExchange::default();
+        exchange.submit(
+            OrderRequest::limit("SOL", OrderSide::Buy, 1.0, 95.0),
+            timestamp(0),
+            100.0,
+        );
+        exchange.submit(
+            OrderRequest::stop("SOL", OrderSide::Buy, 1.0, 104.0),
+            timestamp(0),
+            100.0,
+        );
+        exchange.submit(
+            OrderRequest::limit("SOL", OrderSide::Sell, 1.0, 90.0),
+            timestamp(0),
+            100.0,
+        );
+
+        // Low touches the buy limit; the stop is not reached; the sell limit is below the open
+        let fills = exchange
+            .process_bar(&ohlc(1, 100.0, 103.0, 94.0, 101.0))
+            .fills;
+        let prices: Vec<_> = fills
+            .iter()
+            .map(|fill| (fill.order_id, fill.price))
+            .collect();
+        assert_eq!(prices, vec![(1, 95.0), (3, 100.0)]);
+
+        // Gap above the stop fills at the open, not the trigger
+        let fills = exchange
+            .process_bar(&ohlc(2, 108.0, 110.0, 107.0, 109.0))
+            .fills;
+        assert_eq!(fills[0].order_id, 2);
+        assert_eq!(fills[0].price, 108.0);
+    }
+
+    #[test]
+    fn test_stop_limit_and_trailing_stop() {
+        let mut exchange = SimulatedExchange::default();
+        exchange.submit(
+            OrderRequest::stop_limit("SOL", OrderSide::Sell, 1.0, 95.0, 96.0),
+            timestamp(0),
+            100.0,
+        );
+        exchange.submit(
+            OrderRequest::trailing_stop("SOL", OrderSide::Sell, 1.0, Trail::Percent(0.1)),
+            timestamp(0),
+            100.0,
+        );
+
+        // Trigger trades intrabar below the limit: triggered but not filled
+        let execution = exchange.process_bar(&ohlc(1, 100.0, 120.0, 94.0, 110.0));
+        assert!(execution.fills.is_empty());
+        assert!(exchange.pending_orders()[0].triggered);
+        assert_eq!(exchange.pending_orders()[1].trail_reference, Some(120.0));
+
+        // Now a working sell limit at 96, and the trailing stop sits at 108
+        let fills = exchange
+            .process_bar(&ohlc(2, 110.0, 111.0, 105.0, 106.0))
+            .fills;
+        let prices: Vec<_> = fills
+            .iter()
+            .map(|fill| (fill.order_id, fill.price))
+            .collect();
+        assert_eq!(prices, vec![(1, 110.0), (2, 108.0)]);
+    }
+
+    #[test]
+    fn test_time_in_force() {
+        let mut exchange = SimulatedExchange::default();
+        exchange.set_max_participation(Some(0.001));
+        exchange.submit(
+            OrderRequest::market("SOL", OrderSide::Buy, 3.0).with_time_in_force(TimeInForce::Ioc),
+            timestamp(0),
+            100.0,
+        );
+        exchange.submit(
+            OrderRequest::market("SOL", OrderSide::Buy, 3.0).with_time_in_force(TimeInForce::Fok),
+            timestamp(0),
+            100.0,
+        );
+        exchange.submit(
+            OrderRequest::limit("SOL", OrderSide::Buy, 1.0, 50.0)
+                .with_time_in_force(TimeInForce::Gtd(timestamp(1))),
+            timestamp(0),
+            100.0,
+        );
+        exchange.submit(
+            OrderRequest::market("SOL", OrderSide::Buy, 3.0),
+            timestamp(0),
+            100.0,
+        );
+
+        // One unit of volume available per order and bar
+        let execution = exchange.process_bar(&bar(1, 100.0, 101.0));
+        let fills: Vec<_> = execution
+            .fills
+            .iter()
+            .map(|fill| (fill.order_id, fill.quantity))
+            .collect();
+        assert_eq!(fills, vec![(1, 1.0), (4, 1.0)]);
+        let cancelled: Vec<_> = execution
+            .cancelled
+            .iter()
+            .map(|(order, _)| order.id)
+            .collect();
+        assert_eq!(cancelled, vec![1, 2]);
+
+        // The GTD order expires, the GTC order keeps filling
+        let execution = exchange.process_bar(&bar(2, 100.0, 101.0));
+        assert_eq!(execution.cancelled[0].0.id, 3);
+        assert_eq!(execution.cancelled[0].1, "expired");
+        assert_eq!(execution.fills[0].order_id, 4);
+        assert_eq!(exchange.pending_orders()[0].filled_quantity, 2.0);
+    }
+
+    #[test]
+    fn test_bracket_and_oco() {
+        let mut exchange = SimulatedExchange::default();
+        let orders = exchange.submit_bracket(
+            OrderRequest::market("SOL", OrderSide::Buy, 2.0),
+            110.0,
+            95.0,
+            timestamp(0),
+            100.0,
+        );
+        assert_eq!(orders[1].parent_id, Some(1));
+        assert_eq!(orders[1].oco_group, orders[2].oco_group);
+
+        // Entry fills at the open; both exits are inside the bar, the stop wins
+        let execution = exchange.process_bar(&ohlc(1, 100.0, 112.0, 94.0, 105.0));
+        let fills: Vec<_> = execution
+            .fills
+            .iter()
+            .map(|fill| (fill.order_id, fill.price, fill.side))
+            .collect();
+        assert_eq!(
+            fills,
+            vec![(1, 100.0, OrderSide::Buy), (3, 95.0, OrderSide::Sell)]
+        );
+        assert_eq!(execution.cancelled[0].0.id, 2);
+        assert!(exchange.pending_orders().is_empty());
+
+        // Plain OCO: the target fills and cancels the stop
+        exchange.submit_oco(
+            OrderRequest::limit("SOL", OrderSide::Sell, 1.0, 110.0),
+            OrderRequest::stop("SOL", OrderSide::Sell, 1.0, 90.0),
+            timestamp(1),
+            100.0,
+        );
+        let execution = exchange.process_bar(&ohlc(2, 100.0, 111.0, 99.0, 105.0));
+        assert_eq!(execution.fills[0].price, 110.0);
+        assert_eq!(execution.cancelled[0].1, "OCO sibling filled");
+
+        // Cancelling a bracket entry takes its exits with it
+        exchange.submit_bracket(
+            OrderRequest::limit("SOL", OrderSide::Buy, 1.0, 50.0),
+            110.0,
+            40.0,
+            timestamp(2),
+            100.0,
+        );
+        assert_eq!(exchange.cancel(6).len(), 3);
+    }
+
+    #[test]
+    fn test_protective_orders_match_entry_bar() {
+        let mut exchange = SimulatedExchange::default();
+        exchange.submit(
+            OrderRequest::market("SOL", OrderSide::Buy, 1.0),
+            timestamp(0),
+            100.0,
+        );
+        let entry_bar = ohlc(1, 100.0, 101.0, 90.0, 95.0);
+        let fills = exchange.process_bar(&entry_bar).fills;
+        assert_eq!(fills[0].price, 100.0);
+
+        // Exits attached to the fill at the open are matched against the rest of the bar
+        let orders = exchange.submit_protective(
+            vec![
+                OrderRequest::stop("SOL", OrderSide::Sell, 1.0, 97.0),
+                OrderRequest::stop("SOL", OrderSide::Sell, 1.0, 95.0),
+            ],
+            entry_bar.timestamp,
+            100.0,
+            None,
+        );
+        assert!(orders.iter().all(|order| order.immediate));
+        assert!(exchange
+            .amend(orders[0].id, OrderType::Stop { trigger: 96.0 })
+            .is_some());
+
+        // Both stops are inside the bar: the worse price fills
+        let execution = exchange.process_bar(&entry_bar);
+        assert_eq!(execution.fills.len(), 1);
+        assert_eq!(execution.fills[0].order_id, orders[1].id);
+        assert_eq!(execution.fills[0].price, 95.0);
+        assert!(exchange.process_bar(&entry_bar).fills.is_empty());
+    }
+}
@@ -0,0 +1,220 @@
+use super::orders::Liquidity;
+use serde::{Deserialize, Serialize};
+
+/// Fee rates that apply from a trailing traded volume upwards.
+///
+/// # Fields
+/// * `min_volume`: Trailing traded notional at which the tier starts
+/// * `maker_rate`: Fee rate for fills that added liquidity
+/// * `taker_rate`: Fee rate for fills that took liquidity
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeTier {
+    pub min_volume: f64,
+    pub maker_rate: f64,
+    pub taker_rate: f64,
+}
+
+/// Currency fees are paid in.
+///
+/// Fees are always booked in the quote currency of the ledger; the currency
+/// determines how they are valued.
+///
+/// - `Quote`: Paid in the quote currency
+/// - `Token`: Paid in an exchange token (e.g. BNB) at a `discount` to the
+///   quoted rate
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum FeeCurrency {
+    #[default]
+    Quote,
+    Token {
+        symbol: String,
+        discount: f64,
+    },
+}
+
+/// Exchange fee schedule charged on every fill, entry and exit alike.
+///
+/// Limit and stop-limit fills pay the maker rate, market and stop fills the
+/// taker rate. The tier is chosen by the notional traded over the trailing
+/// `volume_window_days`, plus `external_volume` traded outside the backtest.
+///
+/// The exchange presets mirror the published schedules at the time of writing;
+/// check them against the exchange before relying on exact rates.
+///
+/// # Fields
+/// * `tiers`: Fee tiers, ordered by `min_volume`
+/// * `minimum_fee`: Smallest fee charged per fill, in quote currency
+/// * `currency`: Currency fees are paid in
+/// * `volume_window_days`: Length of the trailing volume window
+/// * `external_volume`: Trailing volume traded outside the backtest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeSchedule {
+    pub tiers: Vec<FeeTier>,
+    pub minimum_fee: f64,
+    pub currency: FeeCurrency,
+    pub volume_window_days: i64,
+    pub external_volume: f64,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self::flat(0.0)
+    }
+}
+
+impl FeeSchedule {
+    /// Creates a schedule with a single rate for makers and takers.
+    pub fn flat(rate: f64) -> Self {
+        Self::maker_taker(rate, rate)
+    }
+
+    /// Creates a single-tier schedule with separate maker and taker rates.
+    pub fn maker_taker(maker_rate: f64, taker_rate: f64) -> Self {
+        Self::tiered(vec![FeeTier {
+            min_volume: 0.0,
+            maker_rate,
+            taker_rate,
+        }])
+    }
+
+    /// Creates a volume-tiered schedule, ordering the tiers by `min_volume`.
+    pub fn tiered(mut tiers: Vec<FeeTier>) -> Self {
+        tiers.sort_by(|a, b| a.min_volume.total_cmp(&b.min_volume));
+        Self {
+            tiers,
+            minimum_fee: 0.0,
+            currency: FeeCurrency::Quote,
+            volume_window_days: 30,
+            external_volume: 0.0,
+        }
+    }
+
+    /// Sets the smallest fee charged per fill.
+    pub fn with_minimum_fee(mut self, minimum_fee: f64) -> Self {
+        self.minimum_fee = minimum_fee;
+        self
+    }
+
+    /// Sets the currency fees are paid in.
+    pub fn with_currency(mut self, currency: FeeCurrency) -> Self {
+        self.currency = currency;
+        self
+    }
+
+    /// Binance spot, regular user and VIP 1-4 tiers.
+    pub fn binance_spot() -> Self {
+        Self::tiered(vec![
+            tier(0.0, 0.0010, 0.0010),
+            tier(1_000_000.0, 0.0009, 0.0010),
+            tier(5_000_000.0, 0.0008, 0.0010),
+            tier(20_000_000.0, 0.0007, 0.0009),
+            tier(75_000_000.0, 0.0007, 0.0008),
+        ])
+    }
+
+    /// Binance spot paying fees in BNB at a 25% discount.
+    pub fn binance_spot_bnb() -> Self {
+        Self::binance_spot().with_currency(FeeCurrency::Token {
+            symbol: "BNB".to_string(),
+            discount: 0.25,
+        })
+    }
+
+    /// Binance USD-M futures, regular user and VIP 1-4 tiers.
+    pub fn binance_futures() -> Self {
+        Self::tiered(vec![
+            tier(0.0, 0.0002, 0.0005),
+            tier(15_000_000.0, 0.00016, 0.0004),
+            tier(50_000_000.0, 0.00014, 0.00035),
+            tier(100_000_000.0, 0.00012, 0.00032),
+            tier(600_000_000.0, 0.0001, 0.0003),
+        ])
+    }
+
+    /// Coinbase Advanced Trade, lower volume tiers.
+    pub fn coinbase_advanced() -> Self {
+        Self::tiered(vec![
+            tier(0.0, 0.0040, 0.0060),
+            tier(10_000.0, 0.0025, 0.0040),
+            tier(50_000.0, 0.0015, 0.0025),
+            tier(100_000.0, 0.0010, 0.0020),
+            tier(1_000_000.0, 0.0008, 0.0018),
+        ])
+    }
+
+    /// Kraken Pro spot, lower volume tiers.
+    pub fn kraken_pro() -> Self {
+        Self::tiered(vec![
+            tier(0.0, 0.0025, 0.0040),
+            tier(10_000.0, 0.0020, 0.0035),
+            tier(50_000.0, 0.0014, 0.0024),
+            tier(100_000.0, 0.0012, 0.0022),
+            tier(250_000.0, 0.0010, 0.0020),
+        ])
+    }
+
+    /// Returns the tier that applies at a trailing traded volume.
+    pub fn tier(&self, trailing_volume: f64) -> Option<&FeeTier> {
+        let volume = trailing_volume + self.external_volume;
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| volume >= tier.min_volume)
+            .or_else(|| self.tiers.first())
+    }
+
+    /// Calculates the fee for a fill, in quote currency.
+    ///
+    /// # Arguments
+    /// * `notional` - Fill quantity times fill price
+    /// * `liquidity` - Whether the fill added or took liquidity
+    /// * `trailing_volume` - Notional traded in the backtest over the volume window
+    pub fn fee(&self, notional: f64, liquidity: Liquidity, trailing_volume: f64) -> f64 {
+        let rate = self
+            .tier(trailing_volume)
+            .map_or(0.0, |tier| match liquidity {
+                Liquidity::Maker => tier.maker_rate,
+                Liquidity::Taker => tier.taker_rate,
+            });
+        let discount = match &self.currency {
+            FeeCurrency::Token { discount, .. } => discount.clamp(0.0, 1.0),
+            FeeCurrency::Quote => 0.0,
+        };
+
+        (notional.abs() * rate * (1.0 - discount)).max(self.minimum_fee)
+    }
+}
+
+fn tier(min_volume: f64, maker_rate: f64, taker_rate: f64) -> FeeTier {
+    FeeTier {
+        min_volume,
+        maker_rate,
+        taker_rate,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_schedules() {
+        let flat = FeeSchedule::flat(0.001);
+        assert!((flat.fee(1000.0, Liquidity::Maker, 0.0) - 1.0).abs() < 1e-12);
+        assert!((flat.fee(1000.0, Liquidity::Taker, 0.0) - 1.0).abs() < 1e-12);
+
+        let minimum = FeeSchedule::maker_taker(0.0002, 0.0005).with_minimum_fee(0.5);
+        assert_eq!(minimum.fee(100.0, Liquidity::Taker, 0.0), 0.5);
+        assert!((minimum.fee(10_000.0, Liquidity::Maker, 0.0) - 2.0).abs() < 1e-12);
+
+        // Tiers step down with trailing volume, including volume traded elsewhere
+        let mut coinbase = FeeSchedule::coinbase_advanced();
+        assert!((coinbase.fee(1000.0, Liquidity::Taker, 0.0) - 6.0).abs() < 1e-9);
+        assert!((coinbase.fee(1000.0, Liquidity::Taker, 60_000.0) - 2.5).abs() < 1e-9);
+        coinbase.external_volume = 2_000_000.0;
+        assert!((coinbase.fee(1000.0, Liquidity::Maker, 0.0) - 0.8).abs() < 1e-9);
+
+        let bnb = FeeSchedule::binance_spot_bnb();
+        assert!((bnb.fee(1000.0, Liquidity::Taker, 0.0) - 0.75).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,299 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// An open position held in the ledger.
+///
+/// # Fields
+/// * `symbol`: Traded symbol
+/// * `quantity`: Signed quantity, positive for long and negative for short
+/// * `average_price`: Volume-weighted entry price of the open quantity
+/// * `mark_price`: Last price the position was marked at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub symbol: String,
+    pub quantity: f64,
+    pub average_price: f64,
+    pub mark_price: f64,
+}
+
+impl Position {
+    /// Returns the signed market value of the position at its mark price.
+    pub fn market_value(&self) -> f64 {
+        self.quantity * self.mark_price
+    }
+
+    /// Returns the PnL of the position if it were closed at its mark price.
+    pub fn unrealized_pnl(&self) -> f64 {
+        (self.mark_price - self.average_price) * self.quantity
+    }
+}
+
+/// State of the account at a single point in time.
+///
+/// `equity` always equals `cash` plus the market value of open positions, and
+/// `initial_capital + realized_pnl + unrealized_pnl - commissions + funding - carry`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub cash: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub commissions: f64,
+    pub funding: f64,
+    pub carry: f64,
+    pub equity: f64,
+}
+
+/// Cash and position accounting for a backtest.
+///
+/// Buying debits cash and selling credits it, so short sales hold their
+/// proceeds in cash against a negative position. Realized PnL is booked when a
+/// position is reduced, closed or reversed; commissions, funding and carry
+/// costs are tracked as separate cash flows so the equity curve can be
+/// reconciled with the trade list.
+///
+/// # Fields
+/// * `initial_capital`: Cash the account started with
+/// * `cash`: Current cash balance
+/// * `positions`: Open positions by symbol
+/// * `realized_pnl`: Gross PnL booked on closed quantity
+/// * `commissions`: Total commissions paid
+/// * `funding`: Net funding received (negative when paid)
+/// * `carry`: Total borrow and financing costs accrued on open positions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ledger {
+    initial_capital: f64,
+    cash: f64,
+    positions: HashMap<String, Position>,
+    realized_pnl: f64,
+    commissions: f64,
+    funding: f64,
+    carry: f64,
+}
+
+impl Ledger {
+    /// Creates an empty ledger holding `initial_capital` in cash.
+    pub fn new(initial_capital: f64) -> Self {
+        Self {
+            initial_capital,
+            cash: initial_capital,
+            positions: HashMap::new(),
+            realized_pnl: 0.0,
+            commissions: 0.0,
+            funding: 0.0,
+            carry: 0.0,
+        }
+    }
+
+    /// Returns the cash the account started with.
+    pub fn initial_capital(&self) -> f64 {
+        self.initial_capital
+    }
+
+    /// Returns the current cash balance.
+    pub fn cash(&self) -> f64 {
+        self.cash
+    }
+
+    /// Returns the gross PnL booked on closed quantity.
+    pub fn realized_pnl(&self) -> f64 {
+        self.realized_pnl
+    }
+
+    /// Returns the total commissions paid.
+    pub fn commissions(&self) -> f64 {
+        self.commissions
+    }
+
+    /// Returns the net funding received.
+    pub fn funding(&self) -> f64 {
+        self.funding
+    }
+
+    /// Returns the total carry costs accrued.
+    pub fn carry(&self) -> f64 {
+        self.carry
+    }
+
+    /// Returns the open position in `symbol`, if any.
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.positions.get(symbol)
+    }
+
+    /// Returns all open positions.
+    pub fn positions(&self) -> impl Iterator<Item = &Position> {
+        self.positions.values()
+    }
+
+    /// Returns the unrealized PnL of all open positions at their mark prices.
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.positions.values().map(Position::unrealized_pnl).sum()
+    }
+
+    /// Returns cash plus the market value of all open positions.
+    pub fn equity(&self) -> f64 {
+        self.cash
+            + self
+                .positions
+                .values()
+                .map(Position::market_value)
+                .sum::<f64>()
+    }
+
+    /// Books a fill of `quantity` units of `symbol` at `price`.
+    ///
+    /// Fills in the direction of the open position increase it at a blended
+    /// average price; fills against it reduce, close or reverse it, booking the
+    /// realized PnL of the closed quantity.
+    ///
+    /// # Arguments
+    /// * `symbol`: Traded symbol
+    /// * `quantity`: Signed quantity, positive to buy and negative to sell
+    /// * `price`: Fill price
+    ///
+    /// # Returns
+    /// The gross PnL realized by the fill
+    pub fn fill(&mut self, symbol: &str, quantity: f64, price: f64) -> f64 {
+        self.cash -= quantity * price;
+
+        let position = self
+            .positions
+            .entry(symbol.to_string())
+            .or_insert_with(|| Position {
+                symbol: symbol.to_string(),
+                quantity: 0.0,
+                average_price: price,
+                mark_price: price,
+            });
+        position.mark_price = price;
+
+        let mut realized = 0.0;
+        if position.quantity == 0.0 || position.quantity.signum() == quantity.signum() {
+            let total = position.quantity + quantity;
+            position.average_price = (position.average_price * position.quantity.abs()
+                + price * quantity.abs())
+                / total.abs();
+            position.quantity = total;
+        } else {
+            let closed = quantity.abs().min(position.quantity.abs());
+            realized = (price - position.average_price) * closed * position.quantity.signum();
+            position.quantity += quantity;
+
+            if position.quantity.abs() <= f64::EPSILON * closed.max(1.0) {
+                position.quantity = 0.0;
+            } else if position.quantity.signum() == quantity.signum() {
+                // Reversed through flat: the remainder is a new position at the fill price
+                position.average_price = price;
+            }
+        }
+
+        if position.quantity == 0.0 {
+            self.positions.remove(symbol);
+        }
+
+        self.realized_pnl += realized;
+        realized
+    }
+
+    /// Closes the whole position in `symbol` at `price`.
+    ///
+    /// # Returns
+    /// The gross PnL realized, or `None` if there was no open position
+    pub fn close(&mut self, symbol: &str, price: f64) -> Option<f64> {
+        let quantity = self.positions.get(symbol)?.quantity;
+        Some(self.fill(symbol, -quantity, price))
+    }
+
+    /// Debits a commission from cash.
+    pub fn charge_commission(&mut self, amount: f64) {
+        self.cash -= amount;
+        self.commissions += amount;
+    }
+
+    /// Credits (positive) or debits (negative) a funding payment.
+    pub fn apply_funding(&mut self, amount: f64) {
+        self.cash += amount;
+        self.funding += amount;
+    }
+
+    /// Debits a borrow or financing cost accrued on an open position.
+    pub fn charge_carry(&mut self, amount: f64) {
+        self.cash -= amount;
+        self.carry += amount;
+    }
+
+    /// Marks the position in `symbol` to `price`.
+    pub fn mark(&mut self, symbol: &str, price: f64) {
+        if let Some(position) = self.positions.get_mut(symbol) {
+            position.mark_price = price;
+        }
+    }
+
+    /// Captures the current state of the account.
+    pub fn snapshot(&self, timestamp: DateTime<Utc>) -> LedgerSnapshot {
+        LedgerSnapshot {
+            timestamp,
+            cash: self.cash,
+            realized_pnl: self.realized_pnl,
+            unrealized_pnl: self.unrealized_pnl(),
+            commissions: self.commissions,
+            funding: self.funding,
+            carry: self.carry,
+            equity: self.equity(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_round_trip() {
+        let mut ledger = Ledger::new(10000.0);
+
+        assert_eq!(ledger.fill("SOL", 10.0, 100.0), 0.0);
+        ledger.charge_commission(1.0);
+        assert_eq!(ledger.cash(), 8999.0);
+
+        ledger.mark("SOL", 110.0);
+        assert_eq!(ledger.unrealized_pnl(), 100.0);
+        assert_eq!(ledger.equity(), 10099.0);
+
+        assert_eq!(ledger.close("SOL", 120.0), Some(200.0));
+        ledger.charge_commission(1.2);
+        assert!(ledger.position("SOL").is_none());
+        assert!((ledger.equity() - (10000.0 + 200.0 - 2.2)).abs() < 1e-9);
+        assert_eq!(ledger.close("SOL", 120.0), None);
+    }
+
+    #[test]
+    fn test_short_scale_and_reverse() {
+        let mut ledger = Ledger::new(10000.0);
+
+        ledger.fill("SOL", -5.0, 100.0);
+        ledger.fill("SOL", -5.0, 110.0);
+        let position = ledger.position("SOL").unwrap();
+        assert_eq!(position.quantity, -10.0);
+        assert!((position.average_price - 105.0).abs() < 1e-9);
+
+        // Buying 15 covers the short at a 5 per unit loss and leaves 5 long
+        let realized = ledger.fill("SOL", 15.0, 110.0);
+        assert!((realized + 50.0).abs() < 1e-9);
+        let position = ledger.position("SOL").unwrap();
+        assert_eq!(position.quantity, 5.0);
+        assert_eq!(position.average_price, 110.0);
+
+        ledger.apply_funding(-3.0);
+        ledger.charge_carry(0.5);
+        ledger.mark("SOL", 112.0);
+        let snapshot = ledger.snapshot(Utc::now());
+        let reconciled = ledger.initial_capital() + snapshot.realized_pnl + snapshot.unrealized_pnl
+            - snapshot.commissions
+            + snapshot.funding
+            - snapshot.carry;
+        assert!((snapshot.equity - reconciled).abs() < 1e-9);
+        assert!((snapshot.equity - (10000.0 - 50.0 + 10.0 - 3.0 - 0.5)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+
+/// How closing fills are matched against the lots of an open position.
+///
+/// - `AveragePrice`: Entries are blended into a single lot at their average price
+/// - `Fifo`: Each entry is a separate lot; the oldest lot is closed first
+/// - `Lifo`: Each entry is a separate lot; the newest lot is closed first
+///
+/// Every lot closed (in whole or in part) is reported as its own trade, so
+/// the lot method decides how PnL is attributed between entries. The ledger
+/// always accounts positions at their average price; both agree once a
+/// position is flat.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LotMethod {
+    #[default]
+    AveragePrice,
+    Fifo,
+    Lifo,
+}
+
+/// Limits on scaling into and out of positions.
+///
+/// A signal in the direction of the open position adds to it until
+/// `max_entries` entries are open, each sized at `entry_scale` times the size
+/// of the previous entry. An opposite signal closes `exit_fraction` of the
+/// position, and only reverses it when the whole position is closed.
+///
+/// # Fields
+/// * `max_entries`: Maximum number of entries per position; 1 disables pyramiding
+/// * `entry_scale`: Size of each added entry relative to the previous one
+/// * `exit_fraction`: Fraction of the position an opposite signal closes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Pyramiding {
+    pub max_entries: usize,
+    pub entry_scale: f64,
+    pub exit_fraction: f64,
+}
+
+impl Default for Pyramiding {
+    fn default() -> Self {
+        Self {
+            max_entries: 1,
+            entry_scale: 1.0,
+            exit_fraction: 1.0,
+        }
+    }
+}
+
+impl Pyramiding {
+    /// Returns the size of the next entry relative to the first one.
+    ///
+    /// # Returns
+    /// `None` once `open_entries` reached `max_entries`
+    pub fn next_entry_scale(&self, open_entries: usize) -> Option<f64> {
+        (open_entries < self.max_entries.max(1)).then(|| self.entry_scale.powi(open_entries as i32))
+    }
+
+    /// Returns `true` if an opposite signal closes the whole position.
+    pub fn closes_fully(&self) -> bool {
+        self.exit_fraction >= 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pyramiding_limits() {
+        let default = Pyramiding::default();
+        assert_eq!(default.next_entry_scale(0), Some(1.0));
+        assert_eq!(default.next_entry_scale(1), None);
+        assert!(default.closes_fully());
+
+        let pyramid = Pyramiding {
+            max_entries: 3,
+            entry_scale: 0.5,
+            exit_fraction: 0.5,
+        };
+        assert_eq!(pyramid.next_entry_scale(1), Some(0.5));
+        assert_eq!(pyramid.next_entry_scale(2), Some(0.25));
+        assert_eq!(pyramid.next_entry_scale(3), None);
+        assert!(!pyramid.closes_fully());
+    }
+}
@@ -0,0 +1,174 @@
+use super::ledger::Position;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How collateral backs leveraged positions.
+///
+/// - `Isolated`: Each position is backed only by the initial margin posted for
+///   it; a liquidation loses at most that margin
+/// - `Cross`: All positions share the account equity as collateral
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarginMode {
+    Isolated,
+    #[default]
+    Cross,
+}
+
+/// Margin requirements for trading perpetual futures.
+///
+/// The initial margin of a position is its notional divided by `leverage`, and
+/// caps the notional that can be opened with the equity available. A position
+/// is liquidated when its margin falls to the maintenance margin,
+/// `maintenance_margin_rate` times its notional.
+///
+/// # Fields
+/// * `mode`: Isolated or cross margin
+/// * `leverage`: Maximum notional as a multiple of posted margin
+/// * `maintenance_margin_rate`: Margin required to keep a position open, as a fraction of notional
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MarginConfig {
+    pub mode: MarginMode,
+    pub leverage: f64,
+    pub maintenance_margin_rate: f64,
+}
+
+impl Default for MarginConfig {
+    fn default() -> Self {
+        Self {
+            mode: MarginMode::Cross,
+            leverage: 1.0,
+            maintenance_margin_rate: 0.005,
+        }
+    }
+}
+
+/// A funding rate paid at one funding timestamp.
+///
+/// Longs pay shorts `rate` times the position notional when the rate is
+/// positive, and receive it when the rate is negative.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FundingRate {
+    pub timestamp: DateTime<Utc>,
+    pub rate: f64,
+}
+
+impl MarginConfig {
+    /// Returns the margin required to open a position of `notional`.
+    pub fn initial_margin(&self, notional: f64) -> f64 {
+        notional.abs() / self.leverage.max(f64::EPSILON)
+    }
+
+    /// Returns the margin required to keep a position of `notional` open.
+    pub fn maintenance_margin(&self, notional: f64) -> f64 {
+        notional.abs() * self.maintenance_margin_rate
+    }
+
+    /// Returns the largest notional that can be opened.
+    ///
+    /// # Arguments
+    /// * `equity` - Account equity
+    /// * `other_exposure` - Gross notional of positions already holding margin
+    pub fn max_notional(&self, equity: f64, other_exposure: f64) -> f64 {
+        (equity * self.leverage - other_exposure).max(0.0)
+    }
+
+    /// Returns the price at which an isolated position has lost all of its margin.
+    pub fn bankruptcy_price(&self, quantity: f64, entry_price: f64) -> f64 {
+        entry_price * (1.0 - quantity.signum() / self.leverage.max(f64::EPSILON))
+    }
+
+    /// Calculates the liquidation price of a position.
+    ///
+    /// Isolated positions are liquidated when the loss on their own initial
+    /// margin leaves only the maintenance margin. Cross positions are liquidated
+    /// when account equity falls to the maintenance margin of all positions,
+    /// assuming the other positions keep their current value.
+    ///
+    /// # Arguments
+    /// * `position` - Open position, marked at its last price
+    /// * `equity` - Account equity at the same marks
+    /// * `other_maintenance` - Maintenance margin of the account's other positions
+    ///
+    /// # Returns
+    /// `None` for flat positions and for cross positions the account can carry
+    /// at any price
+    pub fn liquidation_price(
+        &self,
+        position: &Position,
+        equity: f64,
+        other_maintenance: f64,
+    ) -> Option<f64> {
+        let quantity = position.quantity;
+        if quantity == 0.0 {
+            return None;
+        }
+
+        let price = match self.mode {
+            MarginMode::Isolated => {
+                let side = quantity.signum();
+                position.average_price * (1.0 - side / self.leverage.max(f64::EPSILON))
+                    / (1.0 - side * self.maintenance_margin_rate)
+            }
+            MarginMode::Cross => {
+                // equity + q (P - mark) = mmr |q| P + other maintenance
+                let available = equity - other_maintenance;
+                (quantity * position.mark_price - available)
+                    / (quantity - self.maintenance_margin_rate * quantity.abs())
+            }
+        };
+
+        (price > 0.0 && price.is_finite()).then_some(price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(quantity: f64, price: f64) -> Position {
+        Position {
+            symbol: "SOL-PERP".to_string(),
+            quantity,
+            average_price: price,
+            mark_price: price,
+        }
+    }
+
+    #[test]
+    fn test_liquidation_prices() {
+        let isolated = MarginConfig {
+            mode: MarginMode::Isolated,
+            leverage: 10.0,
+            maintenance_margin_rate: 0.005,
+        };
+        let long = isolated
+            .liquidation_price(&position(1.0, 100.0), 0.0, 0.0)
+            .unwrap();
+        assert!((long - 90.0 / 0.995).abs() < 1e-9);
+        let short = isolated
+            .liquidation_price(&position(-1.0, 100.0), 0.0, 0.0)
+            .unwrap();
+        assert!((short - 110.0 / 1.005).abs() < 1e-9);
+        assert_eq!(isolated.bankruptcy_price(1.0, 100.0), 90.0);
+        assert_eq!(isolated.initial_margin(-1000.0), 100.0);
+
+        // 10 units on 200 of equity: liquidated once equity covers only maintenance
+        let cross = MarginConfig {
+            leverage: 10.0,
+            ..Default::default()
+        };
+        let price = cross
+            .liquidation_price(&position(10.0, 100.0), 200.0, 0.0)
+            .unwrap();
+        let equity_at_price = 200.0 + 10.0 * (price - 100.0);
+        assert!((equity_at_price - cross.maintenance_margin(10.0 * price)).abs() < 1e-9);
+        assert!(price < 100.0);
+
+        // A fully funded long can never be liquidated
+        assert_eq!(
+            cross.liquidation_price(&position(1.0, 100.0), 10000.0, 0.0),
+            None
+        );
+        assert_eq!(cross.max_notional(1000.0, 4000.0), 6000.0);
+    }
+}
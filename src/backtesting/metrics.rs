@@ -0,0 +1,336 @@
+use super::backtester::{EquityPoint, Trade};
+use super::drawdown::drawdown_from_peak;
+use serde::{Deserialize, Serialize};
+
+/// Annualization and risk settings used to compute performance metrics.
+///
+/// # Fields
+/// * `periods_per_year`: Bars per year; 365 for daily crypto bars
+/// * `risk_free_rate`: Annual risk-free rate subtracted in Sharpe and Sortino
+/// * `var_confidence`: Confidence level of value at risk, e.g. `0.95`
+/// * `top_drawdowns`: Number of deepest drawdown periods reported
+/// * `histogram_bins`: Number of bins of the trade analysis histograms
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    pub periods_per_year: f64,
+    pub risk_free_rate: f64,
+    pub var_confidence: f64,
+    pub top_drawdowns: usize,
+    pub histogram_bins: usize,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            periods_per_year: 365.0,
+            risk_free_rate: 0.02,
+            var_confidence: 0.95,
+            top_drawdowns: 5,
+            histogram_bins: 10,
+        }
+    }
+}
+
+/// Return, risk and trade statistics of a backtest.
+///
+/// Return-based metrics use the bar-to-bar returns of the equity curve and
+/// are annualized with `MetricsConfig::periods_per_year`. Ratios whose
+/// denominator is zero (e.g. the profit factor without losing trades) are 0.
+///
+/// # Fields
+/// * `cagr`: Compound annual growth rate of equity
+/// * `annualized_return`: Mean bar return times periods per year
+/// * `annualized_volatility`: Standard deviation of bar returns, annualized
+/// * `sharpe_ratio`: Excess annualized return over annualized volatility
+/// * `sortino_ratio`: Excess annualized return over annualized downside deviation
+/// * `calmar_ratio`: CAGR over maximum drawdown
+/// * `max_drawdown`: Largest fall from a running equity peak, as a fraction of the peak
+/// * `longest_drawdown_bars`: Most consecutive bars spent below a running peak
+/// * `longest_drawdown_days`: Longest time from a peak to its recovery (or the end), in days
+/// * `ulcer_index`: Root mean square of drawdowns from the running peak
+/// * `profit_factor`: Gross profit over gross loss of closed trades
+/// * `expectancy`: Average PnL per closed trade
+/// * `payoff_ratio`: Average win over average loss
+/// * `exposure`: Fraction of bars a position was held at the close
+/// * `turnover`: Traded notional per year as a multiple of average equity
+/// * `tail_ratio`: 95th percentile bar return over the absolute 5th percentile
+/// * `skewness`: Skewness of bar returns
+/// * `kurtosis`: Excess kurtosis of bar returns
+/// * `value_at_risk`: Historical one-bar loss not exceeded at `var_confidence`
+/// * `conditional_value_at_risk`: Average one-bar loss beyond the value at risk
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceMetrics {
+    pub cagr: f64,
+    pub annualized_return: f64,
+    pub annualized_volatility: f64,
+    pub sharpe_ratio: f64,
+    pub sortino_ratio: f64,
+    pub calmar_ratio: f64,
+    pub max_drawdown: f64,
+    pub longest_drawdown_bars: usize,
+    pub longest_drawdown_days: f64,
+    pub ulcer_index: f64,
+    pub profit_factor: f64,
+    pub expectancy: f64,
+    pub payoff_ratio: f64,
+    pub exposure: f64,
+    pub turnover: f64,
+    pub tail_ratio: f64,
+    pub skewness: f64,
+    pub kurtosis: f64,
+    pub value_at_risk: f64,
+    pub conditional_value_at_risk: f64,
+}
+
+impl PerformanceMetrics {
+    /// Calculates the metrics of a backtest.
+    ///
+    /// # Arguments
+    /// * `equity_curve` - Equity before the first bar, then after every bar
+    /// * `trades` - Closed trades
+    /// * `bars_in_market` - Number of bars that closed with a position open
+    /// * `traded_notional` - Total notional of all fills
+    /// * `config` - Annualization and risk settings
+    pub fn calculate(
+        equity_curve: &[EquityPoint],
+        trades: &[Trade],
+        bars_in_market: usize,
+        traded_notional: f64,
+        config: &MetricsConfig,
+    ) -> Self {
+        let periods = config.periods_per_year;
+        let returns = period_returns(equity_curve);
+        let bars = returns.len();
+        let mut metrics = Self::default();
+
+        // Return and risk
+        if bars > 0 {
+            metrics.annualized_return = mean(&returns) * periods;
+            let std_dev = std_dev(&returns);
+            let excess = metrics.annualized_return - config.risk_free_rate;
+            let risk_free_per_period = config.risk_free_rate / periods;
+            let downside = (returns
+                .iter()
+                .map(|r| (r - risk_free_per_period).min(0.0).powi(2))
+                .sum::<f64>()
+                / bars as f64)
+                .sqrt();
+
+            metrics.annualized_volatility = std_dev * periods.sqrt();
+            metrics.sharpe_ratio = sharpe_ratio(&returns, periods, config.risk_free_rate);
+            metrics.sortino_ratio = ratio(excess, downside * periods.sqrt());
+
+            if std_dev > 0.0 {
+                metrics.skewness = standardized_moment(&returns, 3);
+                metrics.kurtosis = standardized_moment(&returns, 4) - 3.0;
+            }
+
+            let mut sorted = returns.clone();
+            sorted.sort_by(f64::total_cmp);
+            let lower = quantile(&sorted, 0.05);
+            metrics.tail_ratio = ratio(quantile(&sorted, 0.95), lower.abs());
+
+            let cutoff = quantile(&sorted, 1.0 - config.var_confidence);
+            metrics.value_at_risk = (-cutoff).max(0.0);
+            let tail: Vec<f64> = sorted.iter().copied().filter(|r| *r <= cutoff).collect();
+            metrics.conditional_value_at_risk = (-mean(&tail)).max(0.0);
+
+            metrics.exposure = bars_in_market as f64 / bars as f64;
+        }
+
+        if let (Some(first), Some(last)) = (equity_curve.first(), equity_curve.last()) {
+            let years = bars as f64 / periods;
+            if first.equity > 0.0 && last.equity > 0.0 && years > 0.0 {
+                metrics.cagr = (last.equity / first.equity).powf(1.0 / years) - 1.0;
+            }
+
+            let average_equity = equity_curve.iter().map(|point| point.equity).sum::<f64>()
+                / equity_curve.len() as f64;
+            if years > 0.0 {
+                metrics.turnover = ratio(traded_notional / years, average_equity);
+            }
+        }
+
+        // Drawdowns from the running peak
+        let mut peak = equity_curve.first().map_or(0.0, |point| point.equity);
+        let mut peak_time = equity_curve.first().map(|point| point.timestamp);
+        let mut underwater_bars = 0;
+        let mut squared_drawdowns = 0.0;
+        for point in equity_curve {
+            if point.equity >= peak {
+                peak = point.equity;
+                peak_time = Some(point.timestamp);
+                underwater_bars = 0;
+            } else {
+                underwater_bars += 1;
+                metrics.longest_drawdown_bars = metrics.longest_drawdown_bars.max(underwater_bars);
+                if let Some(start) = peak_time {
+                    let days = (point.timestamp - start).num_seconds() as f64 / 86_400.0;
+                    metrics.longest_drawdown_days = metrics.longest_drawdown_days.max(days);
+                }
+            }
+            let drawdown = drawdown_from_peak(peak, point.equity);
+            metrics.max_drawdown = metrics.max_drawdown.max(drawdown);
+            squared_drawdowns += drawdown.powi(2);
+        }
+        if !equity_curve.is_empty() {
+            metrics.ulcer_index = (squared_drawdowns / equity_curve.len() as f64).sqrt();
+        }
+        metrics.calmar_ratio = ratio(metrics.cagr, metrics.max_drawdown);
+
+        // Trade statistics
+        let pnls: Vec<f64> = trades.iter().filter_map(|trade| trade.pnl).collect();
+        let wins: Vec<f64> = pnls.iter().copied().filter(|pnl| *pnl > 0.0).collect();
+        let losses: Vec<f64> = pnls.iter().copied().filter(|pnl| *pnl <= 0.0).collect();
+        metrics.profit_factor = ratio(wins.iter().sum(), -losses.iter().sum::<f64>());
+        metrics.expectancy = mean(&pnls);
+        metrics.payoff_ratio = ratio(mean(&wins), -mean(&losses));
+
+        metrics
+    }
+}
+
+/// Bar-to-bar simple returns of an equity curve.
+pub(crate) fn period_returns(equity_curve: &[EquityPoint]) -> Vec<f64> {
+    let values: Vec<f64> = equity_curve.iter().map(|point| point.equity).collect();
+    simple_returns(&values)
+}
+
+/// Step-to-step simple returns of a value series; 0 after a zero value.
+pub(crate) fn simple_returns(values: &[f64]) -> Vec<f64> {
+    values
+        .windows(2)
+        .map(|pair| {
+            if pair[0] != 0.0 {
+                (pair[1] - pair[0]) / pair[0]
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Arithmetic mean; 0 for no values.
+pub(crate) fn mean(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        0.0
+    } else {
+        values.iter().sum::<f64>() / values.len() as f64
+    }
+}
+
+/// Population variance; 0 for no values.
+pub(crate) fn variance(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    mean_of(
+        values.iter().map(|value| (value - mean).powi(2)),
+        values.len(),
+    )
+}
+
+/// Population standard deviation; 0 for no values.
+pub(crate) fn std_dev(values: &[f64]) -> f64 {
+    variance(values).sqrt()
+}
+
+/// Mean of the `power`-th power of the standardized values; 0 without variation.
+pub(crate) fn standardized_moment(values: &[f64], power: i32) -> f64 {
+    let (mean, std_dev) = (mean(values), std_dev(values));
+    if std_dev == 0.0 {
+        return 0.0;
+    }
+    mean_of(
+        values
+            .iter()
+            .map(|value| ((value - mean) / std_dev).powi(power)),
+        values.len(),
+    )
+}
+
+/// Annualized Sharpe ratio of bar returns; 0 without variation.
+///
+/// With one period per year and no risk-free rate this is the per-bar ratio.
+pub(crate) fn sharpe_ratio(returns: &[f64], periods_per_year: f64, risk_free_rate: f64) -> f64 {
+    let excess = mean(returns) * periods_per_year - risk_free_rate;
+    ratio(excess, std_dev(returns) * periods_per_year.sqrt())
+}
+
+fn mean_of(values: impl Iterator<Item = f64>, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        values.sum::<f64>() / count as f64
+    }
+}
+
+/// `numerator / denominator`, or 0 when the denominator is not positive.
+fn ratio(numerator: f64, denominator: f64) -> f64 {
+    if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    }
+}
+
+/// Linearly interpolated quantile of ascending `sorted` values.
+pub(crate) fn quantile(sorted: &[f64], probability: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let position = probability.clamp(0.0, 1.0) * (sorted.len() - 1) as f64;
+    let lower = position.floor() as usize;
+    let upper = position.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (position - lower as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::test_support::curve;
+
+    #[test]
+    fn test_drawdown_and_trade_metrics() {
+        let equity = curve(&[100.0, 110.0, 99.0, 104.5, 121.0, 115.0]);
+        let trades: Vec<Trade> = [30.0, 10.0, -10.0, -10.0]
+            .iter()
+            .map(|&p| Trade::closed_for_test(p))
+            .collect();
+        let config = MetricsConfig {
+            periods_per_year: 5.0,
+            ..Default::default()
+        };
+        let metrics = PerformanceMetrics::calculate(&equity, &trades, 3, 1000.0, &config);
+
+        // Five bars make up one year
+        assert!((metrics.cagr - 0.15).abs() < 1e-9);
+        assert!((metrics.max_drawdown - 0.1).abs() < 1e-9);
+        assert_eq!(metrics.longest_drawdown_bars, 2);
+        assert_eq!(metrics.longest_drawdown_days, 2.0);
+        assert!((metrics.calmar_ratio - 1.5).abs() < 1e-9);
+        assert!((metrics.profit_factor - 2.0).abs() < 1e-9);
+        assert!((metrics.expectancy - 5.0).abs() < 1e-9);
+        assert!((metrics.payoff_ratio - 2.0).abs() < 1e-9);
+        assert!((metrics.exposure - 0.6).abs() < 1e-9);
+        assert!(metrics.turnover > 0.0);
+        assert!(metrics.value_at_risk > 0.0);
+        assert!(metrics.conditional_value_at_risk >= metrics.value_at_risk);
+        assert!(metrics.sortino_ratio > 0.0 && metrics.sharpe_ratio > 0.0);
+    }
+
+    #[test]
+    fn test_return_distribution_metrics() {
+        // Symmetric returns have no skew; a flat curve has no risk
+        let symmetric = curve(&[100.0, 101.0, 100.0, 101.0, 100.0]);
+        let metrics =
+            PerformanceMetrics::calculate(&symmetric, &[], 0, 0.0, &MetricsConfig::default());
+        assert!(metrics.skewness.abs() < 0.05);
+        assert!(metrics.kurtosis < 0.0);
+
+        let flat = curve(&[100.0; 4]);
+        let metrics = PerformanceMetrics::calculate(&flat, &[], 0, 0.0, &MetricsConfig::default());
+        assert_eq!(metrics.sharpe_ratio, 0.0);
+        assert_eq!(metrics.max_drawdown, 0.0);
+        assert_eq!(metrics.value_at_risk, 0.0);
+        assert_eq!(quantile(&[1.0, 2.0, 3.0, 4.0], 0.5), 2.5);
+    }
+}
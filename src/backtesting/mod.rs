@@ -1,3 +1,59 @@
 mod backtester;
+mod benchmark;
+mod carry;
+mod drawdown;
+mod events;
+mod evolution;
+mod exchange;
+mod fees;
+mod ledger;
+mod lots;
+mod margin;
+mod metrics;
+mod monte_carlo;
+mod optimizer;
+mod orders;
+mod overfitting;
+mod risk;
+mod rolling;
+mod sizing;
+mod slippage;
+#[cfg(test)]
+pub(crate) mod test_support;
+mod trade_analysis;
+mod walk_forward;
 
-pub use backtester::{BacktestResult, Backtester, StrategyMode};
+pub use backtester::{
+    BacktestResult, Backtester, EquityPoint, PositionType, SignalMetadata, StrategyMode, Trade,
+    TradeSignal,
+};
+pub use benchmark::{Benchmark, BenchmarkComparison, BenchmarkPoint, RelativePoint};
+pub use carry::{CarryModel, CarryRate};
+pub use drawdown::{drawdown_periods, DrawdownPeriod};
+pub use events::{Event, EventKind};
+pub use evolution::{CmaEsConfig, EarlyStopping, GeneticConfig};
+pub use exchange::{BarExecution, FillTiming, SimulatedExchange};
+pub use fees::{FeeCurrency, FeeSchedule, FeeTier};
+pub use ledger::{Ledger, LedgerSnapshot, Position};
+pub use lots::{LotMethod, Pyramiding};
+pub use margin::{FundingRate, MarginConfig, MarginMode};
+pub use metrics::{MetricsConfig, PerformanceMetrics};
+pub use monte_carlo::{ConfidenceInterval, MonteCarloConfig, MonteCarloReport, TradeResampling};
+pub use optimizer::{
+    Constraint, Heatmap, Objective, OptimizationReport, Optimizer, OptimizerError, ParameterRange,
+    SearchMethod, Trial,
+};
+pub use orders::{Fill, Liquidity, Order, OrderRequest, OrderSide, OrderType, TimeInForce, Trail};
+pub use overfitting::{
+    expected_max_sharpe, minimum_backtest_length, probabilistic_sharpe_ratio,
+    probability_of_backtest_overfitting, OverfittingConfig, OverfittingReport, OverfittingVerdict,
+    MAX_CSCV_SPLITS,
+};
+pub use risk::{average_true_range, ExitDistance, ExitReason, ExitRules};
+pub use rolling::{RollingMetrics, RollingPoint, StrategyGate};
+pub use sizing::{PositionSizing, SizingContext, SizingLimits};
+pub use slippage::SlippageModel;
+pub use trade_analysis::{ExcursionPoint, Histogram, HistogramBin, TradeAnalysis};
+pub use walk_forward::{
+    ParameterStability, WalkForwardConfig, WalkForwardFold, WalkForwardReport, WindowMode,
+};
@@ -0,0 +1,274 @@
+use super::backtester::BacktestResult;
+use super::drawdown::drawdown_from_peak;
+use super::metrics::quantile;
+use super::overfitting::normal_quantile;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// How each simulation reorders the closed trades.
+///
+/// - `Shuffle`: Every trade once, in random order
+/// - `Bootstrap`: As many trades as closed, drawn with replacement
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TradeResampling {
+    #[default]
+    Shuffle,
+    Bootstrap,
+}
+
+/// Settings of a Monte Carlo analysis.
+///
+/// # Fields
+/// * `simulations`: Number of simulated trade sequences
+/// * `resampling`: How trades are reordered
+/// * `max_slippage`: Largest extra slippage on each entry and exit price, as a
+///   fraction of the price; each fill draws uniformly up to this and always
+///   moves against the trade
+/// * `skip_probability`: Chance each trade is left out, as if missed
+/// * `ruin_level`: Equity, as a fraction of the initial capital, at or below
+///   which a simulation counts as ruined
+/// * `confidence`: Confidence level of the intervals
+/// * `seed`: Random seed; the same seed reproduces the same report
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MonteCarloConfig {
+    pub simulations: usize,
+    pub resampling: TradeResampling,
+    pub max_slippage: f64,
+    pub skip_probability: f64,
+    pub ruin_level: f64,
+    pub confidence: f64,
+    pub seed: u64,
+}
+
+impl Default for MonteCarloConfig {
+    fn default() -> Self {
+        Self {
+            simulations: 1000,
+            resampling: TradeResampling::Shuffle,
+            max_slippage: 0.0005,
+            skip_probability: 0.05,
+            ruin_level: 0.5,
+            confidence: 0.95,
+            seed: 42,
+        }
+    }
+}
+
+/// Two-sided confidence interval around an estimate.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceInterval {
+    pub lower: f64,
+    pub estimate: f64,
+    pub upper: f64,
+}
+
+/// Spread of outcomes over simulated trade sequences.
+///
+/// # Fields
+/// * `simulations`: Number of simulated sequences
+/// * `trades`: Number of closed trades resampled
+/// * `confidence`: Coverage of the intervals, e.g. 0.95
+/// * `final_equity`: Percentile interval of final equity, around the median
+/// * `max_drawdown`: Percentile interval of the largest drawdown, around the median
+/// * `risk_of_ruin`: Share of simulations that reached the ruin level, with its
+///   Wilson score interval
+/// * `probability_of_loss`: Share of simulations that ended below the initial capital
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MonteCarloReport {
+    pub simulations: usize,
+    pub trades: usize,
+    pub confidence: f64,
+    pub final_equity: ConfidenceInterval,
+    pub max_drawdown: ConfidenceInterval,
+    pub risk_of_ruin: ConfidenceInterval,
+    pub probability_of_loss: f64,
+}
+
+impl BacktestResult {
+    /// Simulates alternative histories of the closed trades.
+    ///
+    /// Each simulation replays the trades' PnL from the initial capital, after
+    /// reordering, skipping and extra slippage. Funding, carry and open
+    /// positions are not replayed, and trade sizes are kept as traded.
+    ///
+    /// # Arguments
+    /// * `config` - Number of simulations, perturbations and seed
+    pub fn monte_carlo(&self, config: &MonteCarloConfig) -> MonteCarloReport {
+        let initial = self
+            .equity_curve
+            .first()
+            .map_or(self.final_equity, |point| point.equity);
+        // PnL with the entry and exit notional each leg's slippage applies to
+        let trades: Vec<(f64, f64, f64)> = self
+            .trades
+            .iter()
+            .filter_map(|trade| {
+                let pnl = trade.pnl?;
+                let entry_notional = trade.quantity * trade.entry_price;
+                let exit_notional = trade.quantity * trade.exit_price?;
+                Some((pnl, entry_notional, exit_notional))
+            })
+            .collect();
+
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut final_equities = Vec::with_capacity(config.simulations);
+        let mut drawdowns = Vec::with_capacity(config.simulations);
+        let mut ruined = 0;
+
+        for _ in 0..config.simulations {
+            let sequence: Vec<(f64, f64, f64)> = match config.resampling {
+                TradeResampling::Shuffle => {
+                    let mut sequence = trades.clone();
+                    sequence.shuffle(&mut rng);
+                    sequence
+                }
+                TradeResampling::Bootstrap => (0..trades.len())
+                    .filter_map(|_| trades.choose(&mut rng).copied())
+                    .collect(),
+            };
+
+            let (mut equity, mut peak, mut max_drawdown) = (initial, initial, 0.0_f64);
+            let mut ruin = equity <= initial * config.ruin_level;
+            for (pnl, entry_notional, exit_notional) in sequence {
+                if rng.gen::<f64>() < config.skip_probability {
+                    continue;
+                }
+                let slippage = (entry_notional * rng.gen::<f64>()
+                    + exit_notional * rng.gen::<f64>())
+                    * config.max_slippage;
+                equity += pnl - slippage;
+                peak = peak.max(equity);
+                max_drawdown = max_drawdown.max(drawdown_from_peak(peak, equity));
+                ruin |= equity <= initial * config.ruin_level;
+            }
+
+            final_equities.push(equity);
+            drawdowns.push(max_drawdown);
+            if ruin {
+                ruined += 1;
+            }
+        }
+
+        let probability_of_loss = if final_equities.is_empty() {
+            0.0
+        } else {
+            final_equities
+                .iter()
+                .filter(|equity| **equity < initial)
+                .count() as f64
+                / final_equities.len() as f64
+        };
+        MonteCarloReport {
+            simulations: config.simulations,
+            trades: trades.len(),
+            confidence: config.confidence,
+            final_equity: percentile_interval(final_equities, config.confidence),
+            max_drawdown: percentile_interval(drawdowns, config.confidence),
+            risk_of_ruin: wilson_interval(ruined, config.simulations, config.confidence),
+            probability_of_loss,
+        }
+    }
+}
+
+/// Central percentile interval of a sample, around its median.
+fn percentile_interval(mut values: Vec<f64>, confidence: f64) -> ConfidenceInterval {
+    values.sort_by(f64::total_cmp);
+    let tail = (1.0 - confidence) / 2.0;
+    ConfidenceInterval {
+        lower: quantile(&values, tail),
+        estimate: quantile(&values, 0.5),
+        upper: quantile(&values, 1.0 - tail),
+    }
+}
+
+/// Wilson score interval of a proportion.
+fn wilson_interval(successes: usize, trials: usize, confidence: f64) -> ConfidenceInterval {
+    if trials == 0 {
+        return ConfidenceInterval {
+            lower: 0.0,
+            estimate: 0.0,
+            upper: 0.0,
+        };
+    }
+    let n = trials as f64;
+    let p = successes as f64 / n;
+    let z = normal_quantile(0.5 + confidence / 2.0);
+    let denominator = 1.0 + z * z / n;
+    let center = (p + z * z / (2.0 * n)) / denominator;
+    let margin = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+    ConfidenceInterval {
+        lower: (center - margin).max(0.0),
+        estimate: p,
+        upper: (center + margin).min(1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::{Backtester, Trade};
+
+    fn result(pnls: &[f64]) -> BacktestResult {
+        let trades = pnls
+            .iter()
+            .map(|&pnl| Trade::closed_for_test(pnl))
+            .collect();
+        let mut result = Backtester::new(100.0, 10.0, 0.0).run_backtest(&[]);
+        result.trades = trades;
+        result
+    }
+
+    #[test]
+    fn test_shuffle_keeps_final_equity() {
+        let result = result(&[20.0, -30.0, 10.0, -40.0, 25.0]);
+        let config = MonteCarloConfig {
+            simulations: 200,
+            max_slippage: 0.0,
+            skip_probability: 0.0,
+            ..Default::default()
+        };
+        let report = result.monte_carlo(&config);
+
+        // Reordering cannot change the sum, only the path
+        assert_eq!(report.trades, 5);
+        assert_eq!(report.confidence, 0.95);
+        assert!((report.final_equity.lower - 85.0).abs() < 1e-9);
+        assert!((report.final_equity.upper - 85.0).abs() < 1e-9);
+        assert!(report.max_drawdown.lower < report.max_drawdown.upper);
+        assert_eq!(report.probability_of_loss, 1.0);
+
+        // Losses back to back reach the 50% ruin level in some orders only
+        assert!(report.risk_of_ruin.estimate > 0.0 && report.risk_of_ruin.estimate < 1.0);
+        assert!(report.risk_of_ruin.lower < report.risk_of_ruin.estimate);
+        assert!(report.risk_of_ruin.upper > report.risk_of_ruin.estimate);
+    }
+
+    #[test]
+    fn test_perturbations_are_reproducible() {
+        let result = result(&[5.0, -3.0, 4.0, -2.0, 6.0, -1.0, 3.0]);
+        let config = MonteCarloConfig {
+            resampling: TradeResampling::Bootstrap,
+            max_slippage: 0.01,
+            skip_probability: 0.2,
+            ..Default::default()
+        };
+        let report = result.monte_carlo(&config);
+        assert_eq!(report, result.monte_carlo(&config));
+        assert_ne!(
+            report,
+            result.monte_carlo(&MonteCarloConfig { seed: 7, ..config })
+        );
+        assert!(report.final_equity.lower < report.final_equity.estimate);
+        assert!(report.final_equity.estimate < report.final_equity.upper);
+
+        // Skipping every trade leaves the initial capital untouched
+        let skipped = result.monte_carlo(&MonteCarloConfig {
+            skip_probability: 1.0,
+            ..config
+        });
+        assert_eq!(skipped.final_equity.upper, 100.0);
+        assert_eq!(skipped.max_drawdown.upper, 0.0);
+    }
+}
@@ -0,0 +1,892 @@
+use super::backtester::{BacktestResult, Backtester};
+use super::evolution::{self, CmaEsConfig, EarlyStopping, GeneticConfig};
+use super::metrics::period_returns;
+use super::overfitting::{OverfittingConfig, OverfittingReport};
+use crate::data::ProcessedMarketData;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Errors raised while setting up or running a parameter search.
+#[derive(Debug, Error)]
+pub enum OptimizerError {
+    /// The parameter does not belong to the strategies of the backtester's mode
+    #[error("unknown parameter {name}")]
+    UnknownParameter { name: String },
+
+    /// The value is outside the range the parameter accepts
+    #[error("invalid value {value} for {name}")]
+    InvalidValue { name: String, value: f64 },
+
+    /// The search has no parameters, or no values to try
+    #[error("the parameter space is empty")]
+    EmptySpace,
+
+    /// A requested parameter is not part of the search
+    #[error("{name} is not a searched parameter")]
+    NotSearched { name: String },
+
+    /// The data is too short for the requested analysis
+    #[error("{bars} bars of data, but at least {required} are required")]
+    InsufficientData { bars: usize, required: usize },
+}
+
+/// Range of values searched for one strategy parameter.
+///
+/// # Fields
+/// * `name`: Parameter name, as listed by `StrategyMode::parameters`
+/// * `min`: Lowest value tried
+/// * `max`: Highest value tried
+/// * `steps`: Number of evenly spaced values a grid search tries
+/// * `integer`: Whether values are rounded to whole numbers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterRange {
+    pub name: String,
+    pub min: f64,
+    pub max: f64,
+    pub steps: usize,
+    pub integer: bool,
+}
+
+impl ParameterRange {
+    /// Creates a continuous range, tried at `steps` evenly spaced values by grid search.
+    pub fn new(name: &str, min: f64, max: f64, steps: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            min,
+            max,
+            steps,
+            integer: false,
+        }
+    }
+
+    /// Creates a whole-number range; grid search tries every value in `[min, max]`.
+    pub fn integer(name: &str, min: usize, max: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            min: min as f64,
+            max: max as f64,
+            steps: max.saturating_sub(min) + 1,
+            integer: true,
+        }
+    }
+
+    /// Returns the values tried by a grid search.
+    pub fn grid_values(&self) -> Vec<f64> {
+        let mut values: Vec<f64> = match self.steps {
+            0 => Vec::new(),
+            1 => vec![self.min],
+            steps => (0..steps)
+                .map(|step| self.value_at(step as f64 / (steps - 1) as f64))
+                .collect(),
+        };
+        values.dedup();
+        values
+    }
+
+    /// Returns the grid value nearest to `value`, which buckets trials of
+    /// random and Latin-hypercube searches for averaging.
+    fn nearest_grid_value(&self, value: f64) -> f64 {
+        if self.steps <= 1 || self.max == self.min {
+            return self.value_at(0.0);
+        }
+        let intervals = (self.steps - 1) as f64;
+        let position = ((value - self.min) / (self.max - self.min)).clamp(0.0, 1.0);
+        self.value_at((position * intervals).round() / intervals)
+    }
+
+    /// Maps a position in `[0, 1]` to a value of the range.
+    fn value_at(&self, position: f64) -> f64 {
+        let value = self.min + (self.max - self.min) * position;
+        if self.integer {
+            value.round()
+        } else {
+            value
+        }
+    }
+}
+
+/// How candidate parameter sets are chosen.
+///
+/// - `Grid`: Every combination of the ranges' grid values
+/// - `Random`: `samples` sets drawn uniformly from the ranges
+/// - `LatinHypercube`: `samples` sets that split each range into `samples`
+///   equal strata and draw exactly one value from each
+/// - `Genetic`: A genetic algorithm that breeds each generation from the best
+///   sets of the last
+/// - `CmaEs`: An evolution strategy that adapts a normal search distribution
+///   towards the best sets of each generation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SearchMethod {
+    #[default]
+    Grid,
+    Random {
+        samples: usize,
+    },
+    LatinHypercube {
+        samples: usize,
+    },
+    Genetic(GeneticConfig),
+    CmaEs(CmaEsConfig),
+}
+
+/// Restriction on combinations of searched parameters.
+///
+/// - `LessThan`: The value of `lower` must be below the value of `upper`
+/// - `Custom`: Returns `true` for allowed parameter sets, given the values in
+///   the order of the searched ranges
+#[derive(Debug, Clone)]
+pub enum Constraint {
+    LessThan { lower: String, upper: String },
+    Custom(fn(&[f64]) -> bool),
+}
+
+/// Statistic parameter sets are ranked by; higher scores rank first.
+///
+/// - `SharpeRatio`, `SortinoRatio`, `CalmarRatio`: The annualized ratios of
+///   `PerformanceMetrics`
+/// - `TotalReturn`: Final equity over initial equity, less one
+/// - `ProfitFactor`: Gross profit over gross loss of closed trades
+/// - `MaxDrawdown`: The negated maximum drawdown, so shallower ranks first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Objective {
+    #[default]
+    SharpeRatio,
+    SortinoRatio,
+    CalmarRatio,
+    TotalReturn,
+    ProfitFactor,
+    MaxDrawdown,
+}
+
+impl Objective {
+    /// Scores a backtest result.
+    pub fn score(&self, result: &BacktestResult) -> f64 {
+        match self {
+            Objective::SharpeRatio => result.metrics.sharpe_ratio,
+            Objective::SortinoRatio => result.metrics.sortino_ratio,
+            Objective::CalmarRatio => result.metrics.calmar_ratio,
+            Objective::TotalReturn => total_return(result),
+            Objective::ProfitFactor => result.metrics.profit_factor,
+            Objective::MaxDrawdown => -result.max_drawdown,
+        }
+    }
+}
+
+/// Returns final equity over initial equity, less one.
+fn total_return(result: &BacktestResult) -> f64 {
+    result
+        .equity_curve
+        .first()
+        .filter(|first| first.equity > 0.0)
+        .map_or(0.0, |first| result.final_equity / first.equity - 1.0)
+}
+
+/// One backtest of the search.
+///
+/// # Fields
+/// * `rank`: Position by objective, starting at 1
+/// * `parameters`: Parameter values, in the order of the searched ranges
+/// * `objective`: Score by the search objective
+/// * `total_return`: Final equity over initial equity, less one
+/// * `sharpe_ratio`: Annualized Sharpe ratio
+/// * `max_drawdown`: Largest fall from a running equity peak
+/// * `win_rate`: Share of winning trades
+/// * `total_trades`: Number of trades
+/// * `returns`: Bar returns of the equity curve, for the overfitting statistics
+/// * `generation`: Generation of an evolutionary search the set was first tried
+///   in, starting at 0; 0 for other searches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trial {
+    pub rank: usize,
+    pub parameters: Vec<f64>,
+    pub objective: f64,
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub win_rate: f64,
+    pub total_trades: usize,
+    pub returns: Vec<f64>,
+    pub generation: usize,
+}
+
+/// Mean objective over a grid of two parameters.
+///
+/// `values[y][x]` averages the trials with `y_values[y]` and `x_values[x]`
+/// over all values of the other parameters; `None` where no trial ran.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heatmap {
+    pub x_parameter: String,
+    pub y_parameter: String,
+    pub x_values: Vec<f64>,
+    pub y_values: Vec<f64>,
+    pub values: Vec<Vec<Option<f64>>>,
+}
+
+/// Ranked results of a parameter search.
+///
+/// # Fields
+/// * `objective`: Statistic the trials are ranked by
+/// * `parameters`: Names of the searched parameters
+/// * `ranges`: Searched ranges, in the order of `parameters`
+/// * `trials`: One entry per parameter set, best first
+/// * `overfitting`: How much of the best trial's edge the number of trials
+///   explains, with a verdict; `None` if the backtests have too few bars
+/// * `convergence`: Best objective after each generation of an evolutionary
+///   search; empty for other searches
+/// * `stopped_early`: Whether early stopping ended an evolutionary search
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OptimizationReport {
+    pub objective: Objective,
+    pub parameters: Vec<String>,
+    pub ranges: Vec<ParameterRange>,
+    pub trials: Vec<Trial>,
+    pub overfitting: Option<OverfittingReport>,
+    pub convergence: Vec<f64>,
+    pub stopped_early: bool,
+}
+
+impl OptimizationReport {
+    /// Returns the best-ranked trial.
+    pub fn best(&self) -> Option<&Trial> {
+        self.trials.first()
+    }
+
+    /// Returns the value of a named parameter in a trial.
+    pub fn parameter(&self, trial: &Trial, name: &str) -> Option<f64> {
+        let index = self
+            .parameters
+            .iter()
+            .position(|parameter| parameter == name)?;
+        trial.parameters.get(index).copied()
+    }
+
+    /// Formats the trials as CSV with a header row, best first.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("rank,");
+        for name in &self.parameters {
+            csv.push_str(name);
+            csv.push(',');
+        }
+        csv.push_str("objective,total_return,sharpe_ratio,max_drawdown,win_rate,total_trades\n");
+        for trial in &self.trials {
+            csv.push_str(&format!("{},", trial.rank));
+            for value in &trial.parameters {
+                csv.push_str(&format!("{},", value));
+            }
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                trial.objective,
+                trial.total_return,
+                trial.sharpe_ratio,
+                trial.max_drawdown,
+                trial.win_rate,
+                trial.total_trades
+            ));
+        }
+        csv
+    }
+
+    /// Mean objective for each value of one parameter.
+    ///
+    /// Values are binned to the nearest of the range's `steps` grid values, so
+    /// searches over continuous ranges average the trials in each bin.
+    ///
+    /// # Returns
+    /// `(value, mean objective)` pairs in ascending order of value
+    ///
+    /// # Errors
+    /// Returns an error if `name` was not searched
+    pub fn sensitivity(&self, name: &str) -> Result<Vec<(f64, f64)>, OptimizerError> {
+        let index = self.index_of(name)?;
+        let bins = self.bins(index);
+        Ok(distinct(&bins)
+            .into_iter()
+            .map(|value| {
+                let scores: Vec<f64> = self
+                    .trials
+                    .iter()
+                    .zip(&bins)
+                    .filter(|(_, bin)| **bin == value)
+                    .map(|(trial, _)| trial.objective)
+                    .collect();
+                (value, scores.iter().sum::<f64>() / scores.len() as f64)
+            })
+            .collect())
+    }
+
+    /// Mean objective over every pair of values of two parameters.
+    ///
+    /// Values are binned as in `sensitivity`.
+    ///
+    /// # Errors
+    /// Returns an error if either parameter was not searched
+    pub fn heatmap(&self, x: &str, y: &str) -> Result<Heatmap, OptimizerError> {
+        let (x_index, y_index) = (self.index_of(x)?, self.index_of(y)?);
+        let (x_bins, y_bins) = (self.bins(x_index), self.bins(y_index));
+        let (x_values, y_values) = (distinct(&x_bins), distinct(&y_bins));
+        let values = y_values
+            .iter()
+            .map(|&y_value| {
+                x_values
+                    .iter()
+                    .map(|&x_value| {
+                        let scores: Vec<f64> = self
+                            .trials
+                            .iter()
+                            .zip(x_bins.iter().zip(&y_bins))
+                            .filter(|(_, (x_bin, y_bin))| **x_bin == x_value && **y_bin == y_value)
+                            .map(|(trial, _)| trial.objective)
+                            .collect();
+                        (!scores.is_empty())
+                            .then(|| scores.iter().sum::<f64>() / scores.len() as f64)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(Heatmap {
+            x_parameter: x.to_string(),
+            y_parameter: y.to_string(),
+            x_values,
+            y_values,
+            values,
+        })
+    }
+
+    fn index_of(&self, name: &str) -> Result<usize, OptimizerError> {
+        self.parameters
+            .iter()
+            .position(|parameter| parameter == name)
+            .ok_or_else(|| OptimizerError::NotSearched {
+                name: name.to_string(),
+            })
+    }
+
+    /// Bin of each trial's value of a parameter, in trial order.
+    fn bins(&self, index: usize) -> Vec<f64> {
+        self.trials
+            .iter()
+            .map(|trial| self.ranges[index].nearest_grid_value(trial.parameters[index]))
+            .collect()
+    }
+}
+
+/// Distinct values in ascending order.
+fn distinct(values: &[f64]) -> Vec<f64> {
+    let mut values = values.to_vec();
+    values.sort_by(f64::total_cmp);
+    values.dedup();
+    values
+}
+
+/// Searches strategy parameters for the best backtest by an objective.
+///
+/// Every candidate parameter set is applied to a copy of the configured
+/// backtester with `Backtester::set_parameters`, and the backtests run in
+/// parallel across CPU cores.
+#[derive(Clone)]
+pub struct Optimizer {
+    backtester: Backtester,
+    parameters: Vec<ParameterRange>,
+    search: SearchMethod,
+    objective: Objective,
+    seed: u64,
+    overfitting: OverfittingConfig,
+    constraints: Vec<Constraint>,
+    early_stopping: Option<EarlyStopping>,
+}
+
+impl Optimizer {
+    /// Creates an optimizer over a configured backtester.
+    ///
+    /// # Arguments
+    /// * `backtester` - Backtester with every setting other than the searched parameters
+    /// * `objective` - Statistic candidates are ranked by
+    pub fn new(backtester: Backtester, objective: Objective) -> Self {
+        Self {
+            backtester,
+            parameters: Vec::new(),
+            search: SearchMethod::Grid,
+            objective,
+            seed: 42,
+            overfitting: OverfittingConfig::default(),
+            constraints: Vec::new(),
+            early_stopping: None,
+        }
+    }
+
+    /// Adds a parameter to the search.
+    pub fn add_parameter(&mut self, range: ParameterRange) {
+        self.parameters.push(range);
+    }
+
+    /// Sets how candidate parameter sets are chosen; grid search by default.
+    pub fn set_search_method(&mut self, search: SearchMethod) {
+        self.search = search;
+    }
+
+    /// Adds a restriction that every tried parameter set must satisfy.
+    pub fn add_constraint(&mut self, constraint: Constraint) {
+        self.constraints.push(constraint);
+    }
+
+    /// Sets when an evolutionary search stops before its last generation.
+    ///
+    /// # Arguments
+    /// * `early_stopping` - Stall criterion, or `None` to run every generation
+    pub fn set_early_stopping(&mut self, early_stopping: Option<EarlyStopping>) {
+        self.early_stopping = early_stopping;
+    }
+
+    /// Sets the seed of random sampling and evolutionary searches.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    /// Sets the CSCV splits and verdict thresholds of the overfitting statistics.
+    pub fn set_overfitting_config(&mut self, config: OverfittingConfig) {
+        self.overfitting = config;
+    }
+
+    /// Returns the backtester candidates are applied to.
+    pub fn backtester(&self) -> &Backtester {
+        &self.backtester
+    }
+
+    /// Returns the backtester candidates are applied to, for changing its settings.
+    pub fn backtester_mut(&mut self) -> &mut Backtester {
+        &mut self.backtester
+    }
+
+    /// Returns the parameter sets the search tries.
+    ///
+    /// Each set holds one value per parameter, in the order they were added.
+    /// Sets that break a constraint are left out. Evolutionary searches choose
+    /// their sets while they run, and return none here.
+    pub fn candidates(&self) -> Vec<Vec<f64>> {
+        if self.parameters.is_empty() {
+            return Vec::new();
+        }
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let candidates: Vec<Vec<f64>> = match self.search {
+            SearchMethod::Grid => {
+                self.parameters
+                    .iter()
+                    .fold(vec![Vec::new()], |candidates, range| {
+                        candidates
+                            .iter()
+                            .flat_map(|candidate| {
+                                range.grid_values().into_iter().map(move |value| {
+                                    let mut candidate = candidate.clone();
+                                    candidate.push(value);
+                                    candidate
+                                })
+                            })
+                            .collect()
+                    })
+            }
+            SearchMethod::Random { samples } => (0..samples)
+                .map(|_| {
+                    self.parameters
+                        .iter()
+                        .map(|range| range.value_at(rng.gen::<f64>()))
+                        .collect()
+                })
+                .collect(),
+            SearchMethod::LatinHypercube { samples } => {
+                let mut candidates = vec![Vec::with_capacity(self.parameters.len()); samples];
+                for range in &self.parameters {
+                    let mut strata: Vec<usize> = (0..samples).collect();
+                    strata.shuffle(&mut rng);
+                    for (candidate, stratum) in candidates.iter_mut().zip(strata) {
+                        let position = (stratum as f64 + rng.gen::<f64>()) / samples as f64;
+                        candidate.push(range.value_at(position));
+                    }
+                }
+                candidates
+            }
+            SearchMethod::Genetic(_) | SearchMethod::CmaEs(_) => Vec::new(),
+        };
+        candidates
+            .into_iter()
+            .filter(|candidate| self.feasible(candidate))
+            .collect()
+    }
+
+    /// Runs a backtest for every candidate and ranks them by the objective.
+    ///
+    /// Evolutionary searches keep every distinct parameter set they try as a
+    /// trial, so the report holds the full history of the search.
+    ///
+    /// # Errors
+    /// Returns an error if the search is empty, a parameter does not belong to
+    /// the backtester's strategy mode, a constraint names a parameter that is
+    /// not searched, or a parameter receives a value it does not accept
+    pub fn run(&self, data: &[ProcessedMarketData]) -> Result<OptimizationReport, OptimizerError> {
+        // A parameter the strategy mode ignores would only repeat identical trials
+        let accepted = self.backtester.strategy_mode().parameters();
+        if let Some(range) = self
+            .parameters
+            .iter()
+            .find(|range| !accepted.contains(&range.name.as_str()))
+        {
+            return Err(OptimizerError::UnknownParameter {
+                name: range.name.clone(),
+            });
+        }
+        for constraint in &self.constraints {
+            if let Constraint::LessThan { lower, upper } = constraint {
+                for name in [lower, upper] {
+                    if !self.parameters.iter().any(|range| &range.name == name) {
+                        return Err(OptimizerError::NotSearched { name: name.clone() });
+                    }
+                }
+            }
+        }
+
+        let mut trials = Vec::new();
+        let mut generation = 0;
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let positions_to_values = |positions: &[f64]| -> Vec<f64> {
+            self.parameters
+                .iter()
+                .zip(positions)
+                .map(|(range, &position)| range.value_at(position))
+                .collect()
+        };
+        let feasible = |positions: &[f64]| self.feasible(&positions_to_values(positions));
+        let evaluate = |population: &[Vec<f64>]| {
+            let candidates: Vec<Vec<f64>> = population
+                .iter()
+                .map(|positions| positions_to_values(positions))
+                .collect();
+            let tried = |trials: &[Trial], candidate: &Vec<f64>| {
+                trials
+                    .iter()
+                    .find(|trial| &trial.parameters == candidate)
+                    .map(|trial| trial.objective)
+            };
+            let mut new: Vec<Vec<f64>> = Vec::new();
+            for candidate in &candidates {
+                if self.feasible(candidate)
+                    && tried(&trials, candidate).is_none()
+                    && !new.contains(candidate)
+                {
+                    new.push(candidate.clone());
+                }
+            }
+            trials.extend(self.evaluate(&new, generation, data)?);
+            generation += 1;
+
+            // Infeasible sets are never backtested and lose every comparison
+            Ok(candidates
+                .iter()
+                .map(|candidate| tried(&trials, candidate).unwrap_or(f64::NEG_INFINITY))
+                .collect())
+        };
+
+        let dimensions = self.parameters.len();
+        let mut convergence = Vec::new();
+        let mut stopped_early = false;
+        match self.search {
+            SearchMethod::Genetic(config) if dimensions > 0 => {
+                let evolution = evolution::genetic(
+                    &config,
+                    dimensions,
+                    &mut rng,
+                    self.early_stopping,
+                    feasible,
+                    evaluate,
+                )?;
+                (convergence, stopped_early) = (evolution.convergence, evolution.stopped_early);
+            }
+            SearchMethod::CmaEs(config) if dimensions > 0 => {
+                let evolution = evolution::cma_es(
+                    &config,
+                    dimensions,
+                    &mut rng,
+                    self.early_stopping,
+                    feasible,
+                    evaluate,
+                )?;
+                (convergence, stopped_early) = (evolution.convergence, evolution.stopped_early);
+            }
+            _ => trials = self.evaluate(&self.candidates(), 0, data)?,
+        }
+        if trials.is_empty() {
+            return Err(OptimizerError::EmptySpace);
+        }
+
+        trials.sort_by(|a, b| b.objective.total_cmp(&a.objective));
+        for (index, trial) in trials.iter_mut().enumerate() {
+            trial.rank = index + 1;
+        }
+
+        let overfitting = OverfittingReport::calculate(&trials, &self.overfitting);
+        Ok(OptimizationReport {
+            objective: self.objective,
+            parameters: self
+                .parameters
+                .iter()
+                .map(|range| range.name.clone())
+                .collect(),
+            ranges: self.parameters.clone(),
+            trials,
+            overfitting,
+            convergence,
+            stopped_early,
+        })
+    }
+
+    /// Backtests parameter sets in parallel and records them as unranked trials.
+    fn evaluate(
+        &self,
+        candidates: &[Vec<f64>],
+        generation: usize,
+        data: &[ProcessedMarketData],
+    ) -> Result<Vec<Trial>, OptimizerError> {
+        candidates
+            .par_iter()
+            .map(|candidate| {
+                let result = self.backtest(candidate, data)?;
+                Ok(Trial {
+                    rank: 0,
+                    parameters: candidate.clone(),
+                    objective: self.objective.score(&result),
+                    total_return: total_return(&result),
+                    sharpe_ratio: result.sharpe_ratio,
+                    max_drawdown: result.max_drawdown,
+                    win_rate: result.win_rate,
+                    total_trades: result.total_trades,
+                    returns: period_returns(&result.equity_curve),
+                    generation,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns `true` if a parameter set satisfies every constraint.
+    fn feasible(&self, values: &[f64]) -> bool {
+        let value = |name: &str| {
+            self.parameters
+                .iter()
+                .position(|range| range.name == name)
+                .and_then(|index| values.get(index).copied())
+        };
+        self.constraints.iter().all(|constraint| match constraint {
+            Constraint::LessThan { lower, upper } => match (value(lower), value(upper)) {
+                (Some(lower), Some(upper)) => lower < upper,
+                _ => true,
+            },
+            Constraint::Custom(allowed) => allowed(values),
+        })
+    }
+
+    /// Backtests one parameter set on a copy of the configured backtester.
+    ///
+    /// # Errors
+    /// Returns an error if a parameter is unknown or the value is invalid
+    pub fn backtest(
+        &self,
+        parameters: &[f64],
+        data: &[ProcessedMarketData],
+    ) -> Result<BacktestResult, OptimizerError> {
+        let mut backtester = self.backtester.clone();
+        let values: Vec<(&str, f64)> = self
+            .parameters
+            .iter()
+            .zip(parameters)
+            .map(|(range, &value)| (range.name.as_str(), value))
+            .collect();
+        backtester.set_parameters(&values)?;
+        Ok(backtester.run_backtest(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::StrategyMode;
+    use crate::data::{DataProcessor, Scenario};
+
+    fn optimizer() -> Optimizer {
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let mut optimizer = Optimizer::new(backtester, Objective::SharpeRatio);
+        optimizer.add_parameter(ParameterRange::new("rsi_oversold", 25.0, 45.0, 3));
+        optimizer.add_parameter(ParameterRange::integer("rsi_period", 10, 14));
+        optimizer
+    }
+
+    #[test]
+    fn test_search_methods() {
+        let mut optimizer = optimizer();
+        let grid = optimizer.candidates();
+        assert_eq!(grid.len(), 15);
+        assert_eq!(grid[0], vec![25.0, 10.0]);
+        assert_eq!(grid[14], vec![45.0, 14.0]);
+
+        optimizer.set_search_method(SearchMethod::Random { samples: 8 });
+        let random = optimizer.candidates();
+        assert_eq!(random.len(), 8);
+        assert_eq!(random, optimizer.candidates());
+        assert!(random
+            .iter()
+            .all(|set| (25.0..=45.0).contains(&set[0]) && set[1].fract() == 0.0));
+
+        // One sample per stratum of each range
+        optimizer.set_search_method(SearchMethod::LatinHypercube { samples: 4 });
+        let mut strata: Vec<usize> = optimizer
+            .candidates()
+            .iter()
+            .map(|set| (((set[0] - 25.0) / 20.0 * 4.0) as usize).min(3))
+            .collect();
+        strata.sort();
+        assert_eq!(strata, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_optimization_report() {
+        let data = DataProcessor::new(500)
+            .process_batch(Scenario::Sideways.generate(200, 42))
+            .unwrap();
+        let report = optimizer().run(&data).unwrap();
+
+        assert_eq!(report.trials.len(), 15);
+        assert_eq!(report.best().unwrap().rank, 1);
+        assert!(report
+            .trials
+            .windows(2)
+            .all(|pair| pair[0].objective >= pair[1].objective));
+
+        // The best trial reproduces when backtested on its own
+        let best = report.best().unwrap();
+        let rerun = optimizer().backtest(&best.parameters, &data).unwrap();
+        assert_eq!(rerun.sharpe_ratio, best.objective);
+
+        let heatmap = report.heatmap("rsi_period", "rsi_oversold").unwrap();
+        assert_eq!(heatmap.x_values, vec![10.0, 11.0, 12.0, 13.0, 14.0]);
+        assert_eq!(heatmap.y_values, vec![25.0, 35.0, 45.0]);
+        assert!(heatmap.values.iter().flatten().all(Option::is_some));
+        assert_eq!(report.sensitivity("rsi_period").unwrap().len(), 5);
+        assert!(report.heatmap("rsi_period", "bollinger_period").is_err());
+        assert_eq!(report.to_csv().lines().count(), 16);
+
+        let overfitting = report.overfitting.as_ref().unwrap();
+        assert_eq!(overfitting.trials, 15);
+        assert_eq!(overfitting.observations, 200);
+        assert!((0.0..=1.0).contains(&overfitting.deflated_sharpe));
+        assert!(overfitting.deflated_sharpe <= overfitting.probabilistic_sharpe);
+        assert!(overfitting.pbo.is_some());
+
+        let mut unknown = optimizer();
+        unknown.add_parameter(ParameterRange::new("lookback", 1.0, 2.0, 2));
+        assert!(matches!(
+            unknown.run(&data),
+            Err(OptimizerError::UnknownParameter { .. })
+        ));
+
+        // RSI parameters do nothing in Bollinger Bands mode
+        let mut ignored = optimizer();
+        ignored
+            .backtester_mut()
+            .set_strategy_mode(StrategyMode::BollingerBands);
+        assert!(matches!(
+            ignored.run(&data),
+            Err(OptimizerError::UnknownParameter { name }) if name == "rsi_oversold"
+        ));
+
+        // Random values are averaged within the range's grid bins
+        let mut random = optimizer();
+        random.set_search_method(SearchMethod::Random { samples: 12 });
+        let report = random.run(&data).unwrap();
+        let sensitivity = report.sensitivity("rsi_oversold").unwrap();
+        assert!(sensitivity
+            .iter()
+            .all(|(value, _)| [25.0, 35.0, 45.0].contains(value)));
+        let heatmap = report.heatmap("rsi_period", "rsi_oversold").unwrap();
+        assert!(heatmap.y_values.len() <= 3);
+        assert!(heatmap.x_values.len() <= 5);
+    }
+
+    #[test]
+    fn test_evolutionary_search() {
+        let data = DataProcessor::new(500)
+            .process_batch(Scenario::Sideways.generate(200, 42))
+            .unwrap();
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::BollingerBands);
+        let mut optimizer = Optimizer::new(backtester, Objective::SharpeRatio);
+        optimizer.add_parameter(ParameterRange::integer("bollinger_period", 10, 30));
+        optimizer.add_parameter(ParameterRange::new("bollinger_std_dev", 1.0, 3.0, 5));
+        optimizer.add_parameter(ParameterRange::integer("bollinger_trend_fast", 3, 20));
+        optimizer.add_parameter(ParameterRange::integer("bollinger_trend_slow", 10, 40));
+        optimizer.add_parameter(ParameterRange::new("bollinger_exit_fraction", 0.5, 1.0, 5));
+        optimizer.add_constraint(Constraint::LessThan {
+            lower: "bollinger_trend_fast".to_string(),
+            upper: "bollinger_trend_slow".to_string(),
+        });
+        optimizer.set_early_stopping(Some(EarlyStopping {
+            patience: 3,
+            min_improvement: 0.0,
+        }));
+        optimizer.set_search_method(SearchMethod::Genetic(GeneticConfig {
+            population: 8,
+            generations: 6,
+            ..Default::default()
+        }));
+
+        let report = optimizer.run(&data).unwrap();
+        assert!(!report.convergence.is_empty() && report.convergence.len() <= 6);
+        assert!(report.convergence.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert_eq!(
+            report.best().unwrap().objective,
+            *report.convergence.last().unwrap()
+        );
+        assert!(report
+            .trials
+            .iter()
+            .all(|trial| trial.parameters[2] < trial.parameters[3]));
+        assert_eq!(
+            report.overfitting.as_ref().unwrap().trials,
+            report.trials.len()
+        );
+
+        // Distinct sets only, each tagged with the generation it was first tried in
+        for (index, trial) in report.trials.iter().enumerate() {
+            assert!(trial.generation < report.convergence.len());
+            assert!(report.trials[..index]
+                .iter()
+                .all(|other| other.parameters != trial.parameters));
+        }
+        assert_eq!(
+            optimizer.run(&data).unwrap().best().unwrap().parameters,
+            report.best().unwrap().parameters
+        );
+
+        optimizer.set_search_method(SearchMethod::CmaEs(CmaEsConfig {
+            generations: 4,
+            ..Default::default()
+        }));
+        let cma_es = optimizer.run(&data).unwrap();
+        assert!(cma_es.convergence.len() <= 4);
+        assert!(cma_es
+            .trials
+            .iter()
+            .all(|trial| trial.parameters[2] < trial.parameters[3]));
+
+        optimizer.add_constraint(Constraint::LessThan {
+            lower: "rsi_period".to_string(),
+            upper: "bollinger_period".to_string(),
+        });
+        assert!(matches!(
+            optimizer.run(&data),
+            Err(OptimizerError::NotSearched { .. })
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Direction of an order.
+///
+/// - `Buy`: Increases a long or reduces a short position
+/// - `Sell`: Reduces a long or increases a short position
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl OrderSide {
+    /// Returns `1.0` for buys and `-1.0` for sells.
+    pub fn sign(self) -> f64 {
+        match self {
+            OrderSide::Buy => 1.0,
+            OrderSide::Sell => -1.0,
+        }
+    }
+}
+
+/// Distance of a trailing stop from the best price seen since submission.
+///
+/// - `Absolute`: Fixed price distance
+/// - `Percent`: Fraction of the best price, e.g. `0.05` for 5%
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Trail {
+    Absolute(f64),
+    Percent(f64),
+}
+
+impl Trail {
+    /// Returns the stop price trailing `reference` for an order on `side`.
+    ///
+    /// Sell stops trail below the highest price, buy stops above the lowest.
+    pub fn stop_price(self, reference: f64, side: OrderSide) -> f64 {
+        let distance = match self {
+            Trail::Absolute(distance) => distance,
+            Trail::Percent(fraction) => reference * fraction,
+        };
+        reference + side.sign() * distance
+    }
+}
+
+/// Execution condition of an order.
+///
+/// Fills are simulated against the bar range: limit orders fill when the bar
+/// trades at or through the limit, stops trigger when it trades at or through
+/// the trigger. A bar that gaps through the price fills at the open.
+///
+/// - `Market`: Fill at the next bar's open or close (see `FillTiming`)
+/// - `Limit`: Fill at `price` or better
+/// - `Stop`: Become a market order once `trigger` trades
+/// - `StopLimit`: Become a limit order at `limit` once `trigger` trades
+/// - `TrailingStop`: Stop that follows the best price seen by `trail`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OrderType {
+    Market,
+    Limit { price: f64 },
+    Stop { trigger: f64 },
+    StopLimit { trigger: f64, limit: f64 },
+    TrailingStop { trail: Trail },
+}
+
+impl OrderType {
+    /// Returns `true` for order types that execute as stops.
+    ///
+    /// Used to resolve one-cancels-other groups conservatively: when a stop and
+    /// a target would both fill on the same bar, the stop is assumed to fill.
+    pub fn is_stop(&self) -> bool {
+        matches!(
+            self,
+            OrderType::Stop { .. } | OrderType::StopLimit { .. } | OrderType::TrailingStop { .. }
+        )
+    }
+
+    /// Returns `true` for orders that execute against resting liquidity.
+    ///
+    /// Market, stop and trailing stop orders take liquidity when they fill;
+    /// limit and stop-limit orders rest at their limit price.
+    pub fn takes_liquidity(&self) -> bool {
+        matches!(
+            self,
+            OrderType::Market | OrderType::Stop { .. } | OrderType::TrailingStop { .. }
+        )
+    }
+}
+
+/// How long an order stays working.
+///
+/// - `Gtc`: Good till cancelled
+/// - `Ioc`: Immediate or cancel; fill what is possible on the first eligible bar
+/// - `Fok`: Fill or kill; fill completely on the first eligible bar or cancel
+/// - `Gtd`: Good till the given date; cancelled on the first bar after it
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum TimeInForce {
+    #[default]
+    Gtc,
+    Ioc,
+    Fok,
+    Gtd(DateTime<Utc>),
+}
+
+/// Parameters of an order before it is accepted by the exchange.
+///
+/// # Fields
+/// * `symbol`: Symbol to trade
+/// * `side`: Buy or sell
+/// * `quantity`: Unsigned quantity to trade
+/// * `order_type`: Execution condition
+/// * `time_in_force`: How long the order stays working
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderRequest {
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: f64,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+}
+
+impl OrderRequest {
+    /// Creates a good-till-cancelled order request.
+    pub fn new(symbol: &str, side: OrderSide, quantity: f64, order_type: OrderType) -> Self {
+        Self {
+            symbol: symbol.to_string(),
+            side,
+            quantity,
+            order_type,
+            time_in_force: TimeInForce::Gtc,
+        }
+    }
+
+    /// Creates a market order request.
+    pub fn market(symbol: &str, side: OrderSide, quantity: f64) -> Self {
+        Self::new(symbol, side, quantity, OrderType::Market)
+    }
+
+    /// Creates a limit order request.
+    pub fn limit(symbol: &str, side: OrderSide, quantity: f64, price: f64) -> Self {
+        Self::new(symbol, side, quantity, OrderType::Limit { price })
+    }
+
+    /// Creates a stop order request.
+    pub fn stop(symbol: &str, side: OrderSide, quantity: f64, trigger: f64) -> Self {
+        Self::new(symbol, side, quantity, OrderType::Stop { trigger })
+    }
+
+    /// Creates a stop-limit order request.
+    pub fn stop_limit(
+        symbol: &str,
+        side: OrderSide,
+        quantity: f64,
+        trigger: f64,
+        limit: f64,
+    ) -> Self {
+        Self::new(
+            symbol,
+            side,
+            quantity,
+            OrderType::StopLimit { trigger, limit },
+        )
+    }
+
+    /// Creates a trailing stop order request.
+    pub fn trailing_stop(symbol: &str, side: OrderSide, quantity: f64, trail: Trail) -> Self {
+        Self::new(symbol, side, quantity, OrderType::TrailingStop { trail })
+    }
+
+    /// Sets the time in force of the request.
+    pub fn with_time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.time_in_force = time_in_force;
+        self
+    }
+}
+
+/// An order accepted by the simulated exchange.
+///
+/// # Fields
+/// * `id`: Identifier assigned by the exchange, unique within a backtest
+/// * `symbol`: Symbol to trade
+/// * `side`: Buy or sell
+/// * `quantity`: Unsigned quantity to trade
+/// * `order_type`: Execution condition
+/// * `time_in_force`: How long the order stays working
+/// * `submitted_at`: Timestamp of the bar on which the order was generated
+/// * `filled_quantity`: Quantity filled so far
+/// * `oco_group`: Orders sharing a group cancel each other once one of them fills
+/// * `parent_id`: Entry order of a bracket; the order is inactive until it completes
+/// * `triggered`: Whether a stop-limit order's trigger has traded
+/// * `immediate`: Whether the order is matched against the bar it was submitted on
+/// * `trail_reference`: Best price seen by a trailing stop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: f64,
+    pub order_type: OrderType,
+    pub time_in_force: TimeInForce,
+    pub submitted_at: DateTime<Utc>,
+    pub filled_quantity: f64,
+    pub oco_group: Option<u64>,
+    pub parent_id: Option<u64>,
+    pub triggered: bool,
+    pub immediate: bool,
+    pub trail_reference: Option<f64>,
+}
+
+impl Order {
+    /// Returns the quantity still to be filled.
+    pub fn remaining(&self) -> f64 {
+        (self.quantity - self.filled_quantity).max(0.0)
+    }
+}
+
+/// Whether a fill added liquidity to the book or took it.
+///
+/// - `Maker`: A resting limit order was filled
+/// - `Taker`: A market, stop or trailing stop order executed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Liquidity {
+    Maker,
+    #[default]
+    Taker,
+}
+
+/// An execution of (part of) an order.
+///
+/// # Fields
+/// * `order_id`: Identifier of the filled order
+/// * `symbol`: Traded symbol
+/// * `side`: Buy or sell
+/// * `quantity`: Unsigned quantity filled
+/// * `price`: Execution price, including slippage
+/// * `timestamp`: Timestamp of the bar the fill happened on
+/// * `slippage`: Cost of the fill's slippage, in quote currency
+/// * `liquidity`: Whether the fill added or took liquidity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fill {
+    pub order_id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: f64,
+    pub price: f64,
+    pub timestamp: DateTime<Utc>,
+    pub slippage: f64,
+    pub liquidity: Liquidity,
+}
+
+impl Fill {
+    /// Returns the fill quantity signed by side.
+    pub fn signed_quantity(&self) -> f64 {
+        self.side.sign() * self.quantity
+    }
+}
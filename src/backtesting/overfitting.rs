@@ -0,0 +1,439 @@
+use super::metrics::{mean, sharpe_ratio, standardized_moment, std_dev};
+use super::optimizer::Trial;
+use serde::{Deserialize, Serialize};
+
+const EULER_MASCHERONI: f64 = 0.577_215_664_901_532_9;
+
+/// Most blocks combinatorially symmetric cross-validation splits the bars into;
+/// 20 blocks already give 184,756 in-sample choices.
+pub const MAX_CSCV_SPLITS: usize = 20;
+
+/// Thresholds and splits of the overfitting statistics.
+///
+/// # Fields
+/// * `cscv_splits`: Number of blocks the bars are split into for
+///   combinatorially symmetric cross-validation; rounded down to an even number
+///   and capped at `MAX_CSCV_SPLITS`
+/// * `confidence`: Deflated Sharpe ratio a search needs to be judged robust
+/// * `max_pbo`: Probability of backtest overfitting at or above which a search
+///   is judged overfit
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OverfittingConfig {
+    pub cscv_splits: usize,
+    pub confidence: f64,
+    pub max_pbo: f64,
+}
+
+impl Default for OverfittingConfig {
+    fn default() -> Self {
+        Self {
+            cscv_splits: 16,
+            confidence: 0.95,
+            max_pbo: 0.5,
+        }
+    }
+}
+
+/// Overall judgement on whether the best trial's edge is real.
+///
+/// - `Robust`: The deflated Sharpe ratio reaches the confidence level, the
+///   probability of overfitting is below the limit and the backtest is at
+///   least the minimum length
+/// - `Inconclusive`: Neither robust nor likely overfit
+/// - `LikelyOverfit`: The probability of overfitting reaches the limit, or the
+///   best Sharpe ratio is more likely than not explained by the number of trials
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverfittingVerdict {
+    Robust,
+    Inconclusive,
+    LikelyOverfit,
+}
+
+/// Statistics on how much of the best trial's Sharpe ratio is selection luck.
+///
+/// Sharpe ratios here are per bar and not annualized, as the formulas of
+/// Bailey and López de Prado expect.
+///
+/// # Fields
+/// * `trials`: Number of parameter sets tried
+/// * `observations`: Number of bar returns of each trial
+/// * `sharpe_ratio`: Per-bar Sharpe ratio of the best trial
+/// * `expected_max_sharpe`: Highest per-bar Sharpe ratio expected from this
+///   many trials without skill
+/// * `probabilistic_sharpe`: Probability the best trial's true Sharpe ratio is above 0
+/// * `deflated_sharpe`: Probability the best trial's true Sharpe ratio is above
+///   `expected_max_sharpe`
+/// * `min_backtest_length`: Bars needed before the best Sharpe ratio stops being
+///   expected from this many trials; `None` if the best Sharpe ratio is not positive
+/// * `pbo`: Probability of backtest overfitting by combinatorially symmetric
+///   cross-validation; `None` with fewer than two trials or too few bars
+/// * `verdict`: Overall judgement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OverfittingReport {
+    pub trials: usize,
+    pub observations: usize,
+    pub sharpe_ratio: f64,
+    pub expected_max_sharpe: f64,
+    pub probabilistic_sharpe: f64,
+    pub deflated_sharpe: f64,
+    pub min_backtest_length: Option<f64>,
+    pub pbo: Option<f64>,
+    pub verdict: OverfittingVerdict,
+}
+
+impl OverfittingReport {
+    /// Calculates the overfitting statistics of a parameter search.
+    ///
+    /// # Arguments
+    /// * `trials` - Trials of the search, best first, with their bar returns
+    /// * `config` - CSCV splits and verdict thresholds
+    ///
+    /// # Returns
+    /// `None` if there are no trials or the best trial has fewer than two returns
+    pub fn calculate(trials: &[Trial], config: &OverfittingConfig) -> Option<Self> {
+        let best = trials.first()?;
+        let observations = best.returns.len();
+        if observations < 2 {
+            return None;
+        }
+
+        let sharpe_ratios: Vec<f64> = trials
+            .iter()
+            .map(|trial| sharpe_ratio(&trial.returns, 1.0, 0.0))
+            .collect();
+        let sharpe = sharpe_ratios[0];
+        let expected_max_sharpe = expected_max_sharpe(&sharpe_ratios);
+        let probabilistic_sharpe = probabilistic_sharpe_ratio(&best.returns, 0.0);
+        let deflated_sharpe = probabilistic_sharpe_ratio(&best.returns, expected_max_sharpe);
+        let min_backtest_length = minimum_backtest_length(sharpe, trials.len());
+
+        let returns: Vec<&[f64]> = trials
+            .iter()
+            .map(|trial| trial.returns.as_slice())
+            .collect();
+        let pbo = probability_of_backtest_overfitting(&returns, config.cscv_splits);
+
+        let verdict = if pbo.is_some_and(|pbo| pbo >= config.max_pbo) || deflated_sharpe < 0.5 {
+            OverfittingVerdict::LikelyOverfit
+        } else if deflated_sharpe >= config.confidence
+            && pbo.is_some_and(|pbo| pbo < config.max_pbo)
+            && min_backtest_length.is_some_and(|length| observations as f64 >= length)
+        {
+            OverfittingVerdict::Robust
+        } else {
+            OverfittingVerdict::Inconclusive
+        };
+
+        Some(Self {
+            trials: trials.len(),
+            observations,
+            sharpe_ratio: sharpe,
+            expected_max_sharpe,
+            probabilistic_sharpe,
+            deflated_sharpe,
+            min_backtest_length,
+            pbo,
+            verdict,
+        })
+    }
+}
+
+/// Probability that the true Sharpe ratio of a return series exceeds a benchmark.
+///
+/// Corrects the standard error of the observed per-bar Sharpe ratio for the
+/// skewness and kurtosis of the returns.
+///
+/// # Arguments
+/// * `returns` - Bar returns
+/// * `benchmark` - Per-bar Sharpe ratio to beat
+pub fn probabilistic_sharpe_ratio(returns: &[f64], benchmark: f64) -> f64 {
+    let n = returns.len() as f64;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let std_dev = std_dev(returns);
+    if std_dev == 0.0 {
+        return 0.0;
+    }
+    let skewness = standardized_moment(returns, 3);
+    let kurtosis = standardized_moment(returns, 4);
+
+    let sharpe = mean(returns) / std_dev;
+    let variance = 1.0 - skewness * sharpe + (kurtosis - 1.0) / 4.0 * sharpe.powi(2);
+    if variance <= 0.0 {
+        return if sharpe > benchmark { 1.0 } else { 0.0 };
+    }
+    normal_cdf((sharpe - benchmark) * (n - 1.0).sqrt() / variance.sqrt())
+}
+
+/// Expected maximum of `trials` standard normal draws, in standard deviations.
+fn expected_max_z(trials: usize) -> f64 {
+    if trials < 2 {
+        return 0.0;
+    }
+    let trials = trials as f64;
+    (1.0 - EULER_MASCHERONI) * normal_quantile(1.0 - 1.0 / trials)
+        + EULER_MASCHERONI * normal_quantile(1.0 - 1.0 / (trials * std::f64::consts::E))
+}
+
+/// Highest Sharpe ratio expected from a set of trials without skill.
+///
+/// # Arguments
+/// * `sharpe_ratios` - Sharpe ratios of all trials, whose spread scales the expectation
+pub fn expected_max_sharpe(sharpe_ratios: &[f64]) -> f64 {
+    let n = sharpe_ratios.len() as f64;
+    if sharpe_ratios.len() < 2 {
+        return 0.0;
+    }
+    let mean = sharpe_ratios.iter().sum::<f64>() / n;
+    let variance = sharpe_ratios
+        .iter()
+        .map(|sharpe| (sharpe - mean).powi(2))
+        .sum::<f64>()
+        / (n - 1.0);
+    variance.sqrt() * expected_max_z(sharpe_ratios.len())
+}
+
+/// Minimum backtest length, in bars, for a Sharpe ratio to beat selection luck.
+///
+/// Below this length, the best of `trials` skill-less strategies is expected
+/// to reach `sharpe` by chance.
+///
+/// # Arguments
+/// * `sharpe` - Per-bar Sharpe ratio of the best trial
+/// * `trials` - Number of trials
+///
+/// # Returns
+/// `None` if `sharpe` is not positive
+pub fn minimum_backtest_length(sharpe: f64, trials: usize) -> Option<f64> {
+    (sharpe > 0.0).then(|| (expected_max_z(trials) / sharpe).powi(2))
+}
+
+/// Probability of backtest overfitting by combinatorially symmetric cross-validation.
+///
+/// The bars are split into `splits` blocks. For every way of choosing half the
+/// blocks as in-sample, the trial with the best in-sample Sharpe ratio is
+/// ranked among all trials on the other half; the probability is the share of
+/// choices where it ranks at or below the median.
+///
+/// # Arguments
+/// * `returns` - Bar returns of every trial, over the same bars
+/// * `splits` - Number of blocks; rounded down to an even number and capped
+///   at `MAX_CSCV_SPLITS`
+///
+/// # Returns
+/// `None` with fewer than two trials, fewer than two blocks, or fewer bars than blocks
+pub fn probability_of_backtest_overfitting(returns: &[&[f64]], splits: usize) -> Option<f64> {
+    let splits = splits.min(MAX_CSCV_SPLITS);
+    let splits = splits - splits % 2;
+    let bars = returns.iter().map(|series| series.len()).min()?;
+    if returns.len() < 2 || splits < 2 || bars < splits {
+        return None;
+    }
+
+    // Sum, sum of squares and count of each trial's returns per block
+    let blocks: Vec<Vec<(f64, f64, f64)>> = returns
+        .iter()
+        .map(|series| {
+            (0..splits)
+                .map(|block| {
+                    let block = &series[block * bars / splits..(block + 1) * bars / splits];
+                    (
+                        block.iter().sum(),
+                        block.iter().map(|r| r * r).sum(),
+                        block.len() as f64,
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    let sharpe = |trial: usize, mask: u64| {
+        let (sum, sum_squares, count) = blocks[trial]
+            .iter()
+            .enumerate()
+            .filter(|(block, _)| mask & (1 << block) != 0)
+            .fold((0.0, 0.0, 0.0), |total, (_, block)| {
+                (total.0 + block.0, total.1 + block.1, total.2 + block.2)
+            });
+        let mean = sum / count;
+        let std_dev = (sum_squares / count - mean * mean).max(0.0).sqrt();
+        if std_dev > 0.0 {
+            mean / std_dev
+        } else {
+            0.0
+        }
+    };
+
+    // Visit each mask with half the blocks set, in increasing order (Gosper's hack)
+    let all = (1u64 << splits) - 1;
+    let (mut combinations, mut overfit) = (0usize, 0usize);
+    let mut in_sample = (1u64 << (splits / 2)) - 1;
+    while in_sample <= all {
+        let out_of_sample = all & !in_sample;
+        let best = (0..returns.len())
+            .map(|trial| (trial, sharpe(trial, in_sample)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(trial, _)| trial)?;
+
+        let out_of_sample: Vec<f64> = (0..returns.len())
+            .map(|trial| sharpe(trial, out_of_sample))
+            .collect();
+        let rank = out_of_sample
+            .iter()
+            .filter(|sharpe| **sharpe < out_of_sample[best])
+            .count()
+            + 1;
+        let relative_rank = rank as f64 / (returns.len() + 1) as f64;
+        if (relative_rank / (1.0 - relative_rank)).ln() <= 0.0 {
+            overfit += 1;
+        }
+        combinations += 1;
+
+        let lowest = in_sample & in_sample.wrapping_neg();
+        let ripple = in_sample + lowest;
+        in_sample = (((ripple ^ in_sample) >> 2) / lowest) | ripple;
+    }
+
+    Some(overfit as f64 / combinations as f64)
+}
+
+/// Standard normal cumulative distribution function.
+pub(crate) fn normal_cdf(x: f64) -> f64 {
+    0.5 * (1.0 + erf(x / std::f64::consts::SQRT_2))
+}
+
+/// Error function, accurate to about 1e-7 (Abramowitz and Stegun 7.1.26).
+fn erf(x: f64) -> f64 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let value = 1.0 - polynomial * (-x * x).exp();
+    if x >= 0.0 {
+        value
+    } else {
+        -value
+    }
+}
+
+/// Inverse of the standard normal cumulative distribution function (Acklam's approximation).
+pub(crate) fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < 0.024_25 {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - 0.024_25 {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rand_distr::{Distribution, Normal};
+
+    fn noise(trials: usize, bars: usize, drift: f64) -> Vec<Vec<f64>> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let normal = Normal::new(drift, 0.01).unwrap();
+        (0..trials)
+            .map(|_| (0..bars).map(|_| normal.sample(&mut rng)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_normal_distribution() {
+        assert!((normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        assert!((normal_quantile(0.975) - 1.959_964).abs() < 1e-5);
+        assert!((normal_quantile(0.01) + 2.326_348).abs() < 1e-5);
+        assert!((normal_cdf(normal_quantile(0.3)) - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_sharpe_statistics() {
+        let skilled = &noise(1, 500, 0.002)[0];
+        assert!(probabilistic_sharpe_ratio(skilled, 0.0) > 0.99);
+        assert!(probabilistic_sharpe_ratio(skilled, 1.0) < 0.01);
+
+        // More trials raise the bar a lucky Sharpe ratio has to clear
+        let sharpes: Vec<f64> = (0..100).map(|i| (i as f64 - 50.0) / 500.0).collect();
+        assert!(expected_max_sharpe(&sharpes) > expected_max_sharpe(&sharpes[..10]));
+        assert_eq!(expected_max_sharpe(&sharpes[..1]), 0.0);
+
+        assert!(
+            minimum_backtest_length(0.1, 100).unwrap() > minimum_backtest_length(0.1, 10).unwrap()
+        );
+        assert_eq!(minimum_backtest_length(-0.1, 10), None);
+    }
+
+    #[test]
+    fn test_probability_of_backtest_overfitting() {
+        // Pure noise: the in-sample winner is no better than a coin flip out of sample
+        let noise_trials = noise(20, 320, 0.0);
+        let series: Vec<&[f64]> = noise_trials.iter().map(Vec::as_slice).collect();
+        let pbo = probability_of_backtest_overfitting(&series, 10).unwrap();
+        assert!(pbo > 0.2);
+
+        // One trial with a real edge wins in and out of sample
+        let mut skilled = noise(20, 320, 0.0);
+        skilled[3] = noise(1, 320, 0.004).remove(0);
+        let series: Vec<&[f64]> = skilled.iter().map(Vec::as_slice).collect();
+        assert!(probability_of_backtest_overfitting(&series, 10).unwrap() < 0.05);
+
+        // Splits beyond the cap are evaluated at the cap instead of enumerating 2^40 masks
+        let pair = &series[2..4];
+        assert_eq!(
+            probability_of_backtest_overfitting(pair, 40),
+            probability_of_backtest_overfitting(pair, MAX_CSCV_SPLITS)
+        );
+
+        assert_eq!(probability_of_backtest_overfitting(&series[..1], 10), None);
+        assert_eq!(probability_of_backtest_overfitting(&series, 1), None);
+    }
+}
@@ -0,0 +1,154 @@
+use crate::data::MarketData;
+use serde::{Deserialize, Serialize};
+
+/// Distance of a protective exit from the entry price.
+///
+/// - `Percent`: Fraction of the entry price, e.g. `0.05` for 5%
+/// - `Atr`: Multiple of the average true range at entry
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ExitDistance {
+    Percent(f64),
+    Atr(f64),
+}
+
+impl ExitDistance {
+    /// Converts the distance to a price amount.
+    ///
+    /// # Returns
+    /// `None` for ATR distances while the ATR is not available yet
+    pub fn amount(self, entry_price: f64, atr: Option<f64>) -> Option<f64> {
+        match self {
+            ExitDistance::Percent(fraction) => Some(entry_price * fraction),
+            ExitDistance::Atr(multiple) => atr.map(|atr| atr * multiple),
+        }
+    }
+}
+
+/// Why a trade was closed.
+///
+/// - `Signal`: An opposite strategy signal
+/// - `StopLoss`: The fixed stop-loss was hit
+/// - `TakeProfit`: The take-profit target was reached
+/// - `TrailingStop`: The trailing stop was hit
+/// - `BreakEven`: The stop moved to the entry price was hit
+/// - `TimeStop`: The position was held for the maximum number of bars
+/// - `Liquidation`: The position's margin fell to the maintenance margin
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ExitReason {
+    Signal,
+    StopLoss,
+    TakeProfit,
+    TrailingStop,
+    BreakEven,
+    TimeStop,
+    Liquidation,
+}
+
+/// Protective exits attached to every position.
+///
+/// Stops and targets are placed as one-cancels-other orders as soon as the
+/// entry fills and are evaluated intrabar against each bar's high and low. When
+/// a stop and the target both fall inside one bar, the stop is assumed to have
+/// been hit first.
+///
+/// # Fields
+/// * `stop_loss`: Distance of the stop below (long) or above (short) the entry
+/// * `take_profit`: Distance of the profit target from the entry
+/// * `trailing_stop`: Distance the trailing stop follows the best price at
+/// * `break_even`: Favourable move after which the stop is moved to the entry price
+/// * `max_bars_held`: Close the position at the next open after this many bars
+/// * `atr_period`: Lookback of the average true range used by ATR distances
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ExitRules {
+    pub stop_loss: Option<ExitDistance>,
+    pub take_profit: Option<ExitDistance>,
+    pub trailing_stop: Option<ExitDistance>,
+    pub break_even: Option<ExitDistance>,
+    pub max_bars_held: Option<usize>,
+    pub atr_period: usize,
+}
+
+impl Default for ExitRules {
+    fn default() -> Self {
+        Self {
+            stop_loss: None,
+            take_profit: None,
+            trailing_stop: None,
+            break_even: None,
+            max_bars_held: None,
+            atr_period: 14,
+        }
+    }
+}
+
+impl ExitRules {
+    /// Returns `true` if no protective exit is configured.
+    pub fn is_empty(&self) -> bool {
+        self.stop_loss.is_none()
+            && self.take_profit.is_none()
+            && self.trailing_stop.is_none()
+            && self.break_even.is_none()
+            && self.max_bars_held.is_none()
+    }
+}
+
+/// Calculates Wilder's average true range.
+///
+/// # Arguments
+/// * `data`: Bars, oldest first
+/// * `period`: Smoothing period
+///
+/// # Returns
+/// One value per bar; `None` until `period` true ranges are available
+pub fn average_true_range(data: &[MarketData], period: usize) -> Vec<Option<f64>> {
+    let mut atr = Vec::with_capacity(data.len());
+    let mut current: Option<f64> = None;
+    let mut sum = 0.0;
+
+    for (index, bar) in data.iter().enumerate() {
+        let true_range = match index.checked_sub(1).map(|previous| data[previous].price) {
+            Some(previous_close) => (bar.high - bar.low)
+                .max((bar.high - previous_close).abs())
+                .max((bar.low - previous_close).abs()),
+            None => bar.high - bar.low,
+        };
+
+        current = match current {
+            Some(value) => Some((value * (period as f64 - 1.0) + true_range) / period as f64),
+            None => {
+                sum += true_range;
+                (period > 0 && index + 1 == period).then(|| sum / period as f64)
+            }
+        };
+        atr.push(current);
+    }
+
+    atr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::test_support::daily_bars;
+
+    #[test]
+    fn test_average_true_range() {
+        let data: Vec<MarketData> = daily_bars(&[100.0; 5])
+            .into_iter()
+            .map(|bar| MarketData {
+                high: 102.0,
+                low: 98.0,
+                ..bar
+            })
+            .collect();
+
+        let atr = average_true_range(&data, 3);
+        assert_eq!(atr[1], None);
+        assert_eq!(atr[2], Some(4.0));
+        assert_eq!(atr[4], Some(4.0));
+
+        assert_eq!(ExitDistance::Atr(2.0).amount(100.0, atr[4]), Some(8.0));
+        assert_eq!(ExitDistance::Atr(2.0).amount(100.0, atr[0]), None);
+        assert_eq!(ExitDistance::Percent(0.05).amount(200.0, None), Some(10.0));
+    }
+}
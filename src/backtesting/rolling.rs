@@ -0,0 +1,267 @@
+use super::backtester::{EquityPoint, Trade};
+use super::benchmark::beta;
+use super::drawdown::drawdown_from_peak;
+use super::metrics::{period_returns, sharpe_ratio, simple_returns, std_dev, MetricsConfig};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Metrics over the window of bars ending at one point of the equity curve.
+///
+/// # Fields
+/// * `timestamp`: Timestamp of the equity point the window ends at
+/// * `sharpe_ratio`: Annualized Sharpe ratio of the window's returns
+/// * `volatility`: Annualized standard deviation of the window's returns
+/// * `win_rate`: Share of winning trades among those closed in the window;
+///   `None` if no trade closed
+/// * `max_drawdown`: Largest fall from a peak within the window
+/// * `beta`: Beta against the benchmark over the window; `None` without one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollingPoint {
+    pub timestamp: DateTime<Utc>,
+    pub sharpe_ratio: f64,
+    pub volatility: f64,
+    pub win_rate: Option<f64>,
+    pub max_drawdown: f64,
+    pub beta: Option<f64>,
+}
+
+/// Time series of metrics over a rolling window of bars.
+///
+/// `points[k]` covers the `window` returns ending at equity point
+/// `k + window`, so the series starts once a full window is available.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RollingMetrics {
+    pub window: usize,
+    pub points: Vec<RollingPoint>,
+}
+
+impl RollingMetrics {
+    /// Calculates rolling metrics along an equity curve.
+    ///
+    /// # Arguments
+    /// * `equity_curve` - Equity before the first bar, then after every bar
+    /// * `trades` - Trades of the backtest, counted in the window they closed in
+    /// * `benchmark` - Benchmark equity aligned with the end of the equity curve,
+    ///   for beta; windows before the benchmark starts have no beta
+    /// * `window` - Number of bar returns in each window
+    /// * `config` - Annualization and risk-free rate
+    pub fn calculate(
+        equity_curve: &[EquityPoint],
+        trades: &[Trade],
+        benchmark: Option<&[f64]>,
+        window: usize,
+        config: &MetricsConfig,
+    ) -> Self {
+        let mut rolling = Self {
+            window,
+            points: Vec::new(),
+        };
+        if window == 0 {
+            return rolling;
+        }
+
+        let periods = config.periods_per_year;
+        let returns = period_returns(equity_curve);
+        let benchmark_returns = benchmark.map(simple_returns);
+        let offset = benchmark.map_or(0, |benchmark| {
+            equity_curve.len().saturating_sub(benchmark.len())
+        });
+
+        for end in window..equity_curve.len() {
+            let window_returns = &returns[end - window..end];
+            let volatility = std_dev(window_returns) * periods.sqrt();
+            let sharpe_ratio = sharpe_ratio(window_returns, periods, config.risk_free_rate);
+
+            let (start, end_point) = (&equity_curve[end - window], &equity_curve[end]);
+            let closed: Vec<f64> = trades
+                .iter()
+                .filter(|trade| {
+                    trade
+                        .exit_time
+                        .is_some_and(|exit| exit > start.timestamp && exit <= end_point.timestamp)
+                })
+                .filter_map(|trade| trade.pnl)
+                .collect();
+            let win_rate = (!closed.is_empty()).then(|| {
+                closed.iter().filter(|pnl| **pnl > 0.0).count() as f64 / closed.len() as f64
+            });
+
+            let mut peak = start.equity;
+            let mut max_drawdown: f64 = 0.0;
+            for point in &equity_curve[end - window..=end] {
+                peak = peak.max(point.equity);
+                max_drawdown = max_drawdown.max(drawdown_from_peak(peak, point.equity));
+            }
+
+            let beta = benchmark_returns
+                .as_ref()
+                .filter(|benchmark| end - window >= offset && benchmark.len() >= end - offset)
+                .map(|benchmark| {
+                    beta(
+                        window_returns,
+                        &benchmark[end - window - offset..end - offset],
+                    )
+                });
+
+            rolling.points.push(RollingPoint {
+                timestamp: end_point.timestamp,
+                sharpe_ratio,
+                volatility,
+                win_rate,
+                max_drawdown,
+                beta,
+            });
+        }
+
+        rolling
+    }
+
+    /// Returns the metrics of the window ending at an equity point.
+    ///
+    /// # Returns
+    /// `None` while fewer than `window` returns precede the point
+    pub fn at(&self, equity_index: usize) -> Option<&RollingPoint> {
+        self.points.get(equity_index.checked_sub(self.window)?)
+    }
+
+    /// Formats the series as CSV with a header row, for plotting.
+    ///
+    /// Missing win rates and betas are left empty.
+    pub fn to_csv(&self) -> String {
+        let optional =
+            |value: Option<f64>| value.map_or_else(String::new, |value| value.to_string());
+        let mut csv =
+            String::from("timestamp,sharpe_ratio,volatility,win_rate,max_drawdown,beta\n");
+        for point in &self.points {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                point.timestamp.to_rfc3339(),
+                point.sharpe_ratio,
+                point.volatility,
+                optional(point.win_rate),
+                point.max_drawdown,
+                optional(point.beta)
+            ));
+        }
+        csv
+    }
+}
+
+/// Thresholds on rolling metrics that keep a sub-strategy enabled.
+///
+/// In `StrategyMode::Combined`, each sub-strategy is also run on its own, and
+/// its signals are ignored on bars where its rolling metrics up to the
+/// previous bar break a threshold. Sub-strategies stay enabled until a full
+/// window of history is available.
+///
+/// # Fields
+/// * `window`: Number of bar returns in the rolling window
+/// * `min_sharpe`: Lowest rolling Sharpe ratio allowed
+/// * `min_win_rate`: Lowest rolling win rate allowed; windows without closed trades pass
+/// * `max_drawdown`: Largest drawdown within the window allowed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StrategyGate {
+    pub window: usize,
+    pub min_sharpe: Option<f64>,
+    pub min_win_rate: Option<f64>,
+    pub max_drawdown: Option<f64>,
+}
+
+impl Default for StrategyGate {
+    fn default() -> Self {
+        Self {
+            window: 30,
+            min_sharpe: Some(0.0),
+            min_win_rate: None,
+            max_drawdown: None,
+        }
+    }
+}
+
+impl StrategyGate {
+    /// Returns `true` if the rolling metrics keep a sub-strategy enabled.
+    pub fn allows(&self, point: &RollingPoint) -> bool {
+        self.min_sharpe
+            .is_none_or(|min_sharpe| point.sharpe_ratio >= min_sharpe)
+            && self.min_win_rate.is_none_or(|min_win_rate| {
+                point
+                    .win_rate
+                    .is_none_or(|win_rate| win_rate >= min_win_rate)
+            })
+            && self
+                .max_drawdown
+                .is_none_or(|max_drawdown| point.max_drawdown <= max_drawdown)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::test_support::curve;
+
+    #[test]
+    fn test_rolling_metrics() {
+        let equity = curve(&[100.0, 110.0, 99.0, 108.9, 119.79, 100.0]);
+        let benchmark: Vec<f64> = equity.iter().map(|point| point.equity * 2.0).collect();
+        let config = MetricsConfig {
+            risk_free_rate: 0.0,
+            ..Default::default()
+        };
+
+        let rolling = RollingMetrics::calculate(&equity, &[], Some(&benchmark), 2, &config);
+        assert_eq!(rolling.points.len(), 4);
+        assert_eq!(rolling.at(1), None);
+        assert_eq!(rolling.at(2).unwrap().timestamp, equity[2].timestamp);
+
+        // +10% then -10%: zero mean, 10% per-bar deviation
+        let first = rolling.at(2).unwrap();
+        assert!(first.sharpe_ratio.abs() < 1e-9);
+        assert!((first.volatility - 0.1 * 365.0_f64.sqrt()).abs() < 1e-9);
+        assert!((first.max_drawdown - 0.1).abs() < 1e-9);
+        assert!((first.beta.unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(first.win_rate, None);
+
+        // Two rising bars: no drawdown within the window
+        let rising = rolling.at(4).unwrap();
+        assert_eq!(rising.max_drawdown, 0.0);
+
+        // A benchmark that starts a bar later has no beta for the first window
+        let late = RollingMetrics::calculate(&equity, &[], Some(&benchmark[1..]), 2, &config);
+        assert_eq!(late.at(2).unwrap().beta, None);
+        assert!((late.at(3).unwrap().beta.unwrap() - 1.0).abs() < 1e-9);
+
+        let csv = rolling.to_csv();
+        assert_eq!(csv.lines().count(), 5);
+        assert!(csv.starts_with("timestamp,sharpe_ratio"));
+    }
+
+    #[test]
+    fn test_strategy_gate() {
+        let gate = StrategyGate {
+            min_win_rate: Some(0.4),
+            max_drawdown: Some(0.2),
+            ..Default::default()
+        };
+        let point = RollingPoint {
+            timestamp: Utc::now(),
+            sharpe_ratio: 0.5,
+            volatility: 0.3,
+            win_rate: None,
+            max_drawdown: 0.1,
+            beta: None,
+        };
+        assert!(gate.allows(&point));
+        assert!(!gate.allows(&RollingPoint {
+            sharpe_ratio: -0.1,
+            ..point.clone()
+        }));
+        assert!(!gate.allows(&RollingPoint {
+            win_rate: Some(0.3),
+            ..point.clone()
+        }));
+        assert!(!gate.allows(&RollingPoint {
+            max_drawdown: 0.25,
+            ..point
+        }));
+    }
+}
@@ -0,0 +1,301 @@
+use super::backtester::Trade;
+use crate::data::MarketData;
+use serde::{Deserialize, Serialize};
+
+/// Determines the notional value of each new position.
+///
+/// - `Fixed`: Constant notional per trade
+/// - `FixedFractional`: Fraction of current equity
+/// - `VolatilityTarget`: Scale exposure so the position's annualized volatility
+///   matches `annual_volatility`, using realized volatility over `lookback` bars
+///   annualized with `MetricsConfig::periods_per_year`
+/// - `AtrRisk`: Risk `risk_fraction` of equity per trade with a stop
+///   `atr_multiple` ATRs away, using the ATR over `ExitRules::atr_period`
+/// - `Kelly`: `fraction` of the Kelly fraction estimated from the last
+///   `lookback` closed trades; `fallback_fraction` of equity until `min_trades`
+///   trades are available
+/// - `SignalStrength`: Up to `max_fraction` of equity, scaled by signal strength
+///
+/// Models that lack the data they need (e.g. during warm-up) size to zero and
+/// the signal is skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PositionSizing {
+    Fixed {
+        notional: f64,
+    },
+    FixedFractional {
+        fraction: f64,
+    },
+    VolatilityTarget {
+        annual_volatility: f64,
+        lookback: usize,
+    },
+    AtrRisk {
+        risk_fraction: f64,
+        atr_multiple: f64,
+    },
+    Kelly {
+        fraction: f64,
+        lookback: usize,
+        min_trades: usize,
+        fallback_fraction: f64,
+    },
+    SignalStrength {
+        max_fraction: f64,
+    },
+}
+
+/// Caps applied to every sized position.
+///
+/// # Fields
+/// * `max_position_fraction`: Maximum notional of a single position as a fraction of equity
+/// * `max_leverage`: Maximum gross exposure across all positions as a multiple of equity
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SizingLimits {
+    pub max_position_fraction: Option<f64>,
+    pub max_leverage: Option<f64>,
+}
+
+/// Market and account state available when sizing a position.
+///
+/// # Fields
+/// * `equity`: Account equity marked at the signal bar's close
+/// * `price`: Close of the signal bar
+/// * `history`: Bars up to and including the signal bar, oldest first
+/// * `atr`: Average true range at the signal bar; `None` during warm-up
+/// * `periods_per_year`: Number of bars per year, to annualize volatility
+/// * `signal_strength`: Conviction of the signal in `[0, 1]`
+/// * `trades`: Closed trades so far, oldest first
+/// * `other_exposure`: Gross notional of positions in other symbols
+#[derive(Debug, Clone, Copy)]
+pub struct SizingContext<'a> {
+    pub equity: f64,
+    pub price: f64,
+    pub history: &'a [MarketData],
+    pub atr: Option<f64>,
+    pub periods_per_year: f64,
+    pub signal_strength: f64,
+    pub trades: &'a [Trade],
+    pub other_exposure: f64,
+}
+
+impl PositionSizing {
+    /// Calculates the notional value of a new position, before limits.
+    pub fn notional(&self, context: &SizingContext) -> f64 {
+        let notional = match *self {
+            PositionSizing::Fixed { notional } => notional,
+            PositionSizing::FixedFractional { fraction } => context.equity * fraction,
+            PositionSizing::VolatilityTarget {
+                annual_volatility,
+                lookback,
+            } => match realized_volatility(context.history, lookback) {
+                Some(volatility) if volatility > 0.0 => {
+                    context.equity * annual_volatility
+                        / (volatility * context.periods_per_year.sqrt())
+                }
+                _ => 0.0,
+            },
+            PositionSizing::AtrRisk {
+                risk_fraction,
+                atr_multiple,
+            } => match context.atr {
+                Some(atr) if atr > 0.0 => {
+                    let quantity = context.equity * risk_fraction / (atr_multiple * atr);
+                    quantity * context.price
+                }
+                _ => 0.0,
+            },
+            PositionSizing::Kelly {
+                fraction,
+                lookback,
+                min_trades,
+                fallback_fraction,
+            } => match kelly_fraction(context.trades, lookback, min_trades) {
+                Some(kelly) => context.equity * fraction * kelly.max(0.0),
+                None => context.equity * fallback_fraction,
+            },
+            PositionSizing::SignalStrength { max_fraction } => {
+                context.equity * max_fraction * context.signal_strength.clamp(0.0, 1.0)
+            }
+        };
+
+        notional.max(0.0)
+    }
+}
+
+impl SizingLimits {
+    /// Caps `notional` by the position and leverage limits.
+    pub fn apply(&self, notional: f64, context: &SizingContext) -> f64 {
+        let mut capped = notional;
+        if let Some(fraction) = self.max_position_fraction {
+            capped = capped.min(context.equity * fraction);
+        }
+        if let Some(leverage) = self.max_leverage {
+            capped = capped.min(context.equity * leverage - context.other_exposure);
+        }
+        capped.max(0.0)
+    }
+}
+
+/// Standard deviation of the last `lookback` close-to-close log returns.
+fn realized_volatility(history: &[MarketData], lookback: usize) -> Option<f64> {
+    if lookback < 2 || history.len() < lookback + 1 {
+        return None;
+    }
+
+    let returns: Vec<f64> = history[history.len() - lookback - 1..]
+        .windows(2)
+        .map(|pair| (pair[1].price / pair[0].price).ln())
+        .collect();
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / (returns.len() - 1) as f64;
+
+    Some(variance.sqrt())
+}
+
+/// Kelly fraction `p - (1 - p) / b` from the win rate `p` and payoff ratio `b`
+/// of the last `lookback` closed trades.
+fn kelly_fraction(trades: &[Trade], lookback: usize, min_trades: usize) -> Option<f64> {
+    let pnls: Vec<f64> = trades
+        .iter()
+        .rev()
+        .take(lookback)
+        .filter_map(|trade| trade.pnl)
+        .collect();
+    if pnls.len() < min_trades.max(1) {
+        return None;
+    }
+
+    let wins: Vec<f64> = pnls.iter().copied().filter(|pnl| *pnl > 0.0).collect();
+    let losses: Vec<f64> = pnls.iter().copied().filter(|pnl| *pnl <= 0.0).collect();
+    let win_rate = wins.len() as f64 / pnls.len() as f64;
+
+    if losses.is_empty() {
+        return Some(1.0);
+    }
+    if wins.is_empty() {
+        return Some(0.0);
+    }
+
+    let average_win = wins.iter().sum::<f64>() / wins.len() as f64;
+    let average_loss = -losses.iter().sum::<f64>() / losses.len() as f64;
+    if average_loss <= 0.0 {
+        return Some(1.0);
+    }
+
+    Some(win_rate - (1.0 - win_rate) / (average_win / average_loss))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::risk::average_true_range;
+    use crate::backtesting::test_support::daily_bars;
+
+    fn history(prices: &[f64]) -> Vec<MarketData> {
+        daily_bars(prices)
+            .into_iter()
+            .map(|bar| MarketData {
+                high: bar.price + 1.0,
+                low: bar.price - 1.0,
+                ..bar
+            })
+            .collect()
+    }
+
+    fn context<'a>(history: &'a [MarketData], trades: &'a [Trade]) -> SizingContext<'a> {
+        SizingContext {
+            equity: 10000.0,
+            price: history.last().map_or(100.0, |bar| bar.price),
+            history,
+            atr: average_true_range(history, 14).last().copied().flatten(),
+            periods_per_year: 365.0,
+            signal_strength: 0.5,
+            trades,
+            other_exposure: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_equity_based_sizers() {
+        let bars = history(&[100.0; 30]);
+        let context = context(&bars, &[]);
+
+        assert_eq!(
+            PositionSizing::Fixed { notional: 500.0 }.notional(&context),
+            500.0
+        );
+        assert_eq!(
+            PositionSizing::FixedFractional { fraction: 0.1 }.notional(&context),
+            1000.0
+        );
+        assert_eq!(
+            PositionSizing::SignalStrength { max_fraction: 0.2 }.notional(&context),
+            1000.0
+        );
+
+        // Constant true range of 2: risking 1% with a 2 ATR stop buys 25 units
+        let atr_risk = PositionSizing::AtrRisk {
+            risk_fraction: 0.01,
+            atr_multiple: 2.0,
+        };
+        assert!((atr_risk.notional(&context) - 2500.0).abs() < 1e-9);
+
+        // Flat prices have no realized volatility to target
+        let volatility_target = PositionSizing::VolatilityTarget {
+            annual_volatility: 0.2,
+            lookback: 20,
+        };
+        assert_eq!(volatility_target.notional(&context), 0.0);
+        let alternating: Vec<f64> = (0..30)
+            .map(|day| if day % 2 == 0 { 100.0 } else { 101.0 })
+            .collect();
+        let bars = history(&alternating);
+        assert!(volatility_target.notional(&self::context(&bars, &[])) > 0.0);
+    }
+
+    #[test]
+    fn test_kelly_sizer() {
+        let bars = history(&[100.0; 5]);
+        let kelly = PositionSizing::Kelly {
+            fraction: 0.5,
+            lookback: 10,
+            min_trades: 4,
+            fallback_fraction: 0.05,
+        };
+
+        let few = vec![Trade::closed_for_test(20.0)];
+        assert_eq!(kelly.notional(&context(&bars, &few)), 500.0);
+
+        // 60% winners paying 2:1 gives a Kelly fraction of 0.4
+        let trades: Vec<Trade> = [20.0, 20.0, 20.0, -10.0, -10.0]
+            .iter()
+            .map(|&pnl| Trade::closed_for_test(pnl))
+            .collect();
+        assert!((kelly.notional(&context(&bars, &trades)) - 2000.0).abs() < 1e-9);
+
+        let losers: Vec<Trade> = (0..5).map(|_| Trade::closed_for_test(-10.0)).collect();
+        assert_eq!(kelly.notional(&context(&bars, &losers)), 0.0);
+    }
+
+    #[test]
+    fn test_sizing_limits() {
+        let bars = history(&[100.0; 5]);
+        let mut context = context(&bars, &[]);
+        let limits = SizingLimits {
+            max_position_fraction: Some(0.5),
+            max_leverage: Some(1.0),
+        };
+
+        assert_eq!(limits.apply(8000.0, &context), 5000.0);
+        context.other_exposure = 7000.0;
+        assert_eq!(limits.apply(8000.0, &context), 3000.0);
+        context.other_exposure = 12000.0;
+        assert_eq!(limits.apply(8000.0, &context), 0.0);
+        assert_eq!(SizingLimits::default().apply(8000.0, &context), 8000.0);
+    }
+}
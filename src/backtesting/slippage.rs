@@ -0,0 +1,123 @@
+use super::orders::OrderSide;
+use crate::data::MarketData;
+use serde::{Deserialize, Serialize};
+
+/// Model of the price concession paid when an order executes.
+///
+/// Slippage applies to orders that take liquidity (market, stop and trailing
+/// stop fills); limit orders fill at their limit price. The adjusted price is
+/// always worse than the simulated price: higher for buys, lower for sells.
+///
+/// - `None`: Fill at the simulated price
+/// - `FixedBps`: Constant cost in basis points of the price
+/// - `VolatilityProportional`: `range_fraction` of the fill bar's high-low range
+/// - `SquareRootImpact`: `coefficient * sigma * sqrt(quantity / bar volume)`,
+///   with `sigma` the bar's high-low range relative to its open
+/// - `Spread`: Half of a quoted spread of `spread_bps` basis points
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SlippageModel {
+    #[default]
+    None,
+    FixedBps {
+        bps: f64,
+    },
+    VolatilityProportional {
+        range_fraction: f64,
+    },
+    SquareRootImpact {
+        coefficient: f64,
+    },
+    Spread {
+        spread_bps: f64,
+    },
+}
+
+impl SlippageModel {
+    /// Returns the per-unit price concession for a fill.
+    ///
+    /// # Arguments
+    /// * `price` - Simulated fill price
+    /// * `quantity` - Unsigned fill quantity
+    /// * `bar` - Bar the fill happened on
+    pub fn cost_per_unit(&self, price: f64, quantity: f64, bar: &MarketData) -> f64 {
+        let cost = match *self {
+            SlippageModel::None => 0.0,
+            SlippageModel::FixedBps { bps } => price * bps / 10_000.0,
+            SlippageModel::VolatilityProportional { range_fraction } => {
+                (bar.high - bar.low) * range_fraction
+            }
+            SlippageModel::SquareRootImpact { coefficient } => {
+                let sigma = if bar.open > 0.0 {
+                    (bar.high - bar.low) / bar.open
+                } else {
+                    0.0
+                };
+                let participation = if bar.volume > 0.0 {
+                    quantity / bar.volume
+                } else {
+                    1.0
+                };
+                price * coefficient * sigma * participation.sqrt()
+            }
+            SlippageModel::Spread { spread_bps } => price * spread_bps / 20_000.0,
+        };
+
+        cost.max(0.0)
+    }
+
+    /// Returns the fill price after slippage for an order on `side`.
+    pub fn apply(&self, side: OrderSide, price: f64, quantity: f64, bar: &MarketData) -> f64 {
+        price + side.sign() * self.cost_per_unit(price, quantity, bar)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::test_support::daily_bars;
+
+    fn bar(volume: f64) -> MarketData {
+        let [bar] = daily_bars(&[100.0]).try_into().unwrap();
+        MarketData {
+            volume,
+            high: 104.0,
+            low: 96.0,
+            ..bar
+        }
+    }
+
+    #[test]
+    fn test_slippage_models() {
+        let bar = bar(10_000.0);
+
+        assert_eq!(
+            SlippageModel::None.apply(OrderSide::Buy, 100.0, 1.0, &bar),
+            100.0
+        );
+        assert!(
+            (SlippageModel::FixedBps { bps: 10.0 }.apply(OrderSide::Buy, 100.0, 1.0, &bar) - 100.1)
+                .abs()
+                < 1e-9
+        );
+        assert!(
+            (SlippageModel::Spread { spread_bps: 20.0 }.apply(OrderSide::Sell, 100.0, 1.0, &bar)
+                - 99.9)
+                .abs()
+                < 1e-9
+        );
+        assert_eq!(
+            SlippageModel::VolatilityProportional {
+                range_fraction: 0.1
+            }
+            .cost_per_unit(100.0, 1.0, &bar),
+            0.8
+        );
+
+        // Impact grows with the square root of participation
+        let impact = SlippageModel::SquareRootImpact { coefficient: 1.0 };
+        let small = impact.cost_per_unit(100.0, 100.0, &bar);
+        let large = impact.cost_per_unit(100.0, 400.0, &bar);
+        assert!((small - 0.8).abs() < 1e-9);
+        assert!((large - 2.0 * small).abs() < 1e-9);
+    }
+}
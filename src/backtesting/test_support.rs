@@ -0,0 +1,65 @@
+use super::backtester::{EquityPoint, PositionType, Trade};
+use crate::data::MarketData;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+/// Timestamp of the first point of test equity curves.
+pub(crate) fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Builds a daily equity curve starting at `start()`.
+pub(crate) fn curve(equity: &[f64]) -> Vec<EquityPoint> {
+    equity
+        .iter()
+        .enumerate()
+        .map(|(day, &equity)| EquityPoint {
+            timestamp: start() + Duration::days(day as i64),
+            equity,
+            drawdown: 0.0,
+        })
+        .collect()
+}
+
+/// Builds daily SOL bars starting at `start()` with every price field set to
+/// the close; override other fields with struct update syntax.
+pub(crate) fn daily_bars(prices: &[f64]) -> Vec<MarketData> {
+    prices
+        .iter()
+        .enumerate()
+        .map(|(day, &price)| MarketData {
+            timestamp: start() + Duration::days(day as i64),
+            symbol: "SOL".to_string(),
+            price,
+            volume: 1000.0,
+            high: price,
+            low: price,
+            open: price,
+        })
+        .collect()
+}
+
+impl Trade {
+    /// A closed one-unit long trade from 100 with the given PnL; override
+    /// other fields with struct update syntax.
+    pub(crate) fn closed_for_test(pnl: f64) -> Self {
+        Trade {
+            entry_time: start(),
+            exit_time: Some(start()),
+            entry_price: 100.0,
+            exit_price: Some(100.0 + pnl),
+            position_type: PositionType::Long,
+            quantity: 1.0,
+            pnl: Some(pnl),
+            strategy_name: "Rsi".to_string(),
+            entry_order_id: None,
+            exit_order_id: None,
+            exit_reason: None,
+            commission: 0.0,
+            slippage: 0.0,
+            max_adverse_excursion: 0.0,
+            max_favorable_excursion: 0.0,
+            bars_held: 1,
+            signal: None,
+        }
+    }
+}
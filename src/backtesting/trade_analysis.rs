@@ -0,0 +1,224 @@
+use super::backtester::Trade;
+use super::risk::ExitReason;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A bin of a histogram, counting values in `[lower, upper)`.
+///
+/// The last bin of a histogram also counts values equal to its upper edge.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+/// Equal-width histogram of a set of values.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Histogram {
+    pub bins: Vec<HistogramBin>,
+}
+
+impl Histogram {
+    /// Builds a histogram spanning the range of `values`.
+    ///
+    /// # Arguments
+    /// * `values` - Values to count
+    /// * `bins` - Number of equal-width bins; identical values share a single bin
+    pub fn new(values: &[f64], bins: usize) -> Self {
+        let values: Vec<f64> = values
+            .iter()
+            .copied()
+            .filter(|value| value.is_finite())
+            .collect();
+        if values.is_empty() || bins == 0 {
+            return Self::default();
+        }
+
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let bins = if max > min { bins } else { 1 };
+        let width = (max - min) / bins as f64;
+
+        let mut histogram: Vec<HistogramBin> = (0..bins)
+            .map(|bin| HistogramBin {
+                lower: min + width * bin as f64,
+                upper: if bin + 1 == bins {
+                    max
+                } else {
+                    min + width * (bin + 1) as f64
+                },
+                count: 0,
+            })
+            .collect();
+        for value in values {
+            let bin = if width > 0.0 {
+                (((value - min) / width) as usize).min(bins - 1)
+            } else {
+                0
+            };
+            histogram[bin].count += 1;
+        }
+
+        Self { bins: histogram }
+    }
+}
+
+/// Excursions and outcome of one closed trade, for MAE/MFE scatter plots.
+///
+/// # Fields
+/// * `entry_time`: Entry timestamp of the trade
+/// * `mae`: Maximum adverse excursion, as a fraction of the entry price
+/// * `mfe`: Maximum favorable excursion, as a fraction of the entry price
+/// * `net_return`: PnL as a fraction of the entry notional
+/// * `pnl`: Net PnL of the trade
+/// * `bars_held`: Bars between entry and exit
+/// * `exit_reason`: What closed the trade
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcursionPoint {
+    pub entry_time: DateTime<Utc>,
+    pub mae: f64,
+    pub mfe: f64,
+    pub net_return: f64,
+    pub pnl: f64,
+    pub bars_held: usize,
+    pub exit_reason: Option<ExitReason>,
+}
+
+/// Distribution of trade excursions, holding periods and outcomes.
+///
+/// Plotting `excursions` as MAE against `net_return` shows how far winning
+/// trades moved against the position before recovering, and hence where a
+/// stop would cut losers without stopping out winners.
+///
+/// # Fields
+/// * `mae_histogram`: Maximum adverse excursions of closed trades
+/// * `mfe_histogram`: Maximum favorable excursions of closed trades
+/// * `bars_held_histogram`: Holding periods of closed trades, in bars
+/// * `return_histogram`: Net returns of closed trades
+/// * `exit_reasons`: Number of closed trades per exit reason
+/// * `excursions`: One point per closed trade
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TradeAnalysis {
+    pub mae_histogram: Histogram,
+    pub mfe_histogram: Histogram,
+    pub bars_held_histogram: Histogram,
+    pub return_histogram: Histogram,
+    pub exit_reasons: Vec<(ExitReason, usize)>,
+    pub excursions: Vec<ExcursionPoint>,
+}
+
+impl TradeAnalysis {
+    /// Analyzes the closed trades of a backtest.
+    ///
+    /// # Arguments
+    /// * `trades` - Trades of the backtest; open trades are ignored
+    /// * `bins` - Number of bins of each histogram
+    pub fn calculate(trades: &[Trade], bins: usize) -> Self {
+        let excursions: Vec<ExcursionPoint> = trades
+            .iter()
+            .filter_map(|trade| {
+                let pnl = trade.pnl?;
+                Some(ExcursionPoint {
+                    entry_time: trade.entry_time,
+                    mae: trade.max_adverse_excursion,
+                    mfe: trade.max_favorable_excursion,
+                    net_return: trade.net_return().unwrap_or(0.0),
+                    pnl,
+                    bars_held: trade.bars_held,
+                    exit_reason: trade.exit_reason,
+                })
+            })
+            .collect();
+
+        let mut exit_reasons: Vec<(ExitReason, usize)> = Vec::new();
+        for reason in excursions.iter().filter_map(|point| point.exit_reason) {
+            match exit_reasons.iter_mut().find(|(seen, _)| *seen == reason) {
+                Some((_, count)) => *count += 1,
+                None => exit_reasons.push((reason, 1)),
+            }
+        }
+
+        let values = |value: fn(&ExcursionPoint) -> f64| -> Vec<f64> {
+            excursions.iter().map(value).collect()
+        };
+        Self {
+            mae_histogram: Histogram::new(&values(|point| point.mae), bins),
+            mfe_histogram: Histogram::new(&values(|point| point.mfe), bins),
+            bars_held_histogram: Histogram::new(&values(|point| point.bars_held as f64), bins),
+            return_histogram: Histogram::new(&values(|point| point.net_return), bins),
+            exit_reasons,
+            excursions,
+        }
+    }
+
+    /// Returns the largest adverse excursion of a winning trade.
+    ///
+    /// A stop closer to the entry than this would have closed at least one
+    /// winner at a loss.
+    pub fn max_winner_mae(&self) -> f64 {
+        self.excursions
+            .iter()
+            .filter(|point| point.pnl > 0.0)
+            .map(|point| point.mae)
+            .fold(0.0, f64::max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(pnl: f64, mae: f64, mfe: f64, bars_held: usize, reason: ExitReason) -> Trade {
+        Trade {
+            exit_reason: Some(reason),
+            max_adverse_excursion: mae,
+            max_favorable_excursion: mfe,
+            bars_held,
+            ..Trade::closed_for_test(pnl)
+        }
+    }
+
+    #[test]
+    fn test_histogram_bins() {
+        let histogram = Histogram::new(&[0.0, 0.1, 0.25, 0.5, 1.0], 4);
+        let counts: Vec<usize> = histogram.bins.iter().map(|bin| bin.count).collect();
+        assert_eq!(counts, vec![2, 1, 1, 1]);
+        assert_eq!(histogram.bins[3].upper, 1.0);
+
+        let constant = Histogram::new(&[2.0, 2.0], 10);
+        assert_eq!(constant.bins.len(), 1);
+        assert_eq!(constant.bins[0].count, 2);
+        assert!(Histogram::new(&[], 10).bins.is_empty());
+    }
+
+    #[test]
+    fn test_trade_analysis() {
+        let trades = vec![
+            trade(5.0, 0.02, 0.06, 4, ExitReason::Signal),
+            trade(8.0, 0.03, 0.09, 6, ExitReason::TakeProfit),
+            trade(-4.0, 0.05, 0.01, 2, ExitReason::StopLoss),
+            trade(-2.0, 0.04, 0.02, 3, ExitReason::Signal),
+        ];
+
+        let analysis = TradeAnalysis::calculate(&trades, 5);
+        assert_eq!(analysis.excursions.len(), 4);
+        assert!((analysis.excursions[0].net_return - 0.05).abs() < 1e-12);
+        assert_eq!(
+            analysis.exit_reasons,
+            vec![
+                (ExitReason::Signal, 2),
+                (ExitReason::TakeProfit, 1),
+                (ExitReason::StopLoss, 1)
+            ]
+        );
+        let total: usize = analysis
+            .mae_histogram
+            .bins
+            .iter()
+            .map(|bin| bin.count)
+            .sum();
+        assert_eq!(total, 4);
+        assert_eq!(analysis.max_winner_mae(), 0.03);
+    }
+}
@@ -0,0 +1,354 @@
+use super::backtester::{EquityPoint, Trade};
+use super::drawdown::drawdown_from_peak;
+use super::metrics::{mean, period_returns, std_dev, PerformanceMetrics};
+use super::optimizer::{Optimizer, OptimizerError};
+use crate::data::ProcessedMarketData;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// How in-sample windows move from fold to fold.
+///
+/// - `Anchored`: Every in-sample window starts at the first bar and grows by
+///   one out-of-sample window per fold
+/// - `Rolling`: In-sample windows keep their length and slide forward by one
+///   out-of-sample window per fold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindowMode {
+    Anchored,
+    #[default]
+    Rolling,
+}
+
+/// Fold layout of a walk-forward analysis.
+///
+/// # Fields
+/// * `mode`: Anchored or rolling in-sample windows
+/// * `in_sample_bars`: Length of the (first) in-sample window
+/// * `out_of_sample_bars`: Length of each out-of-sample window
+/// * `warmup_bars`: In-sample bars replayed before each out-of-sample window,
+///   without trading, to warm up indicators
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WalkForwardConfig {
+    pub mode: WindowMode,
+    pub in_sample_bars: usize,
+    pub out_of_sample_bars: usize,
+    pub warmup_bars: usize,
+}
+
+impl Default for WalkForwardConfig {
+    fn default() -> Self {
+        Self {
+            mode: WindowMode::Rolling,
+            in_sample_bars: 120,
+            out_of_sample_bars: 30,
+            warmup_bars: 30,
+        }
+    }
+}
+
+impl WalkForwardConfig {
+    /// Splits `bars` bars into `(in-sample, out-of-sample)` index ranges.
+    ///
+    /// The last out-of-sample window may be shorter than `out_of_sample_bars`.
+    pub fn folds(&self, bars: usize) -> Vec<(std::ops::Range<usize>, std::ops::Range<usize>)> {
+        let mut folds = Vec::new();
+        if self.in_sample_bars == 0 || self.out_of_sample_bars == 0 {
+            return folds;
+        }
+
+        let mut fold = 0;
+        loop {
+            let in_sample_end = self.in_sample_bars + fold * self.out_of_sample_bars;
+            if in_sample_end >= bars {
+                return folds;
+            }
+            let in_sample_start = match self.mode {
+                WindowMode::Anchored => 0,
+                WindowMode::Rolling => in_sample_end - self.in_sample_bars,
+            };
+            let out_of_sample_end = (in_sample_end + self.out_of_sample_bars).min(bars);
+            folds.push((
+                in_sample_start..in_sample_end,
+                in_sample_end..out_of_sample_end,
+            ));
+            fold += 1;
+        }
+    }
+}
+
+/// One in-sample optimization and its out-of-sample test.
+///
+/// Returns are annualized mean bar returns of the respective window.
+///
+/// # Fields
+/// * `in_sample_start`, `in_sample_end`: First and last bar of the in-sample window
+/// * `out_of_sample_start`, `out_of_sample_end`: First and last bar of the out-of-sample window
+/// * `parameters`: Best in-sample parameters, in the order of the searched ranges
+/// * `in_sample_objective`: Objective of the best in-sample trial
+/// * `in_sample_return`: Annualized return of the best in-sample trial
+/// * `out_of_sample_return`: Annualized return with the same parameters out of sample
+/// * `out_of_sample_trades`: Trades opened out of sample
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkForwardFold {
+    pub in_sample_start: DateTime<Utc>,
+    pub in_sample_end: DateTime<Utc>,
+    pub out_of_sample_start: DateTime<Utc>,
+    pub out_of_sample_end: DateTime<Utc>,
+    pub parameters: Vec<f64>,
+    pub in_sample_objective: f64,
+    pub in_sample_return: f64,
+    pub out_of_sample_return: f64,
+    pub out_of_sample_trades: usize,
+}
+
+/// Spread of the best value of one parameter across folds.
+///
+/// # Fields
+/// * `name`: Parameter name
+/// * `mean`: Mean of the best values
+/// * `std_dev`: Standard deviation of the best values
+/// * `min`, `max`: Smallest and largest best value
+/// * `coefficient_of_variation`: `std_dev` over the absolute mean; 0 when the mean is 0
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterStability {
+    pub name: String,
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub coefficient_of_variation: f64,
+}
+
+/// Results of a walk-forward analysis.
+///
+/// # Fields
+/// * `parameters`: Names of the searched parameters
+/// * `folds`: One entry per fold, in time order
+/// * `equity_curve`: Out-of-sample equity of all folds, chained from the initial capital
+/// * `metrics`: Performance of the stitched out-of-sample curve and trades;
+///   exposure and turnover are not tracked across folds and are 0
+/// * `efficiency`: Walk-forward efficiency, mean annualized out-of-sample
+///   return over mean annualized in-sample return; 0 when the in-sample return
+///   is not positive
+/// * `parameter_stability`: Spread of each parameter's best value across folds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalkForwardReport {
+    pub parameters: Vec<String>,
+    pub folds: Vec<WalkForwardFold>,
+    pub equity_curve: Vec<EquityPoint>,
+    pub metrics: PerformanceMetrics,
+    pub efficiency: f64,
+    pub parameter_stability: Vec<ParameterStability>,
+}
+
+impl Optimizer {
+    /// Runs a walk-forward analysis.
+    ///
+    /// Every fold re-runs the search on its in-sample window, then backtests
+    /// the best parameters on the following out-of-sample window.
+    ///
+    /// # Errors
+    /// Returns an error if the data is too short for a single fold, or the
+    /// search fails on a fold
+    pub fn walk_forward(
+        &self,
+        data: &[ProcessedMarketData],
+        config: &WalkForwardConfig,
+    ) -> Result<WalkForwardReport, OptimizerError> {
+        let folds = config.folds(data.len());
+        if folds.is_empty() {
+            return Err(OptimizerError::InsufficientData {
+                bars: data.len(),
+                required: config.in_sample_bars + 1,
+            });
+        }
+        let metrics_config = self.backtester().metrics_config();
+        let periods = metrics_config.periods_per_year;
+        let annualized = |returns: &[f64]| mean(returns) * periods;
+
+        let mut report = WalkForwardReport {
+            parameters: Vec::new(),
+            folds: Vec::new(),
+            equity_curve: Vec::new(),
+            metrics: PerformanceMetrics::default(),
+            efficiency: 0.0,
+            parameter_stability: Vec::new(),
+        };
+        let mut trades: Vec<Trade> = Vec::new();
+
+        for (in_sample, out_of_sample) in folds {
+            let optimization = self.run(&data[in_sample.clone()])?;
+            let best = optimization.best().ok_or(OptimizerError::EmptySpace)?;
+            let in_sample_result = self.backtest(&best.parameters, &data[in_sample.clone()])?;
+            report.parameters = optimization.parameters.clone();
+
+            // Replay the end of the in-sample window without trading to warm up indicators
+            let warmup = config.warmup_bars.min(in_sample.len());
+            let mut tester = self.clone();
+            tester.backtester_mut().set_warmup_bars(warmup);
+            let result = tester.backtest(
+                &best.parameters,
+                &data[out_of_sample.start - warmup..out_of_sample.end],
+            )?;
+            let out_of_sample_curve = &result.equity_curve[warmup..];
+            let returns = period_returns(out_of_sample_curve);
+
+            if report.equity_curve.is_empty() {
+                report.equity_curve.push(out_of_sample_curve[0].clone());
+            }
+            for (point, period_return) in out_of_sample_curve[1..].iter().zip(&returns) {
+                let equity = report.equity_curve.last().map_or(0.0, |last| last.equity)
+                    * (1.0 + period_return);
+                report.equity_curve.push(EquityPoint {
+                    timestamp: point.timestamp,
+                    equity,
+                    drawdown: 0.0,
+                });
+            }
+            trades.extend(result.trades.iter().cloned());
+
+            report.folds.push(WalkForwardFold {
+                in_sample_start: data[in_sample.start].raw_data.timestamp,
+                in_sample_end: data[in_sample.end - 1].raw_data.timestamp,
+                out_of_sample_start: data[out_of_sample.start].raw_data.timestamp,
+                out_of_sample_end: data[out_of_sample.end - 1].raw_data.timestamp,
+                parameters: best.parameters.clone(),
+                in_sample_objective: best.objective,
+                in_sample_return: annualized(&period_returns(&in_sample_result.equity_curve)),
+                out_of_sample_return: annualized(&returns),
+                out_of_sample_trades: result.trades.len(),
+            });
+        }
+
+        let mut peak = 0.0_f64;
+        for point in &mut report.equity_curve {
+            peak = peak.max(point.equity);
+            point.drawdown = drawdown_from_peak(peak, point.equity);
+        }
+        report.metrics =
+            PerformanceMetrics::calculate(&report.equity_curve, &trades, 0, 0.0, &metrics_config);
+
+        let fold_count = report.folds.len() as f64;
+        let mean_in_sample = report
+            .folds
+            .iter()
+            .map(|fold| fold.in_sample_return)
+            .sum::<f64>()
+            / fold_count;
+        let mean_out_of_sample = report
+            .folds
+            .iter()
+            .map(|fold| fold.out_of_sample_return)
+            .sum::<f64>()
+            / fold_count;
+        if mean_in_sample > 0.0 {
+            report.efficiency = mean_out_of_sample / mean_in_sample;
+        }
+
+        report.parameter_stability = report
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(index, name)| {
+                let values: Vec<f64> = report
+                    .folds
+                    .iter()
+                    .map(|fold| fold.parameters[index])
+                    .collect();
+                let (mean, std_dev) = (mean(&values), std_dev(&values));
+                ParameterStability {
+                    name: name.clone(),
+                    mean,
+                    std_dev,
+                    min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                    max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                    coefficient_of_variation: if mean != 0.0 {
+                        std_dev / mean.abs()
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::{Backtester, Objective, ParameterRange, StrategyMode};
+    use crate::data::{DataProcessor, Scenario};
+
+    #[test]
+    fn test_fold_layout() {
+        let rolling = WalkForwardConfig {
+            in_sample_bars: 100,
+            out_of_sample_bars: 40,
+            ..Default::default()
+        };
+        assert_eq!(
+            rolling.folds(220),
+            vec![(0..100, 100..140), (40..140, 140..180), (80..180, 180..220)]
+        );
+
+        let anchored = WalkForwardConfig {
+            mode: WindowMode::Anchored,
+            ..rolling
+        };
+        assert_eq!(
+            anchored.folds(200),
+            vec![(0..100, 100..140), (0..140, 140..180), (0..180, 180..200)]
+        );
+        assert!(rolling.folds(100).is_empty());
+    }
+
+    #[test]
+    fn test_walk_forward() {
+        let data = DataProcessor::new(500)
+            .process_batch(Scenario::Sideways.generate(300, 42))
+            .unwrap();
+        let mut backtester = Backtester::new(10000.0, 1000.0, 0.001);
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let mut optimizer = Optimizer::new(backtester, Objective::SharpeRatio);
+        optimizer.add_parameter(ParameterRange::new("rsi_oversold", 30.0, 45.0, 4));
+        optimizer.add_parameter(ParameterRange::integer("rsi_period", 10, 14));
+
+        let config = WalkForwardConfig {
+            in_sample_bars: 150,
+            out_of_sample_bars: 50,
+            warmup_bars: 30,
+            ..Default::default()
+        };
+        let report = optimizer.walk_forward(&data, &config).unwrap();
+        assert_eq!(report.folds.len(), 3);
+
+        // One stitched point per out-of-sample bar, plus the starting capital
+        assert_eq!(report.equity_curve.len(), 151);
+        assert_eq!(report.equity_curve[0].equity, 10000.0);
+        assert_eq!(
+            report.equity_curve[1].timestamp,
+            data[150].raw_data.timestamp
+        );
+        assert!(report.folds.iter().all(|fold| fold.parameters.len() == 2));
+
+        let period = &report.parameter_stability[1];
+        assert_eq!(period.name, "rsi_period");
+        assert!(period.min >= 10.0 && period.max <= 14.0);
+        assert!(period.std_dev >= 0.0);
+        assert!(report.metrics.max_drawdown >= 0.0);
+
+        let too_short = WalkForwardConfig {
+            in_sample_bars: 300,
+            ..config
+        };
+        assert!(matches!(
+            optimizer.walk_forward(&data, &too_short),
+            Err(OptimizerError::InsufficientData {
+                bars: 300,
+                required: 301
+            })
+        ));
+    }
+}
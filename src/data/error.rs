@@ -0,0 +1,119 @@
+use thiserror::Error;
+
+/// Errors produced by the data layer.
+///
+/// Each variant corresponds to a failure callers may want to handle differently:
+/// a missing API key is a configuration problem, rate limiting is usually
+/// transient, while provider errors and schema mismatches point at the request
+/// or at a change in the provider's API.
+#[derive(Debug, Error)]
+pub enum DataError {
+    /// A required credential is not configured
+    #[error("missing credentials: {variable} is not set")]
+    MissingCredentials { variable: String },
+
+    /// The provider throttled the request and retries were exhausted
+    #[error("{provider} rate limit reached: {message}")]
+    RateLimited { provider: String, message: String },
+
+    /// The provider rejected the request with an explicit error message
+    #[error("{provider} returned an error: {message}")]
+    Provider { provider: String, message: String },
+
+    /// The response does not have the structure we expect
+    #[error("unexpected {provider} response: {detail}")]
+    Schema { provider: String, detail: String },
+
+    /// A value in an otherwise well-formed response could not be parsed
+    #[error("failed to parse {field} for {timestamp}: {reason}")]
+    Parse {
+        field: String,
+        timestamp: String,
+        reason: String,
+    },
+
+    /// The provider returned no bars for the request
+    #[error("no market data available for {symbol}")]
+    NoData { symbol: String },
+
+    /// The request could not be completed at the transport or HTTP level;
+    /// `status` is the HTTP status code, `None` if no response arrived
+    #[error("{provider} request failed: {message}")]
+    Http {
+        provider: String,
+        message: String,
+        status: Option<u16>,
+    },
+
+    /// A background fetch task failed before returning a result
+    #[error("fetch task failed: {0}")]
+    Task(String),
+
+    /// A failure while fetching one symbol of a multi-symbol request
+    #[error("failed to fetch {symbol}: {source}")]
+    Symbol {
+        symbol: String,
+        #[source]
+        source: Box<DataError>,
+    },
+}
+
+/// Result type returned by the data layer.
+pub type DataResult<T> = std::result::Result<T, DataError>;
+
+impl DataError {
+    /// Returns the underlying error, looking through per-symbol wrappers.
+    pub fn root_cause(&self) -> &DataError {
+        match self {
+            DataError::Symbol { source, .. } => source.root_cause(),
+            other => other,
+        }
+    }
+
+    /// Returns `true` if the error is transient and the request may succeed later.
+    ///
+    /// HTTP failures are transient if no response arrived or the server
+    /// answered with a 5xx status; other statuses reject the request itself.
+    /// Failed fetch tasks panicked or were cancelled, which retrying won't fix.
+    pub fn is_retryable(&self) -> bool {
+        match self.root_cause() {
+            DataError::RateLimited { .. } => true,
+            DataError::Http { status, .. } => status.is_none_or(|status| status >= 500),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_cause_unwraps_symbol_errors() {
+        let error = DataError::Symbol {
+            symbol: "SOL".to_string(),
+            source: Box::new(DataError::RateLimited {
+                provider: "Alpha Vantage".to_string(),
+                message: "Thank you for using Alpha Vantage!".to_string(),
+            }),
+        };
+
+        assert!(matches!(error.root_cause(), DataError::RateLimited { .. }));
+        assert!(error.is_retryable());
+        assert!(error.to_string().starts_with("failed to fetch SOL"));
+        assert!(!DataError::NoData {
+            symbol: "SOL".to_string()
+        }
+        .is_retryable());
+
+        let http = |status| DataError::Http {
+            provider: "Alpha Vantage".to_string(),
+            message: "request failed".to_string(),
+            status,
+        };
+        assert!(http(None).is_retryable());
+        assert!(http(Some(503)).is_retryable());
+        assert!(!http(Some(404)).is_retryable());
+        assert!(!DataError::Task("task panicked".to_string()).is_retryable());
+    }
+}
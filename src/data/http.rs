@@ -0,0 +1,485 @@
+use super::error::{DataError, DataResult};
+use rand::Rng;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Inspects a successful JSON response body and returns the provider's rate-limit
+/// message if the body is a throttling notice rather than data.
+pub type RateLimitDetector = fn(&serde_json::Value) -> Option<String>;
+
+/// Token-bucket rate limiter shared by every request sent to one provider.
+///
+/// The bucket holds up to `capacity` tokens and refills continuously at
+/// `refill_per_second`; each request consumes one token and waits when the
+/// bucket is empty.
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    refill_per_second: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a new full token bucket.
+    ///
+    /// # Arguments
+    /// * `requests_per_minute`: Sustained request rate allowed by the provider
+    /// * `burst`: Maximum number of requests that may be sent back to back
+    pub fn new(requests_per_minute: f64, burst: u32) -> Self {
+        let capacity = f64::from(burst.max(1));
+        Self {
+            capacity,
+            refill_per_second: requests_per_minute.max(f64::MIN_POSITIVE) / 60.0,
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Takes one token, waiting for the bucket to refill if necessary.
+    ///
+    /// The token is reserved before sleeping, so concurrent callers queue up
+    /// behind each other instead of all waking at the same instant.
+    ///
+    /// # Returns
+    /// How long the caller had to wait for its token
+    pub async fn acquire(&self) -> Duration {
+        let wait = {
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.refill_per_second).min(self.capacity);
+            state.last_refill = now;
+            state.tokens -= 1.0;
+
+            if state.tokens >= 0.0 {
+                Duration::ZERO
+            } else {
+                Duration::from_secs_f64(-state.tokens / self.refill_per_second)
+            }
+        };
+
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+
+        wait
+    }
+}
+
+/// Exponential backoff with jitter applied to transient failures.
+///
+/// # Fields
+/// * `max_retries`: Number of retries after the first attempt
+/// * `base_delay`: Delay before the first retry
+/// * `max_delay`: Upper bound on any single delay
+/// * `jitter`: Fraction (0.0 to 1.0) of each delay that is randomized
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    /// Provides a retry policy suited to free-tier market data APIs.
+    ///
+    /// # Default Values
+    /// * `max_retries`: 3
+    /// * `base_delay`: 2 seconds
+    /// * `max_delay`: 60 seconds
+    /// * `jitter`: 0.5
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(60),
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Computes the delay before retry number `attempt` (starting at 0).
+    ///
+    /// The un-jittered delay doubles with every attempt up to `max_delay`; the
+    /// jittered part is drawn uniformly so that throttled clients spread out.
+    pub fn delay_for(&self, attempt: u32, rng: &mut impl Rng) -> Duration {
+        let exponential = self
+            .base_delay
+            .checked_mul(2u32.saturating_pow(attempt))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = (1.0 - jitter) + jitter * rng.gen::<f64>();
+
+        exponential.mul_f64(factor)
+    }
+}
+
+/// Request, retry and throttling counters of an `HttpClient`.
+#[derive(Debug, Default)]
+pub struct HttpMetrics {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    throttled: AtomicU64,
+    timeouts: AtomicU64,
+    server_errors: AtomicU64,
+    failures: AtomicU64,
+    limiter_waits: AtomicU64,
+    limiter_wait_ms: AtomicU64,
+}
+
+/// Point-in-time copy of `HttpMetrics`.
+///
+/// # Fields
+/// * `requests`: Requests sent, including retries
+/// * `retries`: Retries performed after a transient failure
+/// * `throttled`: Responses that were rate limited (HTTP 429 or a rate-limit body)
+/// * `timeouts`: Requests that hit the request timeout
+/// * `server_errors`: Responses with a 5xx status
+/// * `failures`: Calls that ultimately returned an error
+/// * `limiter_waits`: Requests delayed by the local token bucket
+/// * `limiter_wait_ms`: Total time spent waiting on the token bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HttpMetricsSnapshot {
+    pub requests: u64,
+    pub retries: u64,
+    pub throttled: u64,
+    pub timeouts: u64,
+    pub server_errors: u64,
+    pub failures: u64,
+    pub limiter_waits: u64,
+    pub limiter_wait_ms: u64,
+}
+
+impl HttpMetrics {
+    /// Returns the current value of every counter.
+    pub fn snapshot(&self) -> HttpMetricsSnapshot {
+        HttpMetricsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            throttled: self.throttled.load(Ordering::Relaxed),
+            timeouts: self.timeouts.load(Ordering::Relaxed),
+            server_errors: self.server_errors.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+            limiter_waits: self.limiter_waits.load(Ordering::Relaxed),
+            limiter_wait_ms: self.limiter_wait_ms.load(Ordering::Relaxed),
+        }
+    }
+
+    fn increment(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn record_limiter_wait(&self, wait: Duration) {
+        if !wait.is_zero() {
+            Self::increment(&self.limiter_waits);
+            self.limiter_wait_ms
+                .fetch_add(wait.as_millis() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Per-provider settings of an `HttpClient`.
+///
+/// # Fields
+/// * `name`: Provider name used in logs and errors
+/// * `requests_per_minute`: Sustained request rate enforced by the token bucket
+/// * `burst`: Token bucket capacity
+/// * `timeout`: Timeout applied to every request
+/// * `retry`: Backoff policy for transient failures
+/// * `rate_limit_detector`: Optional detector for rate-limit messages in response bodies
+#[derive(Debug, Clone)]
+pub struct ProviderConfig {
+    pub name: String,
+    pub requests_per_minute: f64,
+    pub burst: u32,
+    pub timeout: Duration,
+    pub retry: RetryPolicy,
+    pub rate_limit_detector: Option<RateLimitDetector>,
+}
+
+/// Outcome of a single request attempt.
+enum Attempt {
+    Done(serde_json::Value),
+    Retry {
+        reason: String,
+        throttled: bool,
+        retry_after: Option<Duration>,
+        status: Option<u16>,
+    },
+}
+
+/// Rate-limited, retrying JSON-over-HTTP client for one data provider.
+///
+/// Retries on timeouts, connection errors, HTTP 429, 5xx responses and
+/// rate-limit bodies, backing off exponentially with jitter. Clones share the
+/// same connection pool, token bucket and metrics.
+#[derive(Debug, Clone)]
+pub struct HttpClient {
+    client: reqwest::Client,
+    config: ProviderConfig,
+    limiter: Arc<RateLimiter>,
+    metrics: Arc<HttpMetrics>,
+}
+
+impl HttpClient {
+    /// Creates a new client for the given provider.
+    ///
+    /// # Errors
+    /// Returns an error if the underlying HTTP client cannot be built
+    pub fn new(config: ProviderConfig) -> DataResult<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout)
+            .build()
+            .map_err(|e| DataError::Http {
+                provider: config.name.clone(),
+                message: e.to_string(),
+                status: None,
+            })?;
+
+        Ok(Self {
+            client,
+            limiter: Arc::new(RateLimiter::new(config.requests_per_minute, config.burst)),
+            metrics: Arc::new(HttpMetrics::default()),
+            config,
+        })
+    }
+
+    /// Returns the provider settings of this client.
+    pub fn config(&self) -> &ProviderConfig {
+        &self.config
+    }
+
+    /// Returns the current request, retry and throttling counters.
+    pub fn metrics(&self) -> HttpMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// Sends a GET request and parses the response body as JSON.
+    ///
+    /// # Errors
+    /// Returns `DataError::RateLimited` when retries are exhausted on HTTP 429
+    /// or a rate-limit body, with the provider's message for the latter,
+    /// `DataError::Http` for other transport and HTTP failures, and
+    /// `DataError::Schema` if the body is not valid JSON
+    pub async fn get_json(&self, url: &str) -> DataResult<serde_json::Value> {
+        let retry = self.config.retry;
+        let mut attempt = 0;
+
+        loop {
+            let wait = self.limiter.acquire().await;
+            self.metrics.record_limiter_wait(wait);
+            HttpMetrics::increment(&self.metrics.requests);
+
+            let outcome = match self.attempt(url).await {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    HttpMetrics::increment(&self.metrics.failures);
+                    return Err(e);
+                }
+            };
+
+            match outcome {
+                Attempt::Done(body) => return Ok(body),
+                Attempt::Retry {
+                    reason,
+                    throttled,
+                    retry_after,
+                    status,
+                } => {
+                    if attempt >= retry.max_retries {
+                        HttpMetrics::increment(&self.metrics.failures);
+                        let message = format!("{} (after {} attempts)", reason, attempt + 1);
+                        let provider = self.config.name.clone();
+                        return Err(if throttled {
+                            DataError::RateLimited { provider, message }
+                        } else {
+                            DataError::Http {
+                                provider,
+                                message,
+                                status,
+                            }
+                        });
+                    }
+
+                    let backoff = retry.delay_for(attempt, &mut rand::thread_rng());
+                    let delay = retry_after.map_or(backoff, |after| after.max(backoff));
+                    tracing::warn!(
+                        provider = %self.config.name,
+                        attempt = attempt + 1,
+                        delay_ms = delay.as_millis() as u64,
+                        %reason,
+                        "retrying request"
+                    );
+
+                    HttpMetrics::increment(&self.metrics.retries);
+                    attempt += 1;
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+    }
+
+    async fn attempt(&self, url: &str) -> DataResult<Attempt> {
+        let http_error = |e: reqwest::Error| DataError::Http {
+            provider: self.config.name.clone(),
+            status: e.status().map(|status| status.as_u16()),
+            message: describe(e),
+        };
+
+        let response = match self.client.get(url).send().await {
+            Ok(response) => response,
+            Err(e) if e.is_timeout() => {
+                HttpMetrics::increment(&self.metrics.timeouts);
+                return Ok(Attempt::Retry {
+                    reason: "request timed out".to_string(),
+                    throttled: false,
+                    retry_after: None,
+                    status: None,
+                });
+            }
+            Err(e) if e.is_connect() => {
+                return Ok(Attempt::Retry {
+                    reason: describe(e),
+                    throttled: false,
+                    retry_after: None,
+                    status: None,
+                });
+            }
+            Err(e) => return Err(http_error(e)),
+        };
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            HttpMetrics::increment(&self.metrics.throttled);
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            return Ok(Attempt::Retry {
+                reason: format!("HTTP {}", status),
+                throttled: true,
+                retry_after,
+                status: Some(status.as_u16()),
+            });
+        }
+
+        if status.is_server_error() {
+            HttpMetrics::increment(&self.metrics.server_errors);
+            return Ok(Attempt::Retry {
+                reason: format!("HTTP {}", status),
+                throttled: false,
+                retry_after: None,
+                status: Some(status.as_u16()),
+            });
+        }
+
+        let body = response
+            .error_for_status()
+            .map_err(http_error)?
+            .json::<serde_json::Value>()
+            .await
+            .map_err(|e| DataError::Schema {
+                provider: self.config.name.clone(),
+                detail: format!("response is not valid JSON: {}", describe(e)),
+            })?;
+
+        if let Some(message) = self
+            .config
+            .rate_limit_detector
+            .and_then(|detect| detect(&body))
+        {
+            HttpMetrics::increment(&self.metrics.throttled);
+            return Ok(Attempt::Retry {
+                reason: message,
+                throttled: true,
+                retry_after: None,
+                status: Some(status.as_u16()),
+            });
+        }
+
+        Ok(Attempt::Done(body))
+    }
+}
+
+/// Describes a reqwest error with its causes, leaving out the request URL,
+/// which carries the API key in its query string.
+fn describe(e: reqwest::Error) -> String {
+    let e = e.without_url();
+    let mut message = e.to_string();
+    let mut source = std::error::Error::source(&e);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[tokio::test]
+    async fn test_rate_limiter_paces_requests() {
+        // 1200 requests per minute = one token every 50ms, no burst
+        let limiter = RateLimiter::new(1200.0, 1);
+        let start = Instant::now();
+
+        assert!(limiter.acquire().await.is_zero());
+        limiter.acquire().await;
+        limiter.acquire().await;
+
+        assert!(start.elapsed() >= Duration::from_millis(95));
+    }
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(1),
+            jitter: 0.5,
+        };
+        let mut rng = StdRng::seed_from_u64(1);
+
+        for attempt in 0..10 {
+            let delay = policy.delay_for(attempt, &mut rng);
+            let ceiling = Duration::from_millis(100 * 2u64.pow(attempt)).min(policy.max_delay);
+            assert!(delay <= ceiling);
+            assert!(delay >= ceiling / 2);
+        }
+
+        let no_jitter = RetryPolicy {
+            jitter: 0.0,
+            ..policy
+        };
+        assert_eq!(no_jitter.delay_for(2, &mut rng), Duration::from_millis(400));
+    }
+}
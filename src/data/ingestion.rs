@@ -1,7 +1,31 @@
-use anyhow::Result;
+use super::error::{DataError, DataResult};
+use super::http::{HttpClient, HttpMetricsSnapshot, ProviderConfig, RetryPolicy};
+use super::panel::{MarketPanel, MissingBarPolicy};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Default request budget of the Alpha Vantage free tier.
+const DEFAULT_REQUESTS_PER_MINUTE: f64 = 5.0;
+
+/// Default number of symbols fetched concurrently by `fetch_historical_panel`.
+const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 2;
+
+/// Timeout applied to every Alpha Vantage request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Provider name reported in errors, logs and metrics.
+const PROVIDER: &str = "Alpha Vantage";
+
+/// Base URL of the Alpha Vantage API.
+const ALPHA_VANTAGE_URL: &str = "https://www.alphavantage.co";
+
+/// Key of the daily time series in Alpha Vantage crypto responses.
+const DAILY_SERIES_KEY: &str = "Time Series (Digital Currency Daily)";
 
 /// Represents a single market data point for a financial instrument.
 ///
@@ -15,6 +39,7 @@ use std::env;
 /// * `volume`: The total trading volume
 /// * `high`: The highest price during the period
 /// * `low`: The lowest price during the period
+/// * `open`: The price at the start of the period
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct MarketData {
     pub timestamp: DateTime<Utc>,
@@ -23,6 +48,7 @@ pub struct MarketData {
     pub volume: f64,
     pub high: f64,
     pub low: f64,
+    pub open: f64,
 }
 
 /// Manages data ingestion from external financial data APIs.
@@ -36,29 +62,99 @@ pub struct MarketData {
 /// * Supports historical data retrieval
 /// * Robust error handling for API interactions
 /// * Automatic environment-based API key management
+/// * Concurrent multi-symbol retrieval under a shared rate limit
+/// * Automatic retries with backoff when the provider throttles requests
+///
+/// Clones share the same HTTP client, token bucket and metrics, so concurrent
+/// tasks built from one instance respect a single rate limit.
+#[derive(Clone)]
 pub struct DataIngestion {
     api_key: String,
-    client: reqwest::Client,
+    base_url: String,
+    http: HttpClient,
+    max_concurrent_requests: usize,
 }
 
 impl DataIngestion {
     /// Creates a new `DataIngestion` instance with API credentials.
     ///
-    /// Retrieves the Alpha Vantage API key from environment variables.
+    /// Retrieves the Alpha Vantage API key from environment variables. Requests are
+    /// rate limited to `ALPHA_VANTAGE_REQUESTS_PER_MINUTE` (default 5, the free-tier
+    /// limit) and retried with exponential backoff when throttled.
     ///
     /// # Errors
-    /// Returns an error if the `ALPHA_VANTAGE_API_KEY` environment variable is not set
+    /// Returns `DataError::MissingCredentials` if the `ALPHA_VANTAGE_API_KEY`
+    /// environment variable is not set or empty
     ///
     /// # Returns
     /// A new `DataIngestion` instance with configured HTTP client
-    pub fn new() -> Result<Self> {
+    pub fn new() -> DataResult<Self> {
         let api_key = env::var("ALPHA_VANTAGE_API_KEY")
-            .expect("ALPHA_VANTAGE_API_KEY must be set in environment");
+            .ok()
+            .filter(|key| !key.trim().is_empty())
+            .ok_or_else(|| DataError::MissingCredentials {
+                variable: "ALPHA_VANTAGE_API_KEY".to_string(),
+            })?;
+
+        let requests_per_minute = env::var("ALPHA_VANTAGE_REQUESTS_PER_MINUTE")
+            .ok()
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|&value| value > 0.0)
+            .unwrap_or(DEFAULT_REQUESTS_PER_MINUTE);
 
-        Ok(Self {
-            api_key,
-            client: reqwest::Client::new(),
-        })
+        let http = HttpClient::new(Self::provider_config(requests_per_minute))?;
+
+        Ok(Self::with_client(api_key, ALPHA_VANTAGE_URL, http))
+    }
+
+    /// Creates a `DataIngestion` instance from explicit settings.
+    ///
+    /// Useful to point the client at a different endpoint, such as a proxy or a
+    /// local stub server, or to share one `HttpClient` between several instances.
+    ///
+    /// # Arguments
+    /// * `api_key`: Alpha Vantage API key
+    /// * `base_url`: Scheme and host of the API, without a trailing slash
+    /// * `http`: Rate-limited HTTP client used for every request
+    pub fn with_client(
+        api_key: impl Into<String>,
+        base_url: impl Into<String>,
+        http: HttpClient,
+    ) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: base_url.into(),
+            http,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+        }
+    }
+
+    /// Returns the default Alpha Vantage provider settings for the given request budget.
+    ///
+    /// # Arguments
+    /// * `requests_per_minute`: Sustained request rate allowed by the API plan
+    pub fn provider_config(requests_per_minute: f64) -> ProviderConfig {
+        ProviderConfig {
+            name: PROVIDER.to_string(),
+            requests_per_minute,
+            burst: 1,
+            timeout: REQUEST_TIMEOUT,
+            retry: RetryPolicy::default(),
+            rate_limit_detector: Some(alpha_vantage_rate_limit),
+        }
+    }
+
+    /// Sets the maximum number of requests `fetch_historical_panel` keeps in flight.
+    ///
+    /// # Arguments
+    /// * `max_concurrent_requests`: Maximum number of concurrent requests (at least 1)
+    pub fn set_max_concurrent_requests(&mut self, max_concurrent_requests: usize) {
+        self.max_concurrent_requests = max_concurrent_requests.max(1);
+    }
+
+    /// Returns request, retry and throttling counters of the underlying HTTP client.
+    pub fn http_metrics(&self) -> HttpMetricsSnapshot {
+        self.http.metrics()
     }
 
     /// Fetches daily cryptocurrency market data for a given symbol.
@@ -71,141 +167,24 @@ impl DataIngestion {
     ///
     /// # Errors
     /// Returns an error if:
-    /// - API request fails
+    /// - API request fails or is rate limited
+    /// - The provider returns an error message
     /// - Response parsing encounters issues
     /// - No data is available for the symbol
     ///
     /// # Returns
     /// A vector of `MarketData` sorted from most recent to oldest
-    pub async fn fetch_crypto_data(&self, symbol: &str) -> Result<Vec<MarketData>> {
-        let url = format!(
-            "https://www.alphavantage.co/query?function=DIGITAL_CURRENCY_DAILY&symbol={}&market=USD&apikey={}",
-            symbol, self.api_key
-        );
-
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
-
-        // Check for error messages
-        if let Some(error_message) = response.get("Error Message") {
-            return Err(anyhow::anyhow!(
-                "Alpha Vantage API error: {}",
-                error_message.as_str().unwrap_or("Unknown error")
-            ));
-        }
-
-        // Check for information messages (like rate limiting)
-        if let Some(info) = response.get("Note") {
-            eprintln!("Alpha Vantage API note: {}", info.as_str().unwrap_or(""));
-            // Continue processing if it's just a warning
-        }
-
-        // Parse the response into our MarketData structure
-        let time_series = match response.get("Time Series (Digital Currency Daily)") {
-            Some(ts) => ts.as_object().ok_or_else(|| {
-                eprintln!("Unexpected API response format: {:?}", response);
-                anyhow::anyhow!("Invalid response format: Time Series data not found")
-            })?,
-            None => {
-                // Print the full response for debugging
-                eprintln!("API Response Debug: {:#?}", response);
-
-                // Check for common error conditions
-                if let Some(note) = response.get("Note") {
-                    return Err(anyhow::anyhow!(
-                        "API Rate limit: {}",
-                        note.as_str().unwrap_or("Unknown rate limit message")
-                    ));
-                }
-
-                if let Some(info) = response.get("Information") {
-                    return Err(anyhow::anyhow!(
-                        "API Information: {}",
-                        info.as_str().unwrap_or("Unknown information message")
-                    ));
-                }
-
-                return Err(anyhow::anyhow!("Time Series data not found in response. This could be due to an invalid API key, rate limiting, or invalid symbol."));
-            }
-        };
-
-        let mut market_data = Vec::new();
-
-        for (timestamp_str, data) in time_series {
-            let data = data.as_object().ok_or_else(|| {
-                anyhow::anyhow!("Invalid data format for timestamp {}", timestamp_str)
-            })?;
-
-            // Use more robust error handling for data extraction
-            let market_entry = MarketData {
-                timestamp: DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", timestamp_str))
-                    .map_err(|e| anyhow::anyhow!("Invalid timestamp format: {}", e))?
-                    .with_timezone(&Utc),
-                symbol: symbol.to_string(),
-                price: {
-                    // Debug print available keys
-                    eprintln!(
-                        "Available data keys: {:#?}",
-                        data.keys().collect::<Vec<_>>()
-                    );
-
-                    data.get("4a. close (USD)")
-                        .or_else(|| data.get("4. close"))
-                        .ok_or_else(|| {
-                            eprintln!("Failed to find close price in data: {:#?}", data);
-                            anyhow::anyhow!("Close price not found in response")
-                        })?
-                        .as_str()
-                        .ok_or_else(|| anyhow::anyhow!("Close price is not a string"))?
-                        .parse()?
-                },
-                volume: {
-                    data.get("5. volume")
-                        .ok_or_else(|| {
-                            eprintln!("Failed to find volume in data: {:#?}", data);
-                            anyhow::anyhow!("Volume not found in response")
-                        })?
-                        .as_str()
-                        .ok_or_else(|| anyhow::anyhow!("Volume is not a string"))?
-                        .parse()?
-                },
-                high: {
-                    data.get("2a. high (USD)")
-                        .or_else(|| data.get("2. high"))
-                        .ok_or_else(|| {
-                            eprintln!("Failed to find high price in data: {:#?}", data);
-                            anyhow::anyhow!("High price not found in response")
-                        })?
-                        .as_str()
-                        .ok_or_else(|| anyhow::anyhow!("High price is not a string"))?
-                        .parse()?
-                },
-                low: {
-                    data.get("3a. low (USD)")
-                        .or_else(|| data.get("3. low"))
-                        .ok_or_else(|| {
-                            eprintln!("Failed to find low price in data: {:#?}", data);
-                            anyhow::anyhow!("Low price not found in response")
-                        })?
-                        .as_str()
-                        .ok_or_else(|| anyhow::anyhow!("Low price is not a string"))?
-                        .parse()?
-                },
-            };
-
-            market_data.push(market_entry);
-        }
+    pub async fn fetch_crypto_data(&self, symbol: &str) -> DataResult<Vec<MarketData>> {
+        let response = self.fetch_daily_series(symbol).await?;
+        let mut market_data = parse_daily_series(&response, symbol, |_| true)?;
 
         // Sort by timestamp in descending order to get most recent first
-        market_data.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        market_data.sort_by_key(|data| std::cmp::Reverse(data.timestamp));
 
         if market_data.is_empty() {
-            return Err(anyhow::anyhow!("No market data returned from API"));
+            return Err(DataError::NoData {
+                symbol: symbol.to_string(),
+            });
         }
 
         Ok(market_data)
@@ -222,144 +201,543 @@ impl DataIngestion {
     ///
     /// # Errors
     /// Returns an error if:
-    /// - API request fails
+    /// - API request fails or is rate limited
+    /// - The provider returns an error message
     /// - Response parsing encounters issues
     /// - No data is available for the symbol or date range
     ///
     /// # Returns
-    /// A vector of `MarketData` within the specified date range, sorted from most recent to oldest
+    /// A vector of `MarketData` within the specified date range, sorted from oldest to most recent
     pub async fn fetch_historical_crypto_data(
         &self,
         symbol: &str,
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
-    ) -> Result<Vec<MarketData>> {
+    ) -> DataResult<Vec<MarketData>> {
+        let response = self.fetch_daily_series(symbol).await?;
+        let mut market_data = parse_daily_series(&response, symbol, |timestamp| {
+            timestamp >= start_date && timestamp <= end_date
+        })?;
+
+        market_data.sort_by_key(|data| data.timestamp);
+
+        if market_data.is_empty() {
+            return Err(DataError::NoData {
+                symbol: symbol.to_string(),
+            });
+        }
+
+        Ok(market_data)
+    }
+
+    /// Fetches historical market data for several symbols into an aligned panel.
+    ///
+    /// Symbols are fetched concurrently, bounded by the configured number of
+    /// concurrent requests and by the provider's token bucket.
+    ///
+    /// # Arguments
+    /// * `symbols`: The cryptocurrency symbols to fetch (e.g., `["SOL", "ETH"]`)
+    /// * `start_date`: The beginning of the date range (inclusive)
+    /// * `end_date`: The end of the date range (inclusive)
+    /// * `policy`: How bars missing for a symbol at a panel timestamp are handled
+    ///
+    /// # Errors
+    /// Returns `DataError::Symbol` wrapping the cause if fetching any of the symbols fails
+    ///
+    /// # Returns
+    /// A `MarketPanel` with the symbols in the order they were requested
+    pub async fn fetch_historical_panel(
+        &self,
+        symbols: &[String],
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+        policy: MissingBarPolicy,
+    ) -> DataResult<MarketPanel> {
+        let semaphore = Arc::new(Semaphore::new(self.max_concurrent_requests));
+        let mut tasks = JoinSet::new();
+
+        for (index, symbol) in symbols.iter().enumerate() {
+            let ingestion = self.clone();
+            let semaphore = Arc::clone(&semaphore);
+            let symbol = symbol.clone();
+
+            tasks.spawn(async move {
+                let _permit = semaphore
+                    .acquire_owned()
+                    .await
+                    .map_err(|e| DataError::Task(e.to_string()))?;
+                let data = ingestion
+                    .fetch_historical_crypto_data(&symbol, start_date, end_date)
+                    .await
+                    .map_err(|e| DataError::Symbol {
+                        symbol: symbol.clone(),
+                        source: Box::new(e),
+                    })?;
+                Ok::<_, DataError>((index, symbol, data))
+            });
+        }
+
+        let mut series = Vec::with_capacity(symbols.len());
+        while let Some(joined) = tasks.join_next().await {
+            series.push(joined.map_err(|e| DataError::Task(e.to_string()))??);
+        }
+        series.sort_by_key(|(index, _, _)| *index);
+
+        Ok(MarketPanel::new(
+            series
+                .into_iter()
+                .map(|(_, symbol, data)| (symbol, data))
+                .collect(),
+            policy,
+        ))
+    }
+
+    /// Requests the daily time series of a symbol and checks it for provider errors.
+    ///
+    /// # Errors
+    /// Returns `DataError::Provider` for an `Error Message` response; throttling
+    /// notices end in `DataError::RateLimited` once the HTTP layer's retries run out
+    async fn fetch_daily_series(&self, symbol: &str) -> DataResult<serde_json::Value> {
         let url = format!(
-            "https://www.alphavantage.co/query?function=DIGITAL_CURRENCY_DAILY&symbol={}&market=USD&apikey={}",
-            symbol, self.api_key
+            "{}/query?function=DIGITAL_CURRENCY_DAILY&symbol={}&market=USD&apikey={}",
+            self.base_url, symbol, self.api_key
         );
 
-        let response = self
-            .client
-            .get(&url)
-            .send()
-            .await?
-            .json::<serde_json::Value>()
-            .await?;
+        let response = self.http.get_json(&url).await?;
 
         // Check for error messages
         if let Some(error_message) = response.get("Error Message") {
-            return Err(anyhow::anyhow!(
-                "Alpha Vantage API error: {}",
-                error_message.as_str().unwrap_or("Unknown error")
-            ));
+            return Err(DataError::Provider {
+                provider: PROVIDER.to_string(),
+                message: error_message
+                    .as_str()
+                    .unwrap_or("Unknown error")
+                    .to_string(),
+            });
         }
 
-        // Check for information messages (like rate limiting)
-        if let Some(info) = response.get("Note") {
-            eprintln!("Alpha Vantage API note: {}", info.as_str().unwrap_or(""));
-            // Continue processing if it's just a warning
+        // A note alongside data is only a warning
+        if let Some(note) = response.get("Note").and_then(|note| note.as_str()) {
+            tracing::warn!(provider = PROVIDER, symbol, note, "provider note");
         }
 
-        let time_series = response["Time Series (Digital Currency Daily)"]
-            .as_object()
-            .ok_or_else(|| {
-                // Print the actual response for debugging
-                eprintln!("Unexpected API response format: {:?}", response);
-                anyhow::anyhow!("Invalid response format: Time Series data not found")
-            })?;
-
-        let mut market_data = Vec::new();
+        Ok(response)
+    }
+}
 
-        for (timestamp_str, data) in time_series {
-            let timestamp = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", timestamp_str))
-                .map_err(|e| anyhow::anyhow!("Invalid timestamp format: {}", e))?
-                .with_timezone(&Utc);
+/// Parses an Alpha Vantage daily crypto time series into `MarketData`.
+///
+/// # Arguments
+/// * `response`: Full JSON response from the provider
+/// * `symbol`: Symbol stamped on every parsed bar
+/// * `include`: Predicate deciding which bar timestamps to keep
+///
+/// # Errors
+/// Returns `DataError::Schema` if the time series is missing or malformed, and
+/// `DataError::Parse` if a timestamp or price field cannot be parsed
+fn parse_daily_series(
+    response: &serde_json::Value,
+    symbol: &str,
+    include: impl Fn(DateTime<Utc>) -> bool,
+) -> DataResult<Vec<MarketData>> {
+    let time_series = match response.get(DAILY_SERIES_KEY) {
+        Some(series) => series.as_object().ok_or_else(|| DataError::Schema {
+            provider: PROVIDER.to_string(),
+            detail: format!("{} is not an object", DAILY_SERIES_KEY),
+        })?,
+        None => {
+            let keys: Vec<&String> = response
+                .as_object()
+                .map(|object| object.keys().collect())
+                .unwrap_or_default();
+            tracing::warn!(
+                provider = PROVIDER,
+                symbol,
+                ?keys,
+                "time series missing from response"
+            );
+
+            return Err(DataError::Schema {
+                provider: PROVIDER.to_string(),
+                detail: format!(
+                    "{} not found in response (keys: {:?}); check the API key and symbol",
+                    DAILY_SERIES_KEY, keys
+                ),
+            });
+        }
+    };
 
-            if timestamp < start_date || timestamp > end_date {
-                continue;
-            }
+    let mut market_data = Vec::with_capacity(time_series.len());
 
-            let data = data.as_object().ok_or_else(|| {
-                anyhow::anyhow!("Invalid data format for timestamp {}", timestamp_str)
-            })?;
+    for (timestamp_str, data) in time_series {
+        let timestamp = DateTime::parse_from_rfc3339(&format!("{}T00:00:00Z", timestamp_str))
+            .map_err(|e| DataError::Parse {
+                field: "timestamp".to_string(),
+                timestamp: timestamp_str.clone(),
+                reason: e.to_string(),
+            })?
+            .with_timezone(&Utc);
 
-            // Use more robust error handling for data extraction
-            let market_entry = MarketData {
-                timestamp,
-                symbol: symbol.to_string(),
-                price: {
-                    // Debug print available keys
-                    eprintln!(
-                        "Available data keys: {:#?}",
-                        data.keys().collect::<Vec<_>>()
-                    );
-
-                    data.get("4a. close (USD)")
-                        .or_else(|| data.get("4. close"))
-                        .ok_or_else(|| {
-                            eprintln!("Failed to find close price in data: {:#?}", data);
-                            anyhow::anyhow!("Close price not found in response")
-                        })?
-                        .as_str()
-                        .ok_or_else(|| anyhow::anyhow!("Close price is not a string"))?
-                        .parse()?
-                },
-                volume: {
-                    data.get("5. volume")
-                        .ok_or_else(|| {
-                            eprintln!("Failed to find volume in data: {:#?}", data);
-                            anyhow::anyhow!("Volume not found in response")
-                        })?
-                        .as_str()
-                        .ok_or_else(|| anyhow::anyhow!("Volume is not a string"))?
-                        .parse()?
-                },
-                high: {
-                    data.get("2a. high (USD)")
-                        .or_else(|| data.get("2. high"))
-                        .ok_or_else(|| {
-                            eprintln!("Failed to find high price in data: {:#?}", data);
-                            anyhow::anyhow!("High price not found in response")
-                        })?
-                        .as_str()
-                        .ok_or_else(|| anyhow::anyhow!("High price is not a string"))?
-                        .parse()?
-                },
-                low: {
-                    data.get("3a. low (USD)")
-                        .or_else(|| data.get("3. low"))
-                        .ok_or_else(|| {
-                            eprintln!("Failed to find low price in data: {:#?}", data);
-                            anyhow::anyhow!("Low price not found in response")
-                        })?
-                        .as_str()
-                        .ok_or_else(|| anyhow::anyhow!("Low price is not a string"))?
-                        .parse()?
-                },
-            };
-
-            market_data.push(market_entry);
+        if !include(timestamp) {
+            continue;
         }
 
-        if market_data.is_empty() {
-            return Err(anyhow::anyhow!(
-                "No market data found in the specified date range"
-            ));
-        }
+        let data = data.as_object().ok_or_else(|| DataError::Schema {
+            provider: PROVIDER.to_string(),
+            detail: format!("bar for {} is not an object", timestamp_str),
+        })?;
+
+        market_data.push(MarketData {
+            timestamp,
+            symbol: symbol.to_string(),
+            price: parse_field(
+                data,
+                &["4a. close (USD)", "4. close"],
+                "close",
+                timestamp_str,
+            )?,
+            volume: parse_field(data, &["5. volume"], "volume", timestamp_str)?,
+            high: parse_field(data, &["2a. high (USD)", "2. high"], "high", timestamp_str)?,
+            low: parse_field(data, &["3a. low (USD)", "3. low"], "low", timestamp_str)?,
+            open: parse_field(data, &["1a. open (USD)", "1. open"], "open", timestamp_str)?,
+        });
+    }
 
-        Ok(market_data)
+    tracing::debug!(
+        provider = PROVIDER,
+        symbol,
+        bars = market_data.len(),
+        "parsed daily series"
+    );
+
+    Ok(market_data)
+}
+
+/// Reads a numeric string field from a bar, trying each candidate key in order.
+///
+/// Alpha Vantage has used both market-suffixed (`"4a. close (USD)"`) and plain
+/// (`"4. close"`) key names for the same field.
+fn parse_field(
+    data: &serde_json::Map<String, serde_json::Value>,
+    keys: &[&str],
+    field: &str,
+    timestamp: &str,
+) -> DataResult<f64> {
+    let parse_error = |reason: String| DataError::Parse {
+        field: field.to_string(),
+        timestamp: timestamp.to_string(),
+        reason,
+    };
+
+    let value = keys.iter().find_map(|key| data.get(*key)).ok_or_else(|| {
+        parse_error(format!(
+            "none of {:?} present (available: {:?})",
+            keys,
+            data.keys().collect::<Vec<_>>()
+        ))
+    })?;
+
+    value
+        .as_str()
+        .ok_or_else(|| parse_error(format!("expected a string, found {}", value)))?
+        .parse()
+        .map_err(|e: std::num::ParseFloatError| parse_error(e.to_string()))
+}
+
+/// Detects Alpha Vantage throttling notices.
+///
+/// Alpha Vantage answers throttled requests with HTTP 200 and a `Note` or
+/// `Information` message in place of the time series.
+fn alpha_vantage_rate_limit(body: &serde_json::Value) -> Option<String> {
+    if body.get(DAILY_SERIES_KEY).is_some() {
+        return None;
     }
+
+    body.get("Note")
+        .or_else(|| body.get("Information"))
+        .map(|message| message.as_str().unwrap_or_default().to_string())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::data::stub_server::{fixture, StubResponse, StubServer};
+    use chrono::TimeZone;
+
+    /// Builds a client against the stub server that retries quickly.
+    fn stub_ingestion(server: &StubServer, max_retries: u32) -> DataIngestion {
+        ingestion_at(server.base_url(), max_retries)
+    }
+
+    fn ingestion_at(base_url: &str, max_retries: u32) -> DataIngestion {
+        let mut config = DataIngestion::provider_config(60_000.0);
+        config.burst = 100;
+        config.retry = RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(5),
+            jitter: 0.0,
+        };
+
+        DataIngestion::with_client("test-key", base_url, HttpClient::new(config).unwrap())
+    }
+
+    async fn fetch_fixture(body: &str) -> DataResult<Vec<MarketData>> {
+        let server = StubServer::start(vec![StubResponse::ok(body)]).await;
+        stub_ingestion(&server, 0).fetch_crypto_data("SOL").await
+    }
 
     #[tokio::test]
     async fn test_fetch_crypto_data() {
-        let ingestion = DataIngestion::new().unwrap();
-        let data = ingestion.fetch_crypto_data("SOL").await;
-        assert!(data.is_ok());
+        let server = StubServer::start(vec![StubResponse::ok(fixture!(
+            "alpha_vantage/daily_sol.json"
+        ))])
+        .await;
+        let ingestion = stub_ingestion(&server, 0);
+
+        let data = ingestion.fetch_crypto_data("SOL").await.unwrap();
+
+        assert_eq!(data.len(), 3);
+        assert!(data[0].timestamp > data[1].timestamp);
+        assert_eq!(data[0].symbol, "SOL");
+        assert_eq!(data[0].price, 258.75);
+        assert_eq!(data[0].open, 255.42);
+        assert_eq!(data[0].high, 262.10);
+        assert_eq!(data[0].low, 251.30);
+        assert_eq!(data[0].volume, 412345.67);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("function=DIGITAL_CURRENCY_DAILY"));
+        assert!(requests[0].contains("symbol=SOL"));
+        assert!(requests[0].contains("apikey=test-key"));
+    }
+
+    #[tokio::test]
+    async fn test_market_suffixed_keys_are_supported() {
+        let data = fetch_fixture(fixture!("alpha_vantage/daily_sol_market_keys.json"))
+            .await
+            .unwrap();
+
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].price, 162.09);
+        assert_eq!(data[0].high, 168.48);
+        assert_eq!(data[0].low, 160.55);
+        assert_eq!(data[0].open, 166.21);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_historical_filters_and_sorts_range() {
+        let server = StubServer::start(vec![StubResponse::ok(fixture!(
+            "alpha_vantage/daily_sol.json"
+        ))])
+        .await;
+        let start = Utc.with_ymd_and_hms(2025, 1, 23, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2025, 1, 31, 0, 0, 0).unwrap();
+
+        let data = stub_ingestion(&server, 0)
+            .fetch_historical_crypto_data("SOL", start, end)
+            .await
+            .unwrap();
+
+        assert_eq!(data.len(), 2);
+        assert_eq!(data[0].timestamp, start);
+        assert!(data[0].timestamp < data[1].timestamp);
+
+        let outside = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let empty = stub_ingestion(&server, 0)
+            .fetch_historical_crypto_data("SOL", outside, outside)
+            .await;
+        assert!(matches!(empty, Err(DataError::NoData { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_provider_and_rate_limit_messages() {
+        let error = fetch_fixture(fixture!("alpha_vantage/error_message.json")).await;
+        assert!(
+            matches!(error, Err(DataError::Provider { ref message, .. }) if message.starts_with("Invalid API call"))
+        );
+
+        let note = fetch_fixture(fixture!("alpha_vantage/note.json")).await;
+        assert!(matches!(note, Err(DataError::RateLimited { .. })));
+
+        let information = fetch_fixture(fixture!("alpha_vantage/information.json")).await;
+        assert!(
+            matches!(information, Err(DataError::RateLimited { ref message, .. }) if message.contains("25 requests per day"))
+        );
+
+        // A note next to a valid series is only a warning
+        let with_data = fetch_fixture(fixture!("alpha_vantage/note_with_data.json"))
+            .await
+            .unwrap();
+        assert_eq!(with_data.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_throttled_requests_are_retried() {
+        let server = StubServer::start(vec![
+            StubResponse::ok(fixture!("alpha_vantage/note.json")),
+            StubResponse::status(429, "{}"),
+            StubResponse::status(503, "{}"),
+            StubResponse::ok(fixture!("alpha_vantage/daily_sol.json")),
+        ])
+        .await;
+        let ingestion = stub_ingestion(&server, 3);
+
+        let data = ingestion.fetch_crypto_data("SOL").await.unwrap();
+        assert_eq!(data.len(), 3);
+
+        let metrics = ingestion.http_metrics();
+        assert_eq!(metrics.requests, 4);
+        assert_eq!(metrics.retries, 3);
+        assert_eq!(metrics.throttled, 2);
+        assert_eq!(metrics.server_errors, 1);
+        assert_eq!(metrics.failures, 0);
+
+        let exhausted = StubServer::start(vec![StubResponse::status(429, "{}")]).await;
+        let result = stub_ingestion(&exhausted, 2).fetch_crypto_data("SOL").await;
+        assert!(matches!(result, Err(DataError::RateLimited { .. })));
+        assert_eq!(exhausted.requests().len(), 3);
+
+        // Running out of retries on a throttling notice counts as a failure
+        let noted =
+            StubServer::start(vec![StubResponse::ok(fixture!("alpha_vantage/note.json"))]).await;
+        let ingestion = stub_ingestion(&noted, 1);
+        let result = ingestion.fetch_crypto_data("SOL").await;
+        assert!(matches!(result, Err(DataError::RateLimited { .. })));
+        assert_eq!(ingestion.http_metrics().failures, 1);
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_is_honoured() {
+        let server = StubServer::start(vec![
+            StubResponse {
+                headers: vec![("Retry-After".to_string(), "1".to_string())],
+                ..StubResponse::status(429, "{}")
+            },
+            StubResponse::ok(fixture!("alpha_vantage/daily_sol.json")),
+        ])
+        .await;
+        let ingestion = stub_ingestion(&server, 1);
+
+        // The server's delay outlasts the 5ms backoff cap
+        let start = std::time::Instant::now();
+        let data = ingestion.fetch_crypto_data("SOL").await.unwrap();
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(data.len(), 3);
+
+        let metrics = ingestion.http_metrics();
+        assert_eq!(metrics.requests, 2);
+        assert_eq!(metrics.throttled, 1);
+        assert_eq!(metrics.retries, 1);
+        assert_eq!(metrics.failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_errors_do_not_leak_the_api_key() {
+        let server = StubServer::start(vec![StubResponse::status(404, "{}")]).await;
+        let rejected = stub_ingestion(&server, 0)
+            .fetch_crypto_data("SOL")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            rejected,
+            DataError::Http {
+                status: Some(404),
+                ..
+            }
+        ));
+        assert!(!rejected.to_string().contains("test-key"));
+
+        // Nothing listens on a port once its listener is dropped
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let refused = ingestion_at(&base_url, 1)
+            .fetch_crypto_data("SOL")
+            .await
+            .unwrap_err();
+        assert!(matches!(refused, DataError::Http { status: None, .. }));
+        assert!(!refused.to_string().contains("test-key"));
+    }
+
+    #[tokio::test]
+    async fn test_schema_mismatches() {
+        for body in [
+            fixture!("alpha_vantage/malformed.txt"),
+            fixture!("alpha_vantage/missing_series.json"),
+            fixture!("alpha_vantage/series_not_object.json"),
+            fixture!("alpha_vantage/bar_not_object.json"),
+        ] {
+            let result = fetch_fixture(body).await;
+            assert!(
+                matches!(result, Err(DataError::Schema { .. })),
+                "{:?}",
+                result
+            );
+        }
+
+        let empty = fetch_fixture(fixture!("alpha_vantage/empty_series.json")).await;
+        assert!(matches!(empty, Err(DataError::NoData { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_parse_failures() {
+        for (body, expected_field) in [
+            (fixture!("alpha_vantage/missing_volume.json"), "volume"),
+            (fixture!("alpha_vantage/non_numeric_close.json"), "close"),
+            (fixture!("alpha_vantage/numeric_close.json"), "close"),
+            (fixture!("alpha_vantage/bad_timestamp.json"), "timestamp"),
+        ] {
+            let result = fetch_fixture(body).await;
+            assert!(
+                matches!(result, Err(DataError::Parse { ref field, .. }) if field == expected_field),
+                "{:?}",
+                result
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_historical_panel() {
+        let server = StubServer::with_routes(vec![
+            (
+                "symbol=SOL",
+                vec![StubResponse::ok(fixture!("alpha_vantage/daily_sol.json"))],
+            ),
+            (
+                "symbol=BTC",
+                vec![StubResponse::ok(fixture!(
+                    "alpha_vantage/note_with_data.json"
+                ))],
+            ),
+            (
+                "symbol=XYZ",
+                vec![StubResponse::ok(fixture!(
+                    "alpha_vantage/error_message.json"
+                ))],
+            ),
+        ])
+        .await;
+        let ingestion = stub_ingestion(&server, 0);
+        let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2025, 1, 31, 0, 0, 0).unwrap();
+        let symbols = vec!["BTC".to_string(), "SOL".to_string()];
+
+        let panel = ingestion
+            .fetch_historical_panel(&symbols, start, end, MissingBarPolicy::Keep)
+            .await
+            .unwrap();
+        assert_eq!(panel.symbols(), symbols.as_slice());
+        assert_eq!(panel.len(), 3);
+        assert_eq!(panel.missing_count("BTC"), 2);
+
+        let failing = vec!["SOL".to_string(), "XYZ".to_string()];
+        let error = ingestion
+            .fetch_historical_panel(&failing, start, end, MissingBarPolicy::Keep)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, DataError::Symbol { ref symbol, .. } if symbol == "XYZ"));
+        assert!(matches!(error.root_cause(), DataError::Provider { .. }));
     }
 }
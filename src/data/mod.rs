@@ -1,9 +1,15 @@
+pub mod error;
+pub mod http;
 pub mod ingestion;
+pub mod panel;
 pub mod processing;
+#[cfg(test)]
+mod stub_server;
+pub mod synthetic;
 
-pub use ingestion::DataIngestion;
+pub use error::{DataError, DataResult};
+pub use http::{HttpClient, HttpMetricsSnapshot, ProviderConfig, RateLimiter, RetryPolicy};
+pub use ingestion::{DataIngestion, MarketData};
+pub use panel::{MarketPanel, MissingBarPolicy, PanelBar};
 pub use processing::{DataProcessor, ProcessedMarketData};
-
-// Re-export for tests
-#[cfg(test)]
-pub use ingestion::MarketData;
+pub use synthetic::{PriceProcess, Regime, Scenario, SyntheticMarket};
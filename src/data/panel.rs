@@ -0,0 +1,316 @@
+use super::ingestion::MarketData;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+
+/// Determines how bars missing for a symbol at a panel timestamp are treated.
+///
+/// - `Keep`: Leave the gap in place as `PanelBar::Missing`
+/// - `ForwardFill`: Carry the last known close forward as a zero-volume `PanelBar::Filled`
+/// - `DropIncomplete`: Only keep timestamps at which every symbol has an observed bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MissingBarPolicy {
+    Keep,
+    ForwardFill,
+    DropIncomplete,
+}
+
+/// A single cell of a `MarketPanel`.
+///
+/// Distinguishes bars actually returned by the data provider from bars synthesized
+/// by the missing-bar policy, so consumers never mistake a fill for real trading.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PanelBar {
+    /// Bar returned by the data provider
+    Observed(MarketData),
+    /// Bar carried forward from the previous close (zero volume, flat range)
+    Filled(MarketData),
+    /// No data available for this symbol at this timestamp
+    Missing,
+}
+
+impl PanelBar {
+    /// Returns the bar data for observed and filled bars.
+    pub fn data(&self) -> Option<&MarketData> {
+        match self {
+            PanelBar::Observed(data) | PanelBar::Filled(data) => Some(data),
+            PanelBar::Missing => None,
+        }
+    }
+
+    /// Returns `true` if the bar was returned by the data provider.
+    pub fn is_observed(&self) -> bool {
+        matches!(self, PanelBar::Observed(_))
+    }
+}
+
+/// Market data for several symbols aligned on a common time index.
+///
+/// Every symbol has exactly one `PanelBar` per panel timestamp, giving portfolio
+/// and cross-asset strategies a consistent view of the market at each step.
+///
+/// # Fields
+/// * `symbols`: Symbols in the panel, in the order they were supplied
+/// * `timestamps`: Sorted union (or intersection, see `MissingBarPolicy`) of bar timestamps
+/// * `bars`: Per-symbol bars, index-aligned with `timestamps`
+/// * `policy`: Missing-bar policy used to build the panel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketPanel {
+    symbols: Vec<String>,
+    timestamps: Vec<DateTime<Utc>>,
+    bars: HashMap<String, Vec<PanelBar>>,
+    policy: MissingBarPolicy,
+}
+
+impl MarketPanel {
+    /// Aligns per-symbol series onto a common time index.
+    ///
+    /// Input series may be in any order and may contain gaps; duplicate timestamps
+    /// within a series keep the last bar supplied. Series supplied more than once
+    /// for a symbol are merged into one, in the position of its first series.
+    ///
+    /// # Arguments
+    /// * `series`: Pairs of symbol and its market data
+    /// * `policy`: How to treat bars missing for a symbol at a given timestamp
+    ///
+    /// # Returns
+    /// A new `MarketPanel` with one bar per symbol per timestamp
+    pub fn new(series: Vec<(String, Vec<MarketData>)>, policy: MissingBarPolicy) -> Self {
+        let mut all_timestamps = BTreeSet::new();
+        let mut by_symbol: Vec<(String, HashMap<DateTime<Utc>, MarketData>)> =
+            Vec::with_capacity(series.len());
+
+        for (symbol, data) in series {
+            let index = match by_symbol
+                .iter()
+                .position(|(existing, _)| *existing == symbol)
+            {
+                Some(index) => index,
+                None => {
+                    by_symbol.push((symbol, HashMap::with_capacity(data.len())));
+                    by_symbol.len() - 1
+                }
+            };
+            let lookup = &mut by_symbol[index].1;
+            for bar in data {
+                all_timestamps.insert(bar.timestamp);
+                lookup.insert(bar.timestamp, bar);
+            }
+        }
+
+        let mut timestamps: Vec<DateTime<Utc>> = all_timestamps.into_iter().collect();
+
+        if policy == MissingBarPolicy::DropIncomplete {
+            timestamps.retain(|timestamp| {
+                by_symbol
+                    .iter()
+                    .all(|(_, lookup)| lookup.contains_key(timestamp))
+            });
+        }
+
+        let mut symbols = Vec::with_capacity(by_symbol.len());
+        let mut bars = HashMap::with_capacity(by_symbol.len());
+
+        for (symbol, mut lookup) in by_symbol {
+            let mut aligned = Vec::with_capacity(timestamps.len());
+            let mut last_close: Option<MarketData> = None;
+
+            for timestamp in &timestamps {
+                let bar = match lookup.remove(timestamp) {
+                    Some(data) => {
+                        last_close = Some(data.clone());
+                        PanelBar::Observed(data)
+                    }
+                    None => match (&last_close, policy) {
+                        (Some(previous), MissingBarPolicy::ForwardFill) => {
+                            PanelBar::Filled(MarketData {
+                                timestamp: *timestamp,
+                                symbol: symbol.clone(),
+                                price: previous.price,
+                                volume: 0.0,
+                                high: previous.price,
+                                low: previous.price,
+                                open: previous.price,
+                            })
+                        }
+                        _ => PanelBar::Missing,
+                    },
+                };
+                aligned.push(bar);
+            }
+
+            symbols.push(symbol.clone());
+            bars.insert(symbol, aligned);
+        }
+
+        Self {
+            symbols,
+            timestamps,
+            bars,
+            policy,
+        }
+    }
+
+    /// Returns the symbols in the panel.
+    pub fn symbols(&self) -> &[String] {
+        &self.symbols
+    }
+
+    /// Returns the aligned timestamps, oldest first.
+    pub fn timestamps(&self) -> &[DateTime<Utc>] {
+        &self.timestamps
+    }
+
+    /// Returns the missing-bar policy the panel was built with.
+    pub fn policy(&self) -> MissingBarPolicy {
+        self.policy
+    }
+
+    /// Returns the number of timestamps in the panel.
+    pub fn len(&self) -> usize {
+        self.timestamps.len()
+    }
+
+    /// Returns `true` if the panel has no timestamps.
+    pub fn is_empty(&self) -> bool {
+        self.timestamps.is_empty()
+    }
+
+    /// Returns the aligned bars of a symbol, or `None` if the symbol is not in the panel.
+    pub fn series(&self, symbol: &str) -> Option<&[PanelBar]> {
+        self.bars.get(symbol).map(|bars| bars.as_slice())
+    }
+
+    /// Returns the bar of `symbol` at timestamp index `index`.
+    pub fn bar(&self, symbol: &str, index: usize) -> Option<&PanelBar> {
+        self.bars.get(symbol).and_then(|bars| bars.get(index))
+    }
+
+    /// Returns the bars of every symbol at timestamp index `index`, in symbol order.
+    pub fn row(&self, index: usize) -> Vec<(&str, &PanelBar)> {
+        self.symbols
+            .iter()
+            .filter_map(|symbol| self.bar(symbol, index).map(|bar| (symbol.as_str(), bar)))
+            .collect()
+    }
+
+    /// Returns `true` if every symbol has an observed bar at timestamp index `index`.
+    pub fn is_complete_row(&self, index: usize) -> bool {
+        self.symbols
+            .iter()
+            .all(|symbol| self.bar(symbol, index).is_some_and(|bar| bar.is_observed()))
+    }
+
+    /// Returns only the bars actually observed for `symbol`, oldest first.
+    ///
+    /// Suitable for single-symbol consumers such as `DataProcessor` and `Backtester`
+    /// that should not see forward-filled bars.
+    pub fn observed_series(&self, symbol: &str) -> Vec<MarketData> {
+        self.series(symbol)
+            .map(|bars| {
+                bars.iter()
+                    .filter_map(|bar| match bar {
+                        PanelBar::Observed(data) => Some(data.clone()),
+                        _ => None,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of timestamps at which `symbol` has no observed bar.
+    pub fn missing_count(&self, symbol: &str) -> usize {
+        self.series(symbol)
+            .map(|bars| bars.iter().filter(|bar| !bar.is_observed()).count())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backtesting::test_support::daily_bars;
+
+    fn create_series(symbol: &str, days: &[usize]) -> (String, Vec<MarketData>) {
+        let prices: Vec<f64> = (0..5).map(|day| 100.0 + day as f64).collect();
+        let bars = daily_bars(&prices);
+        let data = days
+            .iter()
+            .map(|&day| MarketData {
+                symbol: symbol.to_string(),
+                high: bars[day].price + 1.0,
+                low: bars[day].price - 1.0,
+                ..bars[day].clone()
+            })
+            .collect();
+        (symbol.to_string(), data)
+    }
+
+    #[test]
+    fn test_keep_policy_marks_gaps() {
+        let panel = MarketPanel::new(
+            vec![
+                create_series("SOL", &[0, 1, 2, 3]),
+                create_series("ETH", &[3, 1, 0]),
+            ],
+            MissingBarPolicy::Keep,
+        );
+
+        assert_eq!(panel.len(), 4);
+        assert_eq!(panel.symbols(), ["SOL".to_string(), "ETH".to_string()]);
+        assert!(matches!(panel.bar("ETH", 2), Some(PanelBar::Missing)));
+        assert_eq!(panel.missing_count("ETH"), 1);
+        assert!(!panel.is_complete_row(2));
+        assert!(panel.is_complete_row(3));
+        assert_eq!(panel.row(2).len(), 2);
+    }
+
+    #[test]
+    fn test_forward_fill_policy() {
+        let panel = MarketPanel::new(
+            vec![create_series("SOL", &[0, 1, 2]), create_series("ETH", &[1])],
+            MissingBarPolicy::ForwardFill,
+        );
+
+        // Leading gap cannot be filled, trailing gap carries the last close
+        assert!(matches!(panel.bar("ETH", 0), Some(PanelBar::Missing)));
+        let filled = panel.bar("ETH", 2).unwrap();
+        assert!(matches!(filled, PanelBar::Filled(_)));
+        let data = filled.data().unwrap();
+        assert_eq!(data.price, 101.0);
+        assert_eq!(data.volume, 0.0);
+        assert_eq!(data.timestamp, panel.timestamps()[2]);
+        assert_eq!(panel.observed_series("ETH").len(), 1);
+    }
+
+    #[test]
+    fn test_drop_incomplete_policy() {
+        let panel = MarketPanel::new(
+            vec![
+                create_series("SOL", &[0, 1, 2, 4]),
+                create_series("ETH", &[1, 2, 3, 4]),
+            ],
+            MissingBarPolicy::DropIncomplete,
+        );
+
+        assert_eq!(panel.len(), 3);
+        assert!((0..panel.len()).all(|index| panel.is_complete_row(index)));
+        assert_eq!(panel.missing_count("SOL"), 0);
+    }
+
+    #[test]
+    fn test_duplicate_symbols_are_merged() {
+        let panel = MarketPanel::new(
+            vec![
+                create_series("SOL", &[0, 1]),
+                create_series("ETH", &[0, 1, 2]),
+                create_series("SOL", &[2]),
+            ],
+            MissingBarPolicy::Keep,
+        );
+
+        assert_eq!(panel.symbols(), ["SOL".to_string(), "ETH".to_string()]);
+        assert_eq!(panel.missing_count("SOL"), 0);
+        assert_eq!(panel.observed_series("SOL").len(), 3);
+    }
+}
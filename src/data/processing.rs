@@ -242,13 +242,14 @@ mod tests {
             volume: 1000.0,
             high: price + 1.0,
             low: price - 1.0,
+            open: price,
         }
     }
 
     #[test]
     fn test_moving_average_calculation() {
         let mut processor = DataProcessor::new(100);
-        let prices = vec![10.0, 11.0, 12.0, 13.0, 14.0];
+        let prices = [10.0, 11.0, 12.0, 13.0, 14.0];
 
         for price in prices {
             let market_data = create_test_market_data(price);
@@ -265,7 +266,7 @@ mod tests {
     #[test]
     fn test_outlier_detection() {
         let mut processor = DataProcessor::new(100);
-        let normal_prices = vec![100.0, 101.0, 99.0, 100.5, 101.5];
+        let normal_prices = [100.0, 101.0, 99.0, 100.5, 101.5];
 
         for price in normal_prices {
             let market_data = create_test_market_data(price);
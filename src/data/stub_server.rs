@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Loads a recorded response body from `tests/fixtures`.
+macro_rules! fixture {
+    ($name:literal) => {
+        include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/",
+            $name
+        ))
+    };
+}
+pub(crate) use fixture;
+
+/// A canned HTTP response.
+#[derive(Debug, Clone)]
+pub struct StubResponse {
+    pub status: u16,
+    pub body: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl StubResponse {
+    /// A `200 OK` response with the given JSON body.
+    pub fn ok(body: &str) -> Self {
+        Self::status(200, body)
+    }
+
+    /// A response with an arbitrary status code.
+    pub fn status(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            body: body.to_string(),
+            headers: Vec::new(),
+        }
+    }
+}
+
+/// Responses served to requests whose target contains `pattern`.
+///
+/// Responses are served in order; the last one is repeated once the queue
+/// runs out.
+struct Route {
+    pattern: String,
+    responses: VecDeque<StubResponse>,
+}
+
+/// Local HTTP server answering requests from recorded fixtures.
+///
+/// Binds to an ephemeral port on localhost and records every request target so
+/// tests can assert on what the client sent.
+pub struct StubServer {
+    base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+}
+
+impl StubServer {
+    /// Starts a server serving `responses` to every request.
+    pub async fn start(responses: Vec<StubResponse>) -> Self {
+        Self::with_routes(vec![("", responses)]).await
+    }
+
+    /// Starts a server routing requests by a substring of the request target.
+    ///
+    /// # Arguments
+    /// * `routes`: Pairs of target substring and the responses served for it,
+    ///   checked in order
+    pub async fn with_routes(routes: Vec<(&str, Vec<StubResponse>)>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let routes: Arc<Mutex<Vec<Route>>> = Arc::new(Mutex::new(
+            routes
+                .into_iter()
+                .map(|(pattern, responses)| Route {
+                    pattern: pattern.to_string(),
+                    responses: responses.into(),
+                })
+                .collect(),
+        ));
+
+        let recorded = Arc::clone(&requests);
+        tokio::spawn(async move {
+            loop {
+                let Ok((mut socket, _)) = listener.accept().await else {
+                    return;
+                };
+                let routes = Arc::clone(&routes);
+                let recorded = Arc::clone(&recorded);
+
+                tokio::spawn(async move {
+                    let mut buffer = Vec::new();
+                    let mut chunk = [0u8; 1024];
+                    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+                        match socket.read(&mut chunk).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+                        }
+                    }
+
+                    let request = String::from_utf8_lossy(&buffer);
+                    let target = request
+                        .lines()
+                        .next()
+                        .and_then(|line| line.split_whitespace().nth(1))
+                        .unwrap_or_default()
+                        .to_string();
+                    recorded.lock().unwrap().push(target.clone());
+
+                    let response = {
+                        let mut routes = routes.lock().unwrap();
+                        routes
+                            .iter_mut()
+                            .find(|route| target.contains(&route.pattern))
+                            .and_then(|route| {
+                                if route.responses.len() > 1 {
+                                    route.responses.pop_front()
+                                } else {
+                                    route.responses.front().cloned()
+                                }
+                            })
+                            .unwrap_or_else(|| StubResponse::status(404, "{}"))
+                    };
+
+                    let mut raw = format!(
+                        "HTTP/1.1 {} STUB\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+                        response.status,
+                        response.body.len()
+                    );
+                    for (name, value) in &response.headers {
+                        raw.push_str(&format!("{}: {}\r\n", name, value));
+                    }
+                    raw.push_str("\r\n");
+                    raw.push_str(&response.body);
+
+                    let _ = socket.write_all(raw.as_bytes()).await;
+                    let _ = socket.shutdown().await;
+                });
+            }
+        });
+
+        Self { base_url, requests }
+    }
+
+    /// Returns the base URL of the server, e.g. `http://127.0.0.1:45123`.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the request targets received so far, in arrival order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
@@ -0,0 +1,498 @@
+use super::ingestion::MarketData;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, Normal, Poisson, StandardNormal};
+use serde::{Deserialize, Serialize};
+
+/// Stochastic process used to drive the close-to-close moves of a synthetic market.
+///
+/// Drift and volatility parameters are annualized and scaled to the bar interval
+/// through `SyntheticMarket::periods_per_year`, except for the GARCH coefficients
+/// which, as is conventional, are expressed per bar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PriceProcess {
+    /// Geometric Brownian motion with constant drift and volatility
+    GeometricBrownianMotion { drift: f64, volatility: f64 },
+    /// GARCH(1,1) returns: `var_t = omega + alpha * eps_{t-1}^2 + beta * var_{t-1}`
+    Garch {
+        drift: f64,
+        omega: f64,
+        alpha: f64,
+        beta: f64,
+    },
+    /// Merton jump-diffusion: GBM plus Poisson-arriving, normally distributed log jumps
+    JumpDiffusion {
+        drift: f64,
+        volatility: f64,
+        /// Expected number of jumps per year
+        jump_intensity: f64,
+        jump_mean: f64,
+        jump_std: f64,
+    },
+    /// Markov regime-switching GBM; `transitions[i][j]` is the per-bar probability
+    /// of moving from regime `i` to regime `j`
+    RegimeSwitching {
+        regimes: Vec<Regime>,
+        transitions: Vec<Vec<f64>>,
+    },
+    /// Ornstein-Uhlenbeck process on the log price, reverting towards `mean_price`
+    OrnsteinUhlenbeck {
+        mean_price: f64,
+        reversion_speed: f64,
+        volatility: f64,
+    },
+}
+
+/// A single market regime used by `PriceProcess::RegimeSwitching`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Regime {
+    pub drift: f64,
+    pub volatility: f64,
+}
+
+/// A deterministic one-off move applied on top of the price process.
+///
+/// # Fields
+/// * `bar`: Index of the bar on which the shock occurs
+/// * `change`: Simple return of the shock (e.g. `-0.35` for a 35% drop)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PriceShock {
+    pub bar: usize,
+    pub change: f64,
+}
+
+/// Seeded generator of synthetic OHLCV market data.
+///
+/// Produces `MarketData` series whose high, low and volume are consistent with
+/// the simulated closes, so strategies can be exercised on reproducible scenarios
+/// without network access.
+///
+/// # Fields
+/// * `symbol`: Symbol stamped on every generated bar
+/// * `start`: Timestamp of the first bar
+/// * `interval`: Time between consecutive bars
+/// * `initial_price`: Price the series starts from
+/// * `base_volume`: Typical volume of a quiet bar
+/// * `periods_per_year`: Number of bars per year used to scale annualized parameters
+/// * `process`: Stochastic process driving the closes
+/// * `shocks`: Deterministic moves injected on specific bars
+/// * `seed`: Seed of the random number generator
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyntheticMarket {
+    pub symbol: String,
+    pub start: DateTime<Utc>,
+    pub interval: Duration,
+    pub initial_price: f64,
+    pub base_volume: f64,
+    pub periods_per_year: f64,
+    pub process: PriceProcess,
+    pub shocks: Vec<PriceShock>,
+    pub seed: u64,
+}
+
+/// Per-run state carried between bars by the stateful processes.
+enum ProcessState {
+    Stateless,
+    Garch { variance: f64, last_shock: f64 },
+    Regime { current: usize },
+}
+
+impl SyntheticMarket {
+    /// Creates a new synthetic market driven by the given process.
+    ///
+    /// Defaults to daily bars starting on 2024-01-01, an initial price of 100,
+    /// a base volume of 1,000,000 and 365 periods per year.
+    ///
+    /// # Arguments
+    /// * `process`: Stochastic process driving the closes
+    /// * `seed`: Seed making the generated series reproducible
+    pub fn new(process: PriceProcess, seed: u64) -> Self {
+        Self {
+            symbol: "SYN".to_string(),
+            start: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            interval: Duration::days(1),
+            initial_price: 100.0,
+            base_volume: 1_000_000.0,
+            periods_per_year: 365.0,
+            process,
+            shocks: Vec::new(),
+            seed,
+        }
+    }
+
+    /// Generates `bars` consecutive bars, oldest first.
+    ///
+    /// The same configuration and seed always produce the same series.
+    ///
+    /// # Arguments
+    /// * `bars`: Number of bars to generate
+    ///
+    /// # Returns
+    /// A vector of `MarketData` sorted from oldest to most recent
+    pub fn generate(&self, bars: usize) -> Vec<MarketData> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let dt = 1.0 / self.periods_per_year;
+        let mut state = self.initial_state();
+        let mut market_data = Vec::with_capacity(bars);
+        let mut previous_close = self.initial_price;
+
+        for bar in 0..bars {
+            let (mut log_return, bar_volatility) =
+                self.next_log_return(&mut state, previous_close, dt, &mut rng);
+
+            for shock in self.shocks.iter().filter(|shock| shock.bar == bar) {
+                log_return += (1.0 + shock.change).max(1e-6).ln();
+            }
+
+            let open = previous_close;
+            let close = open * log_return.exp();
+
+            // Wicks extend beyond the open/close body by a fraction of the bar volatility
+            let wick_up: f64 = rng.sample::<f64, _>(StandardNormal).abs() * bar_volatility * 0.5;
+            let wick_down: f64 = rng.sample::<f64, _>(StandardNormal).abs() * bar_volatility * 0.5;
+            let high = open.max(close) * wick_up.exp();
+            let low = open.min(close) * (-wick_down).exp();
+
+            // Volume expands with the size of the move relative to normal volatility
+            let volume_noise: f64 = rng.sample(StandardNormal);
+            let activity = 1.0 + log_return.abs() / bar_volatility.max(1e-12);
+            let volume = self.base_volume * activity * (0.25 * volume_noise).exp();
+
+            market_data.push(MarketData {
+                timestamp: self.start + self.interval * bar as i32,
+                symbol: self.symbol.clone(),
+                price: close,
+                volume,
+                high,
+                low,
+                open,
+            });
+
+            previous_close = close;
+        }
+
+        market_data
+    }
+
+    fn initial_state(&self) -> ProcessState {
+        match &self.process {
+            PriceProcess::Garch {
+                omega, alpha, beta, ..
+            } => {
+                let persistence = alpha + beta;
+                let variance = if persistence < 1.0 {
+                    omega / (1.0 - persistence)
+                } else {
+                    *omega
+                };
+                ProcessState::Garch {
+                    variance,
+                    last_shock: 0.0,
+                }
+            }
+            PriceProcess::RegimeSwitching { .. } => ProcessState::Regime { current: 0 },
+            _ => ProcessState::Stateless,
+        }
+    }
+
+    /// Draws the next close-to-close log return.
+    ///
+    /// # Returns
+    /// A tuple of the log return and the per-bar volatility used to shape the bar
+    fn next_log_return(
+        &self,
+        state: &mut ProcessState,
+        previous_close: f64,
+        dt: f64,
+        rng: &mut StdRng,
+    ) -> (f64, f64) {
+        let z: f64 = rng.sample(StandardNormal);
+
+        match (&self.process, state) {
+            (PriceProcess::GeometricBrownianMotion { drift, volatility }, _) => {
+                let bar_volatility = volatility * dt.sqrt();
+                (
+                    (drift - 0.5 * volatility * volatility) * dt + bar_volatility * z,
+                    bar_volatility,
+                )
+            }
+            (
+                PriceProcess::Garch {
+                    drift,
+                    omega,
+                    alpha,
+                    beta,
+                },
+                ProcessState::Garch {
+                    variance,
+                    last_shock,
+                },
+            ) => {
+                *variance = omega + alpha * *last_shock * *last_shock + beta * *variance;
+                let bar_volatility = variance.sqrt();
+                let shock = bar_volatility * z;
+                *last_shock = shock;
+                (drift * dt + shock, bar_volatility)
+            }
+            (
+                PriceProcess::JumpDiffusion {
+                    drift,
+                    volatility,
+                    jump_intensity,
+                    jump_mean,
+                    jump_std,
+                },
+                _,
+            ) => {
+                let bar_volatility = volatility * dt.sqrt();
+                let compensator =
+                    jump_intensity * ((jump_mean + 0.5 * jump_std * jump_std).exp() - 1.0);
+                let mut log_return =
+                    (drift - 0.5 * volatility * volatility - compensator) * dt + bar_volatility * z;
+
+                let expected_jumps = jump_intensity * dt;
+                if expected_jumps > 0.0 {
+                    let jumps = Poisson::new(expected_jumps)
+                        .map(|poisson| poisson.sample(rng) as usize)
+                        .unwrap_or(0);
+                    if let Ok(jump_size) = Normal::new(*jump_mean, jump_std.abs()) {
+                        for _ in 0..jumps {
+                            log_return += jump_size.sample(rng);
+                        }
+                    }
+                }
+
+                (log_return, bar_volatility)
+            }
+            (
+                PriceProcess::RegimeSwitching {
+                    regimes,
+                    transitions,
+                },
+                ProcessState::Regime { current },
+            ) => {
+                if regimes.is_empty() {
+                    return (0.0, 0.0);
+                }
+
+                let regime = regimes[(*current).min(regimes.len() - 1)];
+                let bar_volatility = regime.volatility * dt.sqrt();
+                let log_return = (regime.drift - 0.5 * regime.volatility * regime.volatility) * dt
+                    + bar_volatility * z;
+
+                if let Some(row) = transitions.get(*current) {
+                    *current = Self::next_regime(row, *current, rng).min(regimes.len() - 1);
+                }
+
+                (log_return, bar_volatility)
+            }
+            (
+                PriceProcess::OrnsteinUhlenbeck {
+                    mean_price,
+                    reversion_speed,
+                    volatility,
+                },
+                _,
+            ) => {
+                // Exact discretization of the OU process on the log price
+                let decay = (-reversion_speed * dt).exp();
+                let bar_volatility = if *reversion_speed > 0.0 {
+                    volatility * ((1.0 - decay * decay) / (2.0 * reversion_speed)).sqrt()
+                } else {
+                    volatility * dt.sqrt()
+                };
+                let log_price = previous_close.ln();
+                let next_log_price =
+                    log_price * decay + mean_price.ln() * (1.0 - decay) + bar_volatility * z;
+
+                (next_log_price - log_price, bar_volatility)
+            }
+            // Stateful processes always start with their matching state
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Samples the next regime from a (not necessarily normalized) transition row.
+    fn next_regime(row: &[f64], current: usize, rng: &mut StdRng) -> usize {
+        let total: f64 = row.iter().filter(|p| **p > 0.0).sum();
+        if total <= 0.0 {
+            return current;
+        }
+
+        let mut draw = rng.gen::<f64>() * total;
+        for (regime, probability) in row.iter().enumerate() {
+            if *probability <= 0.0 {
+                continue;
+            }
+            if draw < *probability {
+                return regime;
+            }
+            draw -= probability;
+        }
+
+        current
+    }
+}
+
+/// Ready-made market scenarios for stress-testing strategies.
+///
+/// Each scenario maps to a `SyntheticMarket` configuration tuned to reproduce a
+/// recognizable market condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scenario {
+    /// Steady uptrend with moderate volatility
+    BullTrend,
+    /// Steady downtrend with moderate volatility
+    BearTrend,
+    /// Volatility-clustered market with a 40% crash halfway through
+    Crash,
+    /// Long, range-bound period reverting around the initial price
+    Sideways,
+    /// Alternating calm bull and volatile bear regimes
+    RegimeShifts,
+    /// Diffusion punctuated by frequent large jumps in both directions
+    FlashMoves,
+}
+
+impl Scenario {
+    /// Builds the `SyntheticMarket` configuration for this scenario.
+    ///
+    /// # Arguments
+    /// * `bars`: Number of bars the scenario will span (used to place shocks)
+    /// * `seed`: Seed making the generated series reproducible
+    pub fn market(&self, bars: usize, seed: u64) -> SyntheticMarket {
+        match self {
+            Scenario::BullTrend => SyntheticMarket::new(
+                PriceProcess::GeometricBrownianMotion {
+                    drift: 0.8,
+                    volatility: 0.5,
+                },
+                seed,
+            ),
+            Scenario::BearTrend => SyntheticMarket::new(
+                PriceProcess::GeometricBrownianMotion {
+                    drift: -0.8,
+                    volatility: 0.5,
+                },
+                seed,
+            ),
+            Scenario::Crash => {
+                let mut market = SyntheticMarket::new(
+                    PriceProcess::Garch {
+                        drift: 0.0,
+                        omega: 0.000_02,
+                        alpha: 0.1,
+                        beta: 0.85,
+                    },
+                    seed,
+                );
+                market.shocks.push(PriceShock {
+                    bar: bars / 2,
+                    change: -0.4,
+                });
+                market
+            }
+            Scenario::Sideways => SyntheticMarket::new(
+                PriceProcess::OrnsteinUhlenbeck {
+                    mean_price: 100.0,
+                    reversion_speed: 20.0,
+                    volatility: 0.3,
+                },
+                seed,
+            ),
+            Scenario::RegimeShifts => SyntheticMarket::new(
+                PriceProcess::RegimeSwitching {
+                    regimes: vec![
+                        Regime {
+                            drift: 0.6,
+                            volatility: 0.4,
+                        },
+                        Regime {
+                            drift: -1.2,
+                            volatility: 1.1,
+                        },
+                    ],
+                    transitions: vec![vec![0.97, 0.03], vec![0.06, 0.94]],
+                },
+                seed,
+            ),
+            Scenario::FlashMoves => SyntheticMarket::new(
+                PriceProcess::JumpDiffusion {
+                    drift: 0.0,
+                    volatility: 0.4,
+                    jump_intensity: 12.0,
+                    jump_mean: 0.0,
+                    jump_std: 0.12,
+                },
+                seed,
+            ),
+        }
+    }
+
+    /// Generates `bars` bars of this scenario with the given seed.
+    pub fn generate(&self, bars: usize, seed: u64) -> Vec<MarketData> {
+        self.market(bars, seed).generate(bars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_is_reproducible() {
+        for scenario in [
+            Scenario::BullTrend,
+            Scenario::Crash,
+            Scenario::Sideways,
+            Scenario::RegimeShifts,
+            Scenario::FlashMoves,
+        ] {
+            let first = scenario.generate(250, 7);
+            let second = scenario.generate(250, 7);
+            let other_seed = scenario.generate(250, 8);
+
+            assert_eq!(first.len(), 250);
+            for (a, b) in first.iter().zip(&second) {
+                assert_eq!(a.price, b.price);
+                assert_eq!(a.volume, b.volume);
+                assert_eq!(a.timestamp, b.timestamp);
+            }
+            assert!(first
+                .iter()
+                .zip(&other_seed)
+                .any(|(a, b)| a.price != b.price));
+        }
+    }
+
+    #[test]
+    fn test_bars_are_coherent() {
+        let data = Scenario::FlashMoves.generate(500, 42);
+        let mut previous_close = 100.0;
+
+        for bar in &data {
+            assert!(bar.price > 0.0);
+            assert!(bar.volume > 0.0);
+            assert!(bar.high >= bar.price.max(previous_close));
+            assert!(bar.low <= bar.price.min(previous_close));
+            previous_close = bar.price;
+        }
+
+        for window in data.windows(2) {
+            assert_eq!(window[1].timestamp - window[0].timestamp, Duration::days(1));
+        }
+    }
+
+    #[test]
+    fn test_crash_and_sideways_scenarios() {
+        let crash = Scenario::Crash.generate(200, 3);
+        let before = crash[98].price;
+        let after = crash[100].price;
+        assert!(after < before * 0.7);
+
+        let sideways = Scenario::Sideways.generate(1000, 3);
+        let mean = sideways.iter().map(|bar| bar.price).sum::<f64>() / sideways.len() as f64;
+        assert!((mean - 100.0).abs() < 10.0);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod backtesting;
+pub mod data;
+//pub mod execution;
+pub mod strategies;
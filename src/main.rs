@@ -1,13 +1,36 @@
-mod backtesting;
-mod data;
-//mod execution;
-mod strategies;
-
-use backtesting::{BacktestResult, Backtester, StrategyMode};
 use chrono::{Duration, Utc};
-use data::{DataIngestion, DataProcessor};
 use dotenv::dotenv;
-//use execution::binance::BinanceExecutor;
+use quant_sol::backtesting::{
+    BacktestResult, Backtester, Constraint, EarlyStopping, ExcursionPoint, GeneticConfig,
+    MonteCarloConfig, MonteCarloReport, Objective, OptimizationReport, Optimizer, OptimizerError,
+    ParameterRange, SearchMethod, StrategyMode, WalkForwardConfig, WalkForwardReport,
+};
+use quant_sol::data::{DataError, DataIngestion, DataProcessor, MissingBarPolicy};
+//use quant_sol::execution::binance::BinanceExecutor;
+
+/// Reads the symbols to trade from the `TRADING_SYMBOL` environment variable.
+///
+/// Accepts a comma-separated list (e.g. `SOL,ETH,BTC`), keeping the first
+/// occurrence of repeated symbols, and falls back to `SOL` when the variable is
+/// unset or empty.
+fn trading_symbols() -> Vec<String> {
+    let mut symbols: Vec<String> = Vec::new();
+    for symbol in std::env::var("TRADING_SYMBOL")
+        .unwrap_or_default()
+        .split(',')
+    {
+        let symbol = symbol.trim().to_uppercase();
+        if !symbol.is_empty() && !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+
+    if symbols.is_empty() {
+        vec!["SOL".to_string()]
+    } else {
+        symbols
+    }
+}
 
 /// Monitors current market conditions for a specified cryptocurrency.
 ///
@@ -17,9 +40,10 @@ use dotenv::dotenv;
 /// # Arguments
 /// * `ingestion`: A reference to the `DataIngestion` instance for fetching market data
 /// * `processor`: A mutable reference to the `DataProcessor` for processing market data
+/// * `symbol`: The cryptocurrency symbol to monitor
 ///
 /// # Behavior
-/// - Fetches current market data for the SOL cryptocurrency
+/// - Fetches current market data for the given cryptocurrency
 /// - Processes the data and prints market status information
 /// - Displays timestamp, symbol, price, volume, RSI, moving averages, and volatility
 ///
@@ -28,9 +52,10 @@ use dotenv::dotenv;
 async fn monitor_current_market(
     ingestion: &DataIngestion,
     processor: &mut DataProcessor,
+    symbol: &str,
 ) -> anyhow::Result<()> {
-    println!("\nMonitoring current market conditions...");
-    let current_data = ingestion.fetch_crypto_data("SOL").await?;
+    println!("\nMonitoring current market conditions for {}...", symbol);
+    let current_data = ingestion.fetch_crypto_data(symbol).await?;
 
     if current_data.is_empty() {
         println!("No current market data available");
@@ -44,7 +69,10 @@ async fn monitor_current_market(
         println!("Time: {}", latest.raw_data.timestamp);
         println!("Symbol: {}", latest.raw_data.symbol);
         println!("Price: ${:.4}", latest.raw_data.price);
-        println!("Volume: {:.2} SOL", latest.raw_data.volume);
+        println!(
+            "Volume: {:.2} {}",
+            latest.raw_data.volume, latest.raw_data.symbol
+        );
 
         if let Some(rsi) = latest.rsi_14 {
             println!("RSI (14): {:.2}", rsi);
@@ -79,17 +107,153 @@ async fn monitor_current_market(
 /// - Maximum drawdown
 /// - Average win and loss
 /// - Largest win and loss trades
+/// - CAGR, Sortino and Calmar ratios, profit factor, expectancy, exposure,
+///   value at risk and the longest drawdown
+/// - Benchmark return, alpha, beta, information ratio and up/down capture
+/// - The deepest drawdown periods with their start, trough, recovery, depth and duration
+/// - Average trade excursions and holding period, and trades per exit reason
+/// - The latest rolling Sharpe ratio, volatility and drawdown of each window
 fn print_backtest_results(results: &BacktestResult, strategy_name: &str) {
     println!("\n{} Results:", strategy_name);
     println!("Total Trades: {}", results.total_trades);
     println!("Win Rate: {:.2}%", results.win_rate * 100.0);
     println!("Total PnL: ${:.2}", results.total_pnl);
+    println!("Final Equity: ${:.2}", results.final_equity);
+    println!("Unrealized PnL: ${:.2}", results.unrealized_pnl);
+    println!("Commissions: ${:.2}", results.total_commission);
+    println!("Slippage: ${:.2}", results.total_slippage);
+    println!("Funding: ${:.2}", results.total_funding);
+    println!("Carry Costs: ${:.2}", results.total_carry);
     println!("Sharpe Ratio: {:.2}", results.sharpe_ratio);
     println!("Max Drawdown: {:.2}%", results.max_drawdown * 100.0);
     println!("Average Win: ${:.2}", results.average_win);
     println!("Average Loss: ${:.2}", results.average_loss);
     println!("Largest Win: ${:.2}", results.largest_win);
     println!("Largest Loss: ${:.2}", results.largest_loss);
+    println!("CAGR: {:.2}%", results.metrics.cagr * 100.0);
+    println!("Sortino Ratio: {:.2}", results.metrics.sortino_ratio);
+    println!("Calmar Ratio: {:.2}", results.metrics.calmar_ratio);
+    println!("Profit Factor: {:.2}", results.metrics.profit_factor);
+    println!("Expectancy: ${:.2}", results.metrics.expectancy);
+    println!("Exposure: {:.2}%", results.metrics.exposure * 100.0);
+    println!(
+        "VaR/CVaR (95%): {:.2}% / {:.2}%",
+        results.metrics.value_at_risk * 100.0,
+        results.metrics.conditional_value_at_risk * 100.0
+    );
+    println!(
+        "Longest Drawdown: {} bars",
+        results.metrics.longest_drawdown_bars
+    );
+    if let Some(benchmark) = &results.benchmark {
+        println!(
+            "Benchmark Return: {:.2}%",
+            benchmark.benchmark_return * 100.0
+        );
+        println!(
+            "Alpha/Beta: {:.2}% / {:.2}",
+            benchmark.alpha * 100.0,
+            benchmark.beta
+        );
+        println!(
+            "Information Ratio: {:.2} (Tracking Error {:.2}%)",
+            benchmark.information_ratio,
+            benchmark.tracking_error * 100.0
+        );
+        println!(
+            "Up/Down Capture: {:.2} / {:.2}",
+            benchmark.up_capture, benchmark.down_capture
+        );
+    }
+    let analysis = &results.trade_analysis;
+    if !analysis.excursions.is_empty() {
+        let count = analysis.excursions.len() as f64;
+        let average = |value: fn(&ExcursionPoint) -> f64| {
+            analysis.excursions.iter().map(value).sum::<f64>() / count
+        };
+        println!(
+            "Average MAE/MFE: {:.2}% / {:.2}%",
+            average(|point| point.mae) * 100.0,
+            average(|point| point.mfe) * 100.0
+        );
+        println!(
+            "Average Bars Held: {:.1}",
+            average(|point| point.bars_held as f64)
+        );
+        println!(
+            "Largest Winner MAE: {:.2}%",
+            analysis.max_winner_mae() * 100.0
+        );
+        for (reason, trades) in &analysis.exit_reasons {
+            println!("  Exits by {:?}: {}", reason, trades);
+        }
+    }
+    for rolling in &results.rolling {
+        if let Some(latest) = rolling.points.last() {
+            println!(
+                "Rolling {}-bar Sharpe/Volatility/Drawdown: {:.2} / {:.2}% / {:.2}%",
+                rolling.window,
+                latest.sharpe_ratio,
+                latest.volatility * 100.0,
+                latest.max_drawdown * 100.0
+            );
+        }
+    }
+    if !results.drawdown_periods.is_empty() {
+        println!("Top Drawdowns:");
+        for period in &results.drawdown_periods {
+            let recovery = period.recovery.map_or_else(
+                || "not recovered".to_string(),
+                |date| date.date_naive().to_string(),
+            );
+            println!(
+                "  {:.2}% from {} to {} (trough {}), {} bars / {:.1} days",
+                period.depth * 100.0,
+                period.start.date_naive(),
+                recovery,
+                period.trough.date_naive(),
+                period.duration_bars,
+                period.duration_days
+            );
+        }
+    }
+}
+
+/// Prints the spread of outcomes from a Monte Carlo analysis of a backtest.
+///
+/// # Arguments
+/// * `report`: A reference to the `MonteCarloReport` of the backtest
+/// * `strategy_name`: Name of the strategy being evaluated
+///
+/// # Metrics Displayed
+/// - Median final equity and maximum drawdown with their confidence intervals
+/// - Risk of ruin with its confidence interval
+fn print_monte_carlo(report: &MonteCarloReport, strategy_name: &str) {
+    if report.trades == 0 {
+        return;
+    }
+    println!(
+        "\n{} Monte Carlo ({} simulations, {:.0}% intervals):",
+        strategy_name,
+        report.simulations,
+        report.confidence * 100.0
+    );
+    println!(
+        "  Final Equity: ${:.2} (${:.2} to ${:.2})",
+        report.final_equity.estimate, report.final_equity.lower, report.final_equity.upper
+    );
+    println!(
+        "  Max Drawdown: {:.2}% ({:.2}% to {:.2}%)",
+        report.max_drawdown.estimate * 100.0,
+        report.max_drawdown.lower * 100.0,
+        report.max_drawdown.upper * 100.0
+    );
+    println!(
+        "  Risk of Ruin: {:.2}% ({:.2}% to {:.2}%)",
+        report.risk_of_ruin.estimate * 100.0,
+        report.risk_of_ruin.lower * 100.0,
+        report.risk_of_ruin.upper * 100.0
+    );
 }
 
 /// Compares backtest results between individual and combined trading strategies.
@@ -133,25 +297,135 @@ fn compare_results(individual: &BacktestResult, combined: &BacktestResult) {
     );
 }
 
+/// Prints the best parameter sets of an optimization run.
+///
+/// # Arguments
+/// * `report`: A reference to the ranked `OptimizationReport`
+/// * `title`: Name of the search being reported
+/// * `top`: Number of parameter sets to print
+fn print_optimization(report: &OptimizationReport, title: &str, top: usize) {
+    println!("\n{} (by {:?}):", title, report.objective);
+    for trial in report.trials.iter().take(top) {
+        let parameters: Vec<String> = report
+            .parameters
+            .iter()
+            .zip(&trial.parameters)
+            .map(|(name, value)| format!("{}={:.2}", name, value))
+            .collect();
+        println!(
+            "  #{} {}: objective {:.2}, return {:.2}%, max drawdown {:.2}%, {} trades",
+            trial.rank,
+            parameters.join(" "),
+            trial.objective,
+            trial.total_return * 100.0,
+            trial.max_drawdown * 100.0,
+            trial.total_trades
+        );
+    }
+    if !report.convergence.is_empty() {
+        println!(
+            "  {} generations{}, {} distinct trials",
+            report.convergence.len(),
+            if report.stopped_early {
+                " (stopped early)"
+            } else {
+                ""
+            },
+            report.trials.len()
+        );
+    }
+    if let Some(overfitting) = &report.overfitting {
+        println!(
+            "  Overfitting: {:?} (deflated Sharpe {:.2}, probabilistic Sharpe {:.2}, PBO {}, {} trials)",
+            overfitting.verdict,
+            overfitting.deflated_sharpe,
+            overfitting.probabilistic_sharpe,
+            overfitting
+                .pbo
+                .map_or_else(|| "n/a".to_string(), |pbo| format!("{:.2}", pbo)),
+            overfitting.trials
+        );
+        if let Some(length) = overfitting.min_backtest_length {
+            println!(
+                "  Minimum Backtest Length: {:.0} bars ({} tested)",
+                length, overfitting.observations
+            );
+        }
+    }
+}
+
+/// Prints the folds and summary of a walk-forward analysis.
+///
+/// Shows the parameters chosen for each out-of-sample window with the
+/// in-sample and out-of-sample returns they produced, the performance of the
+/// stitched out-of-sample curve, the walk-forward efficiency and how much each
+/// parameter moved between folds.
+///
+/// # Arguments
+/// * `report`: A reference to the `WalkForwardReport` to print
+/// * `title`: Name of the analysis being reported
+fn print_walk_forward(report: &WalkForwardReport, title: &str) {
+    println!("\n{}:", title);
+    for fold in &report.folds {
+        let parameters: Vec<String> = report
+            .parameters
+            .iter()
+            .zip(&fold.parameters)
+            .map(|(name, value)| format!("{}={:.2}", name, value))
+            .collect();
+        println!(
+            "  {} -> {}: {}, in-sample {:.2}%, out-of-sample {:.2}% ({} trades)",
+            fold.out_of_sample_start.format("%Y-%m-%d"),
+            fold.out_of_sample_end.format("%Y-%m-%d"),
+            parameters.join(" "),
+            fold.in_sample_return * 100.0,
+            fold.out_of_sample_return * 100.0,
+            fold.out_of_sample_trades
+        );
+    }
+    println!(
+        "  Stitched CAGR: {:.2}%, Sharpe {:.2}, Max Drawdown {:.2}%",
+        report.metrics.cagr * 100.0,
+        report.metrics.sharpe_ratio,
+        report.metrics.max_drawdown * 100.0
+    );
+    println!("  Walk-Forward Efficiency: {:.2}", report.efficiency);
+    for stability in &report.parameter_stability {
+        println!(
+            "  {}: mean {:.2}, std dev {:.2}, range {:.2}..{:.2}",
+            stability.name, stability.mean, stability.std_dev, stability.min, stability.max
+        );
+    }
+}
+
 /// Main application entry point for the quantitative trading solution.
 ///
 /// This function orchestrates the entire trading analysis workflow:
 /// 1. Initialize logging and environment variables
 /// 2. Create data ingestion and processing instances
 /// 3. Monitor current market conditions
-/// 4. Fetch historical market data
+/// 4. Fetch historical market data for every configured symbol
 /// 5. Run backtests for individual and combined trading strategies
 /// 6. Print and compare backtest results
+/// 7. Sweep the RSI thresholds and print the best parameter sets
+/// 8. Walk the RSI thresholds forward to check they hold out of sample
+/// 9. Evolve the Bollinger Bands parameters with a genetic algorithm
 ///
 /// # Workflow Steps
 /// - Load environment variables from .env file
-/// - Fetch and process current market data
-/// - Retrieve historical market data for the past 180 days
-/// - Run backtests for:
+/// - Read the symbols to trade from `TRADING_SYMBOL`
+/// - Fetch and process current market data for the first symbol
+/// - Retrieve an aligned panel of historical market data for the past 180 days
+/// - Run backtests per symbol for:
 ///   * RSI Strategy
 ///   * Bollinger Bands Strategy
 ///   * Combined Strategy
 /// - Compare and display performance metrics
+/// - Run a Monte Carlo analysis of each strategy's trades
+/// - Grid-search the RSI oversold and overbought thresholds, with overfitting statistics
+/// - Walk the RSI threshold search forward over rolling in-sample/out-of-sample windows
+/// - Search the Bollinger Bands period, width, trend and exit parameters with a
+///   genetic algorithm
 ///
 /// # Returns
 /// Returns `Ok(())` if all operations complete successfully, 
@@ -164,47 +438,146 @@ async fn main() -> anyhow::Result<()> {
     // Load environment variables from .env file
     dotenv().ok();
 
+    let symbols = trading_symbols();
+
     // Create data ingestion instance
-    let ingestion = DataIngestion::new()?;
+    let ingestion = match DataIngestion::new() {
+        Ok(ingestion) => ingestion,
+        Err(DataError::MissingCredentials { variable }) => {
+            anyhow::bail!(
+                "{} is not set; add it to your environment or .env file",
+                variable
+            );
+        }
+        Err(e) => return Err(e.into()),
+    };
 
     // Create data processor with increased history capacity for better indicator calculations
     let mut processor = DataProcessor::new(500);
 
-    // Monitor current market conditions
-    monitor_current_market(&ingestion, &mut processor).await?;
+    // Monitor current market conditions; a throttled snapshot should not block the backtests
+    if let Err(e) = monitor_current_market(&ingestion, &mut processor, &symbols[0]).await {
+        match e.downcast_ref::<DataError>() {
+            Some(DataError::RateLimited { message, .. }) => {
+                tracing::warn!(%message, "skipping market snapshot: rate limited");
+            }
+            _ => return Err(e),
+        }
+    }
 
     // Fetch historical data for backtesting
     let end_date = Utc::now();
     let start_date = end_date - Duration::days(180);
 
-    let historical_data = ingestion
-        .fetch_historical_crypto_data("SOL", start_date, end_date)
-        .await?;
+    let panel = match ingestion
+        .fetch_historical_panel(&symbols, start_date, end_date, MissingBarPolicy::Keep)
+        .await
+    {
+        Ok(panel) => panel,
+        Err(e) => match e.root_cause() {
+            DataError::RateLimited { .. } => anyhow::bail!(
+                "{}; lower ALPHA_VANTAGE_REQUESTS_PER_MINUTE or try again later",
+                e
+            ),
+            DataError::Provider { .. } | DataError::NoData { .. } => anyhow::bail!(
+                "{}; check that TRADING_SYMBOL only lists symbols supported by the provider",
+                e
+            ),
+            _ => return Err(e.into()),
+        },
+    };
 
-    let processed_data = processor.process_batch(historical_data)?;
+    for symbol in panel.symbols() {
+        let missing = panel.missing_count(symbol);
+        if missing > 0 {
+            println!(
+                "\n{}: {} of {} bars missing from the aligned panel",
+                symbol,
+                missing,
+                panel.len()
+            );
+        }
 
-    // Create backtester with initial settings
-    let mut backtester = Backtester::new(
-        10000.0, 
-        500.0,   
-        0.001,   
-    );
+        // Indicators are path dependent, so every symbol gets its own processor
+        let mut processor = DataProcessor::new(500);
+        let processed_data = processor.process_batch(panel.observed_series(symbol))?;
+
+        // Create backtester with initial settings
+        let mut backtester = Backtester::new(10000.0, 500.0, 0.001);
+
+        // Run backtest with individual strategies
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let rsi_results = backtester.run_backtest(&processed_data);
+        print_backtest_results(&rsi_results, &format!("{} RSI Strategy", symbol));
 
-    // Run backtest with individual strategies
-    backtester.set_strategy_mode(StrategyMode::Rsi);
-    let rsi_results = backtester.run_backtest(&processed_data);
-    print_backtest_results(&rsi_results, "RSI Strategy");
+        backtester.set_strategy_mode(StrategyMode::BollingerBands);
+        let bb_results = backtester.run_backtest(&processed_data);
+        print_backtest_results(&bb_results, &format!("{} Bollinger Bands Strategy", symbol));
 
-    backtester.set_strategy_mode(StrategyMode::BollingerBands);
-    let bb_results = backtester.run_backtest(&processed_data);
-    print_backtest_results(&bb_results, "Bollinger Bands Strategy");
+        backtester.set_strategy_mode(StrategyMode::Combined);
+        let combined_results = backtester.run_backtest(&processed_data);
+        print_backtest_results(&combined_results, &format!("{} Combined Strategy", symbol));
 
-    backtester.set_strategy_mode(StrategyMode::Combined);
-    let combined_results = backtester.run_backtest(&processed_data);
-    print_backtest_results(&combined_results, "Combined Strategy");
+        compare_results(&rsi_results, &combined_results);
+        compare_results(&bb_results, &combined_results);
 
-    compare_results(&rsi_results, &combined_results);
-    compare_results(&bb_results, &combined_results);
+        // Reshuffle, resample and perturb each strategy's trades
+        let monte_carlo = MonteCarloConfig::default();
+        for (results, name) in [
+            (&rsi_results, "RSI Strategy"),
+            (&bb_results, "Bollinger Bands Strategy"),
+            (&combined_results, "Combined Strategy"),
+        ] {
+            let report = results.monte_carlo(&monte_carlo);
+            print_monte_carlo(&report, &format!("{} {}", symbol, name));
+        }
+
+        // Sweep the RSI thresholds instead of hand-editing them
+        backtester.set_strategy_mode(StrategyMode::Rsi);
+        let mut optimizer = Optimizer::new(backtester, Objective::SharpeRatio);
+        optimizer.add_parameter(ParameterRange::new("rsi_oversold", 25.0, 45.0, 5));
+        optimizer.add_parameter(ParameterRange::new("rsi_overbought", 55.0, 75.0, 5));
+        let report = optimizer.run(&processed_data)?;
+        print_optimization(&report, &format!("{} RSI Threshold Sweep", symbol), 5);
+
+        // A short history should not end the run for the remaining symbols
+        match optimizer.walk_forward(&processed_data, &WalkForwardConfig::default()) {
+            Ok(walk_forward) => {
+                print_walk_forward(&walk_forward, &format!("{} RSI Walk-Forward", symbol))
+            }
+            Err(OptimizerError::InsufficientData { bars, required }) => {
+                tracing::warn!(%symbol, bars, required, "skipping walk-forward: not enough bars");
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        // Too many Bollinger parameters for a grid; evolve them instead
+        let mut backtester = optimizer.backtester().clone();
+        backtester.set_strategy_mode(StrategyMode::BollingerBands);
+        let mut optimizer = Optimizer::new(backtester, Objective::SharpeRatio);
+        optimizer.add_parameter(ParameterRange::integer("bollinger_period", 10, 40));
+        optimizer.add_parameter(ParameterRange::new("bollinger_std_dev", 1.0, 3.0, 9));
+        optimizer.add_parameter(ParameterRange::integer("bollinger_trend_fast", 3, 20));
+        optimizer.add_parameter(ParameterRange::integer("bollinger_trend_slow", 10, 60));
+        optimizer.add_parameter(ParameterRange::new("bollinger_exit_fraction", 0.5, 1.0, 6));
+        optimizer.add_constraint(Constraint::LessThan {
+            lower: "bollinger_trend_fast".to_string(),
+            upper: "bollinger_trend_slow".to_string(),
+        });
+        optimizer.set_search_method(SearchMethod::Genetic(GeneticConfig::default()));
+        optimizer.set_early_stopping(Some(EarlyStopping::default()));
+        let report = optimizer.run(&processed_data)?;
+        print_optimization(&report, &format!("{} Bollinger Genetic Search", symbol), 5);
+    }
+
+    let http_metrics = ingestion.http_metrics();
+    tracing::info!(
+        requests = http_metrics.requests,
+        retries = http_metrics.retries,
+        throttled = http_metrics.throttled,
+        limiter_wait_ms = http_metrics.limiter_wait_ms,
+        "data ingestion finished"
+    );
 
     Ok(())
 }
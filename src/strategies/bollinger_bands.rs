@@ -21,6 +21,10 @@ use std::collections::VecDeque;
 /// * `position_open`: Flag indicating if a trading position is currently open
 /// * `current_position`: Current trading position (long or short)
 /// * `trend_ma_period`: Period used for trend detection moving average
+/// * `trend_fast_period`: Period of the fast moving average in trend detection
+/// * `trend_slow_period`: Period of the slow moving average in trend detection
+/// * `exit_band_fraction`: Fraction of the distance from the middle to the
+///   outer band at which a position is exited in a trending market
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BollingerBands {
     pub period: usize,
@@ -30,6 +34,9 @@ pub struct BollingerBands {
     pub position_open: bool,
     pub current_position: Option<Position>,
     pub trend_ma_period: usize, // For trend detection
+    pub trend_fast_period: usize,
+    pub trend_slow_period: usize,
+    pub exit_band_fraction: f64,
 }
 
 /// Represents the possible trading positions in the Bollinger Bands strategy.
@@ -96,6 +103,8 @@ impl Default for BollingerBands {
     /// * `period`: 20 periods
     /// * `std_dev_multiplier`: 1.8 (more sensitive than standard 2.0)
     /// * `trend_ma_period`: 30 periods (more responsive trend detection)
+    /// * `trend_fast_period`, `trend_slow_period`: 8 and 15 periods
+    /// * `exit_band_fraction`: 0.8
     fn default() -> Self {
         Self {
             period: 20,
@@ -105,6 +114,9 @@ impl Default for BollingerBands {
             position_open: false,
             current_position: None,
             trend_ma_period: 30, // Reduced from 50 to be more responsive
+            trend_fast_period: 8,
+            trend_slow_period: 15,
+            exit_band_fraction: 0.8,
         }
     }
 }
@@ -127,6 +139,9 @@ impl BollingerBands {
             position_open: false,
             current_position: None,
             trend_ma_period: 30,
+            trend_fast_period: 8,
+            trend_slow_period: 15,
+            exit_band_fraction: 0.8,
         }
     }
 
@@ -170,27 +185,28 @@ impl BollingerBands {
 
     /// Detects the current market trend using moving average crossover.
     ///
-    /// Uses 8-period and 15-period moving averages to determine trend direction.
+    /// Uses the fast and slow moving averages (8 and 15 periods by default)
+    /// to determine trend direction.
     ///
     /// # Returns
     /// An `Option` containing the detected trend (`Position::Long` or `Position::Short`),
     /// or `None` if trend cannot be determined
     fn detect_trend(&self) -> Option<Position> {
-        if self.price_history.len() < 15 {
+        let (fast, slow) = (self.trend_fast_period.max(1), self.trend_slow_period.max(1));
+        if self.price_history.len() < fast.max(slow) {
             return None;
         }
 
         let prices: Vec<f64> = self.price_history.iter().copied().collect();
         let current_price = prices[0];
 
-        // Calculate 8-period and 15-period MAs for faster trend detection
-        let ma_8: f64 = prices.iter().take(8).sum::<f64>() / 8.0;
-        let ma_15: f64 = prices.iter().take(15).sum::<f64>() / 15.0;
+        let ma_fast: f64 = prices.iter().take(fast).sum::<f64>() / fast as f64;
+        let ma_slow: f64 = prices.iter().take(slow).sum::<f64>() / slow as f64;
 
         // Determine trend based on MA crossover and price position
-        if ma_8 > ma_15 && current_price > ma_8 {
+        if ma_fast > ma_slow && current_price > ma_fast {
             Some(Position::Long)
-        } else if ma_8 < ma_15 && current_price < ma_8 {
+        } else if ma_fast < ma_slow && current_price < ma_fast {
             Some(Position::Short)
         } else {
             None
@@ -210,7 +226,10 @@ impl BollingerBands {
     pub fn analyze(&mut self, market_data: &ProcessedMarketData) -> BollingerSignal {
         // Update price history
         self.price_history.push_front(market_data.raw_data.price);
-        if self.price_history.len() > self.period * 2 {
+        let capacity = (self.period * 2)
+            .max(self.trend_fast_period)
+            .max(self.trend_slow_period);
+        if self.price_history.len() > capacity {
             self.price_history.pop_back();
         }
 
@@ -246,7 +265,7 @@ impl BollingerBands {
             Some(Position::Long) => {
                 if current_price <= lower {
                     SignalType::Buy
-                } else if current_price >= middle + (upper - middle) * 0.8 {
+                } else if current_price >= middle + (upper - middle) * self.exit_band_fraction {
                     SignalType::Sell
                 } else {
                     SignalType::Hold
@@ -255,7 +274,7 @@ impl BollingerBands {
             Some(Position::Short) => {
                 if current_price >= upper {
                     SignalType::Sell
-                } else if current_price <= middle - (middle - lower) * 0.8 {
+                } else if current_price <= middle - (middle - lower) * self.exit_band_fraction {
                     SignalType::Buy
                 } else {
                     SignalType::Hold
@@ -312,6 +331,7 @@ mod tests {
                 volume: 1000.0,
                 high: price + 1.0,
                 low: price - 1.0,
+                open: price,
             },
             moving_average_5: Some(price),
             moving_average_20: Some(price),
@@ -326,7 +346,7 @@ mod tests {
         let mut strategy = BollingerBands::new(5, 2.0);
 
         // Add a series of prices
-        let prices = vec![100.0, 101.0, 99.0, 102.0, 98.0];
+        let prices = [100.0, 101.0, 99.0, 102.0, 98.0];
         for price in prices {
             let data = create_test_data(price);
             strategy.analyze(&data);
@@ -263,6 +263,7 @@ mod tests {
                 volume: 1000.0,
                 high: price + 1.0,
                 low: price - 1.0,
+                open: price,
             },
             moving_average_5: Some(price),
             moving_average_20: Some(price),
@@ -302,6 +303,6 @@ mod tests {
 
         assert!(matches!(signal.signal_type, SignalType::Hold));
         assert!(!strategy.position_open);
-        assert!(matches!(strategy.current_position, None));
+        assert!(strategy.current_position.is_none());
     }
 }